@@ -0,0 +1,309 @@
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+
+const DEFAULT_LIMIT: usize = 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub timestamp: String,
+    pub session: String,
+    pub source: String,
+    pub tool: String,
+    pub arguments: serde_json::Value,
+    pub status: String,
+    pub duration_ms: u64,
+    pub bytes_in: usize,
+    pub bytes_out: usize,
+    /// The user who approved the call, `declined`, or `auto` when nobody
+    /// was asked
+    pub approved_by: String,
+}
+
+impl AuditRecord {
+    pub fn new(
+        source: &str,
+        tool: &str,
+        arguments: &serde_json::Value,
+        success: bool,
+        duration: Duration,
+        bytes_out: usize,
+    ) -> Self {
+        Self {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            session: session(),
+            source: source.to_string(),
+            tool: tool.to_string(),
+            arguments: arguments.clone(),
+            status: if success { "success" } else { "error" }.to_string(),
+            duration_ms: duration.as_millis() as u64,
+            bytes_in: arguments.to_string().len(),
+            bytes_out,
+            approved_by: "auto".to_string(),
+        }
+    }
+
+    pub fn display(&self) -> String {
+        let mut args = self.arguments.to_string();
+        if args.chars().count() > 60 {
+            args = format!("{}…", args.chars().take(60).collect::<String>());
+        }
+        let time = chrono::DateTime::parse_from_rfc3339(&self.timestamp)
+            .map(|t| {
+                t.with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string()
+            })
+            .unwrap_or_else(|_| self.timestamp.clone());
+        format!(
+            "{} [{}] {} ({}) {} {}ms in:{}B out:{}B approval:{} {}",
+            time,
+            self.session,
+            self.tool,
+            self.source,
+            self.status,
+            self.duration_ms,
+            self.bytes_in,
+            self.bytes_out,
+            self.approved_by,
+            args
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct AuditFilter {
+    pub tool: Option<String>,
+    pub status: Option<String>,
+    pub session: Option<String>,
+    pub source: Option<String>,
+    pub limit: usize,
+}
+
+impl AuditFilter {
+    /// Parse `/audit` arguments: an optional count and `key=value` filters
+    pub fn parse(args: &[&str]) -> Result<Self, String> {
+        let mut filter = AuditFilter {
+            limit: DEFAULT_LIMIT,
+            ..Default::default()
+        };
+
+        for arg in args {
+            if let Ok(limit) = arg.parse::<usize>() {
+                filter.limit = limit;
+                continue;
+            }
+            match arg.split_once('=') {
+                Some(("tool", v)) => filter.tool = Some(v.to_string()),
+                Some(("status", v)) => filter.status = Some(v.to_string()),
+                Some(("session", v)) => filter.session = Some(v.to_string()),
+                Some(("source", v)) => filter.source = Some(v.to_string()),
+                Some(("limit", v)) => {
+                    filter.limit = v.parse().map_err(|_| format!("Invalid limit: {}", v))?
+                }
+                _ => return Err(format!("Unknown filter: {}", arg)),
+            }
+        }
+
+        Ok(filter)
+    }
+
+    pub fn matches(&self, record: &AuditRecord) -> bool {
+        self.tool.as_ref().is_none_or(|t| &record.tool == t)
+            && self.status.as_ref().is_none_or(|s| &record.status == s)
+            && self.session.as_ref().is_none_or(|s| &record.session == s)
+            && self
+                .source
+                .as_ref()
+                .is_none_or(|s| record.source.starts_with(s.as_str()))
+    }
+}
+
+fn session_slot() -> &'static Mutex<String> {
+    static SESSION: OnceLock<Mutex<String>> = OnceLock::new();
+    SESSION.get_or_init(|| Mutex::new("default".to_string()))
+}
+
+/// Record the chat session name so every audit entry can be attributed to it
+pub fn set_session(name: &str) {
+    *session_slot().lock().unwrap() = name.to_string();
+}
+
+pub fn session() -> String {
+    session_slot().lock().unwrap().clone()
+}
+
+pub fn current_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+tokio::task_local! {
+    /// The user's answer for the tool call being run, once they were asked
+    static APPROVAL: Cell<Option<bool>>;
+}
+
+/// Note the user's answer for the tool call being run, for its audit record
+pub fn note_approval(approved: bool) {
+    let _ = APPROVAL.try_with(|a| a.set(Some(approved)));
+}
+
+/// Run a tool call and say who approved it, for `AuditRecord::approved_by`
+pub async fn with_approval<F: Future>(run: F) -> (F::Output, String) {
+    APPROVAL
+        .scope(Cell::new(None), async move {
+            let output = run.await;
+            let approved_by = match APPROVAL.with(|a| a.get()) {
+                Some(true) => current_user(),
+                Some(false) => "declined".to_string(),
+                None => "auto".to_string(),
+            };
+            (output, approved_by)
+        })
+        .await
+}
+
+fn dir_slot() -> &'static Mutex<Option<PathBuf>> {
+    static DIR: OnceLock<Mutex<Option<PathBuf>>> = OnceLock::new();
+    DIR.get_or_init(|| Mutex::new(None))
+}
+
+/// Write audit records under `dir` instead of `.wiseowlcli/audit`
+pub fn set_dir(dir: PathBuf) {
+    *dir_slot().lock().unwrap() = Some(dir);
+}
+
+/// Keep the records of tests that run tools out of the working tree
+#[cfg(test)]
+pub(crate) fn use_test_dir() {
+    set_dir(std::env::temp_dir().join(format!("wiseowl_audit_{}", std::process::id())));
+}
+
+fn audit_dir() -> std::io::Result<PathBuf> {
+    if let Some(dir) = dir_slot().lock().unwrap().clone() {
+        return Ok(dir);
+    }
+    Ok(std::env::current_dir()?.join(".wiseowlcli").join("audit"))
+}
+
+/// Append one record to today's audit file. Failures are reported but never
+/// interrupt the tool call being audited.
+pub async fn record(entry: AuditRecord) {
+    if let Err(e) = append(&entry).await {
+        eprintln!("⚠️  Failed to write audit log: {}", e);
+    }
+}
+
+async fn append(entry: &AuditRecord) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = audit_dir()?;
+    tokio::fs::create_dir_all(&dir).await?;
+    let file = dir.join(format!("{}.jsonl", chrono::Local::now().format("%Y-%m-%d")));
+
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+
+    let mut handle = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(file)
+        .await?;
+    handle.write_all(line.as_bytes()).await?;
+    Ok(())
+}
+
+/// Most recent matching records, newest last
+pub async fn recent(filter: &AuditFilter) -> Result<Vec<AuditRecord>, Box<dyn std::error::Error>> {
+    let dir = audit_dir()?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut files: Vec<PathBuf> = std::fs::read_dir(&dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "jsonl"))
+        .collect();
+    // Daily files sort chronologically by name
+    files.sort();
+
+    let mut records = Vec::new();
+    for file in files.iter().rev() {
+        let content = tokio::fs::read_to_string(file).await?;
+        let mut day: Vec<AuditRecord> = content
+            .lines()
+            .filter_map(|l| serde_json::from_str::<AuditRecord>(l).ok())
+            .filter(|r| filter.matches(r))
+            .collect();
+        day.append(&mut records);
+        records = day;
+        if records.len() >= filter.limit {
+            break;
+        }
+    }
+
+    let skip = records.len().saturating_sub(filter.limit);
+    Ok(records.into_iter().skip(skip).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(tool: &str, success: bool) -> AuditRecord {
+        AuditRecord::new(
+            "builtin",
+            tool,
+            &serde_json::json!({"path": "src/main.rs"}),
+            success,
+            Duration::from_millis(12),
+            42,
+        )
+    }
+
+    #[test]
+    fn test_record_fields() {
+        let record = sample("read_file", true);
+        assert_eq!(record.status, "success");
+        assert_eq!(record.duration_ms, 12);
+        assert_eq!(record.bytes_out, 42);
+        assert_eq!(record.bytes_in, r#"{"path":"src/main.rs"}"#.len());
+    }
+
+    #[tokio::test]
+    async fn test_approval_is_recorded_per_call() {
+        let (_, nobody) = with_approval(async {}).await;
+        assert_eq!(nobody, "auto");
+        let (_, declined) = with_approval(async { note_approval(false) }).await;
+        assert_eq!(declined, "declined");
+        let ((_, inner), outer) = with_approval(async {
+            note_approval(true);
+            with_approval(async {}).await
+        })
+        .await;
+        assert_eq!(inner, "auto");
+        assert_eq!(outer, current_user());
+        assert_eq!(sample("read_file", true).approved_by, "auto");
+    }
+
+    #[test]
+    fn test_filter_parse() {
+        let filter = AuditFilter::parse(&["5", "tool=execute_bash", "status=error"]).unwrap();
+        assert_eq!(filter.limit, 5);
+        assert_eq!(filter.tool.as_deref(), Some("execute_bash"));
+        assert_eq!(filter.status.as_deref(), Some("error"));
+        assert!(AuditFilter::parse(&["bogus"]).is_err());
+    }
+
+    #[test]
+    fn test_filter_matches() {
+        let filter = AuditFilter::parse(&["tool=read_file", "status=error"]).unwrap();
+        assert!(filter.matches(&sample("read_file", false)));
+        assert!(!filter.matches(&sample("read_file", true)));
+        assert!(!filter.matches(&sample("write_file", false)));
+    }
+}
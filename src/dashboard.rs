@@ -8,6 +8,7 @@ pub struct DashboardStats {
     pub turn_count: usize,
     pub start_time: Instant,
     pub activity_log: Vec<String>,
+    pub tool_timings: Vec<(String, f64)>,
 }
 
 impl DashboardStats {
@@ -19,6 +20,7 @@ impl DashboardStats {
             turn_count: 0,
             start_time: Instant::now(),
             activity_log: Vec::new(),
+            tool_timings: Vec::new(),
         }
     }
 
@@ -37,6 +39,14 @@ impl DashboardStats {
         }
     }
 
+    pub fn add_tool_timing(&mut self, tool: &str, duration: Duration) {
+        self.tool_timings
+            .push((tool.to_string(), duration.as_secs_f64() * 1000.0));
+        if self.tool_timings.len() > 10 {
+            self.tool_timings.remove(0);
+        }
+    }
+
     pub fn update_memory(&mut self) {
         let mut sys = System::new_all();
         sys.refresh_all();
@@ -119,6 +129,16 @@ mod tests {
         assert_eq!(stats.activity_log.len(), 10);
     }
 
+    #[test]
+    fn test_tool_timing_limit() {
+        let mut stats = DashboardStats::new();
+        for i in 0..12 {
+            stats.add_tool_timing("read_file", Duration::from_millis(i));
+        }
+        assert_eq!(stats.tool_timings.len(), 10);
+        assert_eq!(stats.tool_timings[0].1, 2.0);
+    }
+
     #[test]
     fn test_uptime() {
         let stats = DashboardStats::new();
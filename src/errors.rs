@@ -31,7 +31,10 @@ pub fn suggest_command(cmd: &str) -> Option<String> {
         "write",
         "preview",
         "apply",
+        "discard",
+        "dryrun",
         "rollback",
+        "test",
         "clear",
         "exit",
         "mcp",
@@ -40,6 +43,8 @@ pub fn suggest_command(cmd: &str) -> Option<String> {
         "stats",
         "monitor",
         "git",
+        "jobs",
+        "audit",
         "version",
     ];
 
@@ -1,11 +1,17 @@
+use std::path::Path;
 use std::process::Command;
 
 pub struct GitHelper;
 
 impl GitHelper {
     pub fn status() -> Result<String, String> {
+        Self::status_in(Path::new("."))
+    }
+
+    pub fn status_in(dir: &Path) -> Result<String, String> {
         let output = Command::new("git")
             .args(["status", "--short"])
+            .current_dir(dir)
             .output()
             .map_err(|e| format!("Git error: {}", e))?;
 
@@ -21,9 +27,66 @@ impl GitHelper {
         Ok(String::from_utf8_lossy(&output.stdout).to_string())
     }
 
+    /// `git diff` in `dir`, optionally of the index (`--cached`) and limited
+    /// to one path
+    pub fn diff_with(dir: &Path, staged: bool, path: Option<&str>) -> Result<String, String> {
+        let mut args = vec!["diff"];
+        if staged {
+            args.push("--cached");
+        }
+        if let Some(path) = path {
+            args.push("--");
+            args.push(path);
+        }
+        Self::run(dir, &args)
+    }
+
+    /// `git show` of a commit; `stat_only` lists changed files instead of the patch
+    pub fn show(dir: &Path, rev: &str, stat_only: bool) -> Result<String, String> {
+        if rev.starts_with('-') {
+            return Err(format!("Invalid revision: {}", rev));
+        }
+        let mut args = vec!["show", "--no-color"];
+        if stat_only {
+            args.push("--stat");
+        }
+        args.push(rev);
+        Self::run(dir, &args)
+    }
+
+    /// `git log --oneline`, optionally limited to commits touching `path`
+    pub fn log_path(dir: &Path, count: usize, path: Option<&str>) -> Result<String, String> {
+        let count = format!("-{}", count);
+        let mut args = vec!["log", count.as_str(), "--oneline"];
+        if let Some(path) = path {
+            args.push("--");
+            args.push(path);
+        }
+        Self::run(dir, &args)
+    }
+
+    fn run(dir: &Path, args: &[&str]) -> Result<String, String> {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .map_err(|e| format!("Git error: {}", e))?;
+
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        } else {
+            Err(String::from_utf8_lossy(&output.stderr).to_string())
+        }
+    }
+
     pub fn commit(message: &str) -> Result<String, String> {
+        Self::commit_in(Path::new("."), message)
+    }
+
+    pub fn commit_in(dir: &Path, message: &str) -> Result<String, String> {
         let output = Command::new("git")
             .args(["commit", "-am", message])
+            .current_dir(dir)
             .output()
             .map_err(|e| format!("Git error: {}", e))?;
 
@@ -43,3 +106,13 @@ impl GitHelper {
         Ok(String::from_utf8_lossy(&output.stdout).to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_show_rejects_option_revisions() {
+        assert!(GitHelper::show(Path::new("."), "--output=/tmp/x", false).is_err());
+    }
+}
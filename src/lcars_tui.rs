@@ -151,12 +151,15 @@ impl LcarsTUI {
             .block(Block::default().borders(Borders::ALL).title(" Memory "));
         f.render_widget(mem_widget, top_chunks[1]);
 
-        // MCP
-        let mcp_text = vec![
+        // MCP and recent tool call timings
+        let mut mcp_text = vec![
             Line::from(format!("✓ {} tools loaded", mcp_count)),
         ];
+        for (tool, ms) in stats.tool_timings.iter().rev() {
+            mcp_text.push(Line::from(format!("{} {:.0}ms", tool, ms)));
+        }
         let mcp_widget = Paragraph::new(mcp_text)
-            .block(Block::default().borders(Borders::ALL).title(" MCP Tools "));
+            .block(Block::default().borders(Borders::ALL).title(" Tools "));
         f.render_widget(mcp_widget, top_chunks[2]);
 
         // Activity log
@@ -1,9 +1,28 @@
+pub mod audit;
 pub mod mcp;
+pub mod mcp_config;
+pub mod mcp_http;
+pub mod mcp_arguments;
+pub mod mcp_sampling;
+pub mod mcp_server;
+pub mod mcp_supervisor;
 pub mod cache;
 pub mod streaming;
 pub mod validator;
 pub mod metrics;
 pub mod build_verifier;
 pub mod error_formatter;
+pub mod git;
 pub mod history;
 pub mod exporter;
+pub mod multi_file;
+pub mod outline;
+pub mod output_policy;
+pub mod patch;
+pub mod process;
+pub mod progress;
+pub mod search;
+pub mod test_runner;
+pub mod tool_parser;
+pub mod tools;
+pub mod wiseowl;
@@ -1,3 +1,4 @@
+mod audit;
 mod metrics;
 mod context;
 mod readline;
@@ -15,13 +16,27 @@ mod cache;
 mod git;
 mod lcars;
 pub mod mcp;
+mod mcp_arguments;
+mod mcp_config;
+mod mcp_http;
+mod mcp_sampling;
+mod mcp_server;
+mod mcp_supervisor;
 mod multi_file;
+mod outline;
+mod output_policy;
+mod patch;
 mod planning;
+mod process;
 mod prompts;
+mod search;
 mod stats;
 mod streaming;
+mod test_runner;
+mod tool_parser;
 mod tools;
 mod tui;
+mod validator;
 mod wiseowl;
 
 use clap::{Parser, Subcommand};
@@ -46,6 +61,10 @@ struct Args {
     #[arg(short = 'V', long)]
     version: bool,
 
+    /// Stage file writes and commands for review instead of applying them
+    #[arg(long)]
+    dry_run: bool,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -57,6 +76,8 @@ enum Commands {
         session: Option<String>,
     },
     Init,
+    /// Serve the built-in tools and WiseOwl memory as an MCP server over stdio
+    McpServe,
     Plan {
         goal: String,
     },
@@ -78,11 +99,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         Some(Commands::Init) => {
             init_project_mode(&client, &args.model).await?;
         }
+        Some(Commands::McpServe) => {
+            crate::mcp_server::serve_stdio(args.dry_run).await?;
+        }
         Some(Commands::Chat { session }) => {
-            chat_mode(&client, &args.model, session.as_deref()).await?;
+            chat_mode(&client, &args.model, session.as_deref(), args.dry_run).await?;
         }
         None => {
-            chat_mode(&client, &args.model, None).await?;
+            chat_mode(&client, &args.model, None, args.dry_run).await?;
         }
     }
 
@@ -93,9 +117,50 @@ async fn chat_mode(
     client: &Client,
     model: &str,
     session: Option<&str>,
+    dry_run: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    let result = chat_session(client, model, session, dry_run).await;
+    stop_background_work().await;
+    match result {
+        // The prompt's read may still be blocked on stdin, which would keep
+        // the runtime from shutting down
+        Ok(true) => std::process::exit(130),
+        Ok(false) => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Stop background processes and MCP servers; runs however the session ends
+async fn stop_background_work() {
+    let stopped = crate::process::processes().lock().await.shutdown_all().await;
+    if stopped > 0 {
+        println!("🛑 Stopped {} background process(es)", stopped);
+    }
+    let mcp_stopped = crate::mcp::session_client().await.lock().await.shutdown().await;
+    if mcp_stopped > 0 {
+        println!("🔌 Stopped {} MCP server(s)", mcp_stopped);
+    }
+}
+
+/// Ctrl+C that no MCP call claimed, SIGTERM, or the terminal closing
+async fn stop_requested() {
+    tokio::select! {
+        _ = crate::mcp::idle_interrupt() => {}
+        _ = crate::process::terminated() => {}
+    }
+}
+
+/// The chat loop; returns whether it was interrupted rather than exited
+async fn chat_session(
+    client: &Client,
+    model: &str,
+    session: Option<&str>,
+    mut dry_run: bool,
+) -> Result<bool, Box<dyn std::error::Error>> {
     let owl = crate::wiseowl::WiseOwl::init().await?;
     let session_name = session.unwrap_or("default");
+    crate::audit::set_session(session_name);
+    crate::mcp_sampling::set_model(model);
     let mut context = ConversationContext::load(session_name).await?;
     let mut editor = MultiFileEditor::new();
     // Dashboard stats
@@ -111,14 +176,16 @@ async fn chat_mode(
         )
     );
     println!("Type 'exit' or Ctrl+C to end");
+    if dry_run {
+        println!("🧪 Dry run: writes and commands are staged for /preview and /apply");
+    }
     // Show startup banner
-    let mut mcp_client = crate::mcp::MCPClient::new();
-    let mcp_count =
-        if mcp_client.load_config().await.is_ok() && mcp_client.discover_tools().await.is_ok() {
-            mcp_client.list_available_tools().len()
-        } else {
-            0
-        };
+    let mcp_count = crate::mcp::session_client()
+        .await
+        .lock()
+        .await
+        .list_available_tools()
+        .len();
 
     if mcp_count > 0 {
         println!("🔌 {} MCP tools available", mcp_count);
@@ -130,16 +197,25 @@ async fn chat_mode(
         println!("📜 Loaded {} messages", context.messages.len());
     }
 
+    let mut interrupted = false;
     loop {
         print!("You: ");
         io::stdout().flush()?;
 
-        let mut input = String::new();
-        match io::stdin().read_line(&mut input) {
-            Ok(0) => break,
-            Ok(_) => {}
-            Err(_) => break,
-        }
+        let line = tokio::task::spawn_blocking(|| {
+            let mut input = String::new();
+            io::stdin().read_line(&mut input).map(|read| (read, input))
+        });
+        let input = tokio::select! {
+            line = line => match line {
+                Ok(Ok((read, input))) if read > 0 => input,
+                _ => break,
+            },
+            _ = stop_requested() => {
+                interrupted = true;
+                break;
+            }
+        };
 
         let input = input.trim();
 
@@ -170,7 +246,17 @@ async fn chat_mode(
             ));
             continue;
         }
-            if !handle_slash_command(&owl, client, model, input, &mut context, &mut editor).await? {
+            if !handle_slash_command(
+                &owl,
+                client,
+                model,
+                input,
+                &mut context,
+                &mut editor,
+                &mut dry_run,
+            )
+            .await?
+            {
                 break;
             }
             continue;
@@ -191,8 +277,22 @@ async fn chat_mode(
         );
         let full_prompt = format!("{}\n\nUser: {}", system_prompt, input);
 
-        let response = stream_with_tools(client, model, &full_prompt).await?;
-        context.add_message("assistant".to_string(), response);
+        let staging = if dry_run { Some(&mut editor) } else { None };
+        let turn = tokio::select! {
+            turn = stream_with_tools(client, model, &full_prompt, staging) => turn?,
+            _ = stop_requested() => {
+                interrupted = true;
+                break;
+            }
+        };
+        context.add_message("assistant".to_string(), turn.response);
+        for (tool, elapsed) in &turn.tool_timings {
+            stats.add_tool_timing(tool, *elapsed);
+        }
+        if dry_run && editor.has_pending() {
+            println!("\n🧪 Dry run complete. {}", editor.show_preview());
+            println!("💡 /apply to run these changes, /discard to drop them");
+        }
         // Update stats after response
         stats.add_response_time(start.elapsed());
         stats.add_activity("AI: Response complete".to_string());
@@ -203,7 +303,11 @@ async fn chat_mode(
         }
     }
 
-    Ok(())
+    if interrupted {
+        context.save(session_name).await?;
+        println!("\n💾 Session saved. Goodbye!");
+    }
+    Ok(interrupted)
 }
 
 async fn handle_slash_command(
@@ -213,6 +317,7 @@ async fn handle_slash_command(
     input: &str,
     context: &mut ConversationContext,
     editor: &mut MultiFileEditor,
+    dry_run: &mut bool,
 ) -> Result<bool, Box<dyn std::error::Error>> {
     let parts: Vec<&str> = input[1..].split_whitespace().collect();
     if parts.is_empty() {
@@ -240,13 +345,15 @@ async fn handle_slash_command(
             println!("{}🔧 WiseOwl{}", PURPLE, RESET);
             println!("  /todo /done /rule /context");
             println!("{}📁 Files{}", PURPLE, RESET);
-            println!("  /read /write /write-direct /append /build /template /model /preview /apply /rollback");
+            println!("  /read /write /write-direct /append /build /test /template /model /preview /apply /discard /rollback");
+            println!("  /dryrun on|off - stage agent writes and commands instead of running them");
             println!("{}🔌 MCP{}", PURPLE, RESET);
-            println!("  /mcp list | /mcp call <tool>");
+            println!("  /mcp list | /mcp call <tool> | /mcp resources | /mcp read <uri> | /mcp prompt <name> [key=value...]");
+            println!("  /mcp add [--global] <name> <command|url> | /mcp remove|enable|disable <name>");
             println!("{}⚙️  Config{}", PURPLE, RESET);
             println!("  /config list|set|get | /export");
             println!("{}📊 Monitor{}", PURPLE, RESET);
-            println!("  /stats /monitor /git");
+            println!("  /stats /monitor /git /jobs /audit");
             println!("{}ℹ️  Other{}", PURPLE, RESET);
             println!("  /dashboard /history /alias /perf /help /version /clear /exit");
             println!("  /help /version /clear /exit");
@@ -388,6 +495,29 @@ async fn handle_slash_command(
             }
         }
 
+        "test" => {
+            let (runner, filter) = crate::test_runner::parse_command_args(&parts[1..]);
+            let runner = runner.or_else(|| crate::test_runner::TestRunner::detect(Path::new(".")));
+            let Some(runner) = runner else {
+                println!("❌ Could not detect a test runner");
+                println!("Usage: /test [cargo|go|jest|npm|pytest|auto] [filter]");
+                return Ok(true);
+            };
+
+            println!("🧪 Testing with {}...", runner.name());
+            match crate::test_runner::run_tests(Some(runner), filter.as_deref()).await {
+                Ok(report) => {
+                    if report.success {
+                        println!("✅ Tests passed");
+                    } else {
+                        println!("❌ Tests failed");
+                    }
+                    print!("{}", report.display());
+                }
+                Err(e) => println!("❌ Error: {}", e),
+            }
+        }
+
         "template" => {
             if parts.len() < 3 {
                 println!("❌ Usage: /template <type> <path>");
@@ -545,13 +675,56 @@ if __name__ == "__main__":
                             });
                         }
                     }
-                    Err(e) => println!("❌ Error: {}", e),
+                    Err(e) => {
+                        // Commands usually build or test the edits, so they
+                        // must not run against a half-applied tree
+                        println!("❌ Error: {}", e);
+                        println!("⚙️  Staged commands were not run; /preview, /apply or /discard them");
+                        return Ok(true);
+                    }
+                }
+                for (tool, parameters) in editor.take_deferred_calls() {
+                    let call = crate::tools::ToolCall { tool, parameters };
+                    println!("⚙️  {} {}", call.tool, call.parameters);
+                    match crate::tools::execute_tool(&call, None).await {
+                        crate::tools::ToolResult::Success(out) => println!("{}", out),
+                        crate::tools::ToolResult::Error(err) => println!("❌ {}", err),
+                    }
                 }
             } else {
                 println!("❌ Cancelled");
             }
         }
 
+        "discard" => {
+            if editor.has_pending() {
+                editor.clear();
+                println!("🗑️  Discarded pending changes");
+            } else {
+                println!("📭 No pending changes");
+            }
+        }
+
+        "dryrun" => {
+            match parts.get(1).copied() {
+                Some("on") => *dry_run = true,
+                Some("off") => *dry_run = false,
+                None => {}
+                Some(other) => {
+                    println!("❌ Usage: /dryrun [on|off] (got '{}')", other);
+                    return Ok(true);
+                }
+            }
+            if *dry_run {
+                println!("🧪 Dry run ON: agent writes and commands are staged for /preview and /apply");
+            } else {
+                println!("✅ Dry run OFF: agent changes are applied directly");
+            }
+            if !*dry_run && editor.has_pending() {
+                println!("💡 Staged changes remain; use /apply or /discard");
+            }
+        }
+
         "rollback" => match context.rollback_last_change().await {
             Ok(msg) => println!("✅ {}", msg),
             Err(e) => println!("❌ Error: {}", e),
@@ -622,8 +795,13 @@ Executing..."
 Use tools as needed and provide the result.",
                         step.description
                     );
-                    let _response =
-                        crate::streaming::stream_with_tools(client, model, &prompt).await?;
+                    let staging = if *dry_run { Some(&mut *editor) } else { None };
+                    let _turn =
+                        crate::streaming::stream_with_tools(client, model, &prompt, staging)
+                            .await?;
+                    if *dry_run && editor.has_pending() {
+                        println!("🧪 {}", editor.show_preview());
+                    }
 
                     plan.complete_step(step.number, "Completed".to_string());
                     plan.save("current").await?;
@@ -722,6 +900,46 @@ Use tools as needed and provide the result.",
             }
         }
 
+        "jobs" => {
+            let jobs = crate::process::processes().lock().await.list();
+            if jobs.is_empty() {
+                println!("No background processes");
+            } else {
+                println!("⚙️  Background Processes:");
+                for job in jobs {
+                    println!(
+                        "  {} [{}] pid {} up {}s: {}",
+                        job.name,
+                        job.status,
+                        job.pid.map(|p| p.to_string()).unwrap_or_else(|| "-".to_string()),
+                        job.uptime.as_secs(),
+                        job.command
+                    );
+                }
+            }
+        }
+
+        "audit" => {
+            let filter = match crate::audit::AuditFilter::parse(&parts[1..]) {
+                Ok(f) => f,
+                Err(e) => {
+                    println!("❌ {}", e);
+                    println!("Usage: /audit [count] [tool=<name>] [status=success|error] [session=<name>] [source=builtin|mcp]");
+                    return Ok(true);
+                }
+            };
+            match crate::audit::recent(&filter).await {
+                Ok(records) if records.is_empty() => println!("No matching audit entries"),
+                Ok(records) => {
+                    println!("🔍 Audit Log ({} entries):", records.len());
+                    for record in records {
+                        println!("  {}", record.display());
+                    }
+                }
+                Err(e) => println!("❌ Error reading audit log: {}", e),
+            }
+        }
+
         "stats" => {
             let stats = crate::stats::SessionStats::new();
             println!("{}", stats.display());
@@ -729,48 +947,286 @@ Use tools as needed and provide the result.",
 
         "mcp" => {
             if parts.len() < 2 {
-                println!("Usage: /mcp <list|call>");
+                println!("Usage: /mcp <list|status|call|resources|read|prompt|add|remove|enable|disable>");
                 return Ok(true);
             }
 
             match parts[1] {
                 "list" => {
-                    let mut mcp_client = crate::mcp::MCPClient::new();
-                    mcp_client.load_config().await?;
-                    mcp_client.discover_tools().await?;
-
+                    let mcp_client = crate::mcp::session_client().await.lock().await.clone();
+                    let servers = mcp_client.server_infos().await;
                     let tools = mcp_client.list_available_tools();
-                    if tools.is_empty() {
-                        println!("No MCP tools available. Add servers to .wiseowlcli/mcp_servers.json");
-                    } else {
-                        println!("Available MCP Tools:");
-                        for tool in tools {
-                            println!("  - {} ({}): {}", tool.name, tool.server, tool.description);
+                    if servers.is_empty() {
+                        println!("No MCP servers configured. Add one with /mcp add <name> <command|url>");
+                        return Ok(true);
+                    }
+
+                    println!("MCP Servers:");
+                    for (server, info) in &servers {
+                        let status = match info {
+                            _ if !server.enabled => "disabled".to_string(),
+                            Some(info) => info.display(),
+                            None => "not running".to_string(),
+                        };
+                        println!("  📡 {} [{}]: {}", server.name, server.scope.name(), status);
+                        for tool in tools.iter().filter(|t| t.server == server.name) {
+                            println!("      - {}: {}", tool.qualified_name(), tool.description);
+                        }
+                    }
+                    for (name, servers) in mcp_client.duplicate_tool_names() {
+                        println!(
+                            "  ⚠️  {} is offered by {}; use the server.tool name",
+                            name,
+                            servers.join(", ")
+                        );
+                    }
+                }
+                "status" => {
+                    let mcp_client = crate::mcp::session_client().await.lock().await.clone();
+                    let servers = mcp_client.health();
+                    if servers.is_empty() {
+                        println!("No MCP servers configured");
+                        return Ok(true);
+                    }
+
+                    println!("MCP Server Health:");
+                    for (server, health) in &servers {
+                        let summary = if server.enabled {
+                            health.summary()
+                        } else {
+                            "disabled".to_string()
+                        };
+                        println!("  📡 {}: {}", server.name, summary);
+                        if let Some(error) = &health.last_error {
+                            println!("      last error: {}", error);
+                        }
+                        if let Some(line) = &health.last_stderr {
+                            println!("      last stderr: {}", line);
+                        }
+                        if server.url.is_none() && health.started_at.is_some() {
+                            println!(
+                                "      log: {}",
+                                mcp_client.log_path(&server.name).display()
+                            );
+                        }
+                        for (tool, stats) in &health.tools {
+                            println!("      - {}: {}", tool, stats.display());
                         }
                     }
                 }
                 "call" => {
                     if parts.len() < 3 {
-                        println!("Usage: /mcp call <tool_name> [params]");
+                        println!("Usage: /mcp call <tool_name> [{{json}}|key=value ...]");
                         return Ok(true);
                     }
 
-                    let mut mcp_client = crate::mcp::MCPClient::new();
-                    mcp_client.load_config().await?;
-                    mcp_client.discover_tools().await?;
-
+                    let mcp_client = crate::mcp::session_client().await.lock().await.clone();
                     let tool_name = parts[2];
-                    let params = if parts.len() > 3 {
-                        serde_json::json!({"input": parts[3..].join(" ")})
+                    let schema = match mcp_client.resolve_tool(tool_name) {
+                        Ok(tool) => tool.input_schema.clone(),
+                        Err(e) => {
+                            println!("❌ {}", e);
+                            return Ok(true);
+                        }
+                    };
+                    let arguments = skip_words(&input[1..], 3);
+                    let params = if arguments.is_empty() {
+                        crate::mcp_arguments::prompt_for_arguments(&schema, |prompt| {
+                            print!("{}", prompt);
+                            std::io::Write::flush(&mut std::io::stdout()).ok();
+                            let mut answer = String::new();
+                            match std::io::stdin().read_line(&mut answer) {
+                                Ok(0) | Err(_) => None,
+                                Ok(_) => Some(answer.trim_end().to_string()),
+                            }
+                        })
                     } else {
-                        serde_json::json!({})
+                        crate::mcp_arguments::parse_tool_arguments(&schema, arguments)
                     };
+                    let params = match params {
+                        Ok(params) => params,
+                        Err(e) => {
+                            println!("❌ {}", e);
+                            return Ok(true);
+                        }
+                    };
+
+                    match mcp_client.call_tool_streaming(tool_name, params).await {
+                        Ok(result) => {
+                            let rendered = crate::mcp_arguments::render_tool_result(&result);
+                            if result["isError"].as_bool().unwrap_or(false) {
+                                println!("❌ Tool error:\n{}", rendered);
+                            } else {
+                                println!("✅ Result:\n{}", rendered);
+                            }
+                        }
+                        Err(e) => println!("❌ Error: {}", e),
+                    }
+                }
+                "resources" => {
+                    let mcp_client = crate::mcp::session_client().await.lock().await.clone();
+                    let resources = mcp_client.list_resources().await;
+                    let templates = mcp_client.list_resource_templates().await;
+                    if resources.is_empty() && templates.is_empty() {
+                        println!("No MCP resources available");
+                        return Ok(true);
+                    }
+                    if !resources.is_empty() {
+                        println!("MCP Resources:");
+                        for resource in &resources {
+                            println!(
+                                "  - {} ({}): {} {}",
+                                resource.uri,
+                                resource.server,
+                                resource.name,
+                                resource.mime_type.as_deref().unwrap_or("")
+                            );
+                        }
+                    }
+                    if !templates.is_empty() {
+                        println!("MCP Resource Templates:");
+                        for template in &templates {
+                            println!(
+                                "  - {} ({}): {}",
+                                template.uri_template, template.server, template.name
+                            );
+                        }
+                    }
+                    let prompts = mcp_client.list_prompts().await;
+                    if !prompts.is_empty() {
+                        println!("MCP Prompts:");
+                        for prompt in &prompts {
+                            println!(
+                                "  - {} ({}): {}",
+                                prompt.usage(),
+                                prompt.server,
+                                prompt.description
+                            );
+                        }
+                    }
+                }
+                "read" => {
+                    if parts.len() < 3 {
+                        println!("Usage: /mcp read <uri>");
+                        return Ok(true);
+                    }
 
-                    match mcp_client.call_tool(tool_name, params).await {
-                        Ok(result) => println!("✅ Result: {:?}", result),
+                    let mcp_client = crate::mcp::session_client().await.lock().await.clone();
+                    match mcp_client.read_resource(parts[2]).await {
+                        Ok(content) => {
+                            println!("📄 Read {} ({} bytes)", parts[2], content.len());
+                            context.add_file(parts[2].to_string(), content);
+                            println!("✅ Added to context");
+                        }
                         Err(e) => println!("❌ Error: {}", e),
                     }
                 }
+                "prompt" => {
+                    if parts.len() < 3 {
+                        println!("Usage: /mcp prompt <name> [key=value...]");
+                        return Ok(true);
+                    }
+
+                    let mcp_client = crate::mcp::session_client().await.lock().await.clone();
+                    let arguments = crate::mcp::parse_prompt_arguments(&parts[3..]);
+                    let prompt = match mcp_client.get_prompt(parts[2], &arguments).await {
+                        Ok(prompt) => prompt,
+                        Err(e) => {
+                            println!("❌ Error: {}", e);
+                            return Ok(true);
+                        }
+                    };
+                    println!("💬 {}\n", prompt);
+
+                    context.add_message("user".to_string(), prompt.clone());
+                    let full_prompt = format!(
+                        "{}\n{}\n\n{}",
+                        get_system_prompt_with_mcp()
+                            .await
+                            .unwrap_or_else(|_| get_system_prompt()),
+                        context.get_context_summary(),
+                        prompt
+                    );
+                    let staging = if *dry_run { Some(&mut *editor) } else { None };
+                    let turn = stream_with_tools(client, model, &full_prompt, staging).await?;
+                    context.add_message("assistant".to_string(), turn.response);
+                    if *dry_run && editor.has_pending() {
+                        println!("🧪 {}", editor.show_preview());
+                    }
+                }
+                "add" => {
+                    let global = parts.contains(&"--global");
+                    let words: Vec<&str> =
+                        parts[2..].iter().copied().filter(|w| *w != "--global").collect();
+                    if words.len() < 2 {
+                        println!("Usage: /mcp add [--global] <name> <command [args...]|url>");
+                        return Ok(true);
+                    }
+
+                    let mut server = crate::mcp::MCPServer {
+                        name: words[0].to_string(),
+                        ..Default::default()
+                    };
+                    if words[1].starts_with("http://") || words[1].starts_with("https://") {
+                        server.url = Some(words[1].to_string());
+                    } else {
+                        server.command = words[1].to_string();
+                        server.args = words[2..].iter().map(|a| a.to_string()).collect();
+                    }
+                    let scope = if global {
+                        crate::mcp_config::ConfigScope::Global
+                    } else {
+                        crate::mcp_config::ConfigScope::Project
+                    };
+                    match scope.path() {
+                        Ok(path) => match crate::mcp_config::add_server(&path, &server).await {
+                            Ok(()) => {
+                                println!("✅ Added {} to {}", server.name, path.display());
+                                reload_mcp().await;
+                            }
+                            Err(e) => println!("❌ {}", e),
+                        },
+                        Err(e) => println!("❌ {}", e),
+                    }
+                }
+                "remove" | "enable" | "disable" => {
+                    if parts.len() < 3 {
+                        println!("Usage: /mcp {} <name>", parts[1]);
+                        return Ok(true);
+                    }
+
+                    let scope = crate::mcp::session_client()
+                        .await
+                        .lock()
+                        .await
+                        .find_server(parts[2])
+                        .map(|s| s.scope);
+                    let Some(scope) = scope else {
+                        println!("❌ No MCP server named {}", parts[2]);
+                        return Ok(true);
+                    };
+                    let edited = match scope.path() {
+                        Ok(path) => match parts[1] {
+                            "remove" => crate::mcp_config::remove_server(&path, parts[2]).await,
+                            action => {
+                                crate::mcp_config::set_server_enabled(
+                                    &path,
+                                    parts[2],
+                                    action == "enable",
+                                )
+                                .await
+                            }
+                        },
+                        Err(e) => Err(e),
+                    };
+                    match edited {
+                        Ok(()) => {
+                            println!("✅ {}: {} ({} config)", parts[1], parts[2], scope.name());
+                            reload_mcp().await;
+                        }
+                        Err(e) => println!("❌ {}", e),
+                    }
+                }
                 _ => println!("Unknown mcp command"),
             }
         }
@@ -1039,6 +1495,28 @@ Use tools as needed and provide the result.",
     Ok(true)
 }
 
+/// Restart the session's MCP servers after their config changed
+async fn reload_mcp() {
+    let mut client = crate::mcp::session_client().await.lock().await;
+    match client.reload().await {
+        Ok(()) => println!(
+            "🔌 {} MCP tools available",
+            client.list_available_tools().len()
+        ),
+        Err(e) => println!("⚠️  Failed to reload MCP config: {}", e),
+    }
+}
+
+/// What follows the first `count` words of `text`, whitespace kept as typed
+fn skip_words(text: &str, count: usize) -> &str {
+    let mut rest = text.trim_start();
+    for _ in 0..count {
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        rest = rest[end..].trim_start();
+    }
+    rest.trim_end()
+}
+
 async fn init_project_mode(client: &Client, model: &str) -> Result<(), Box<dyn std::error::Error>> {
     println!("🤖 OCLI - Initializing project");
     println!("⏳ Analyzing project structure...");
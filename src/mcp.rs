@@ -1,40 +1,1181 @@
 use serde::{Deserialize, Serialize};
-use std::io::{BufRead, BufReader, Write};
-use std::process::{Command, Stdio};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::sync::{oneshot, Mutex, Notify, OnceCell};
 
+use crate::mcp_config::{expand_env_vars, load_servers, ConfigScope};
+use crate::mcp_http::{HttpKind, HttpTransport};
+use crate::mcp_sampling::Sampler;
+use crate::mcp_supervisor::{ServerHealth, Supervisor};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(2);
+
+/// Newest first; the first entry is what we ask for in `initialize`
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+/// One entry of `mcp_servers.json`; see `mcp_config` for where those live
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MCPServer {
     pub name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub command: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub args: Vec<String>,
+    /// Extra environment for the server process; values may use `${VAR}`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub env: HashMap<String, String>,
+    /// Working directory for the server process
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    /// How long to wait for each response (default 60s)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+    /// Disabled servers stay in the config but are never started
+    #[serde(default = "enabled_by_default", skip_serializing_if = "is_enabled")]
+    pub enabled: bool,
+    /// HTTP servers are reached at `url` instead of being launched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// `"http"` (Streamable HTTP, the default) or `"sse"` (the legacy
+    /// HTTP+SSE transport); only used with `url`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transport: Option<String>,
+    /// Extra headers for every HTTP request, e.g. `Authorization`; values
+    /// may use `${VAR}`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "ReconnectPolicy::is_default")]
+    pub reconnect: ReconnectPolicy,
+    /// The config file this entry was loaded from
+    #[serde(skip)]
+    pub scope: ConfigScope,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+fn is_enabled(enabled: &bool) -> bool {
+    *enabled
+}
+
+impl Default for MCPServer {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            command: String::new(),
+            args: Vec::new(),
+            env: HashMap::new(),
+            cwd: None,
+            timeout_secs: None,
+            enabled: true,
+            url: None,
+            transport: None,
+            headers: HashMap::new(),
+            reconnect: ReconnectPolicy::default(),
+            scope: ConfigScope::default(),
+        }
+    }
+}
+
+impl MCPServer {
+    pub fn timeout(&self) -> Duration {
+        self.timeout_secs
+            .map(Duration::from_secs)
+            .unwrap_or(REQUEST_TIMEOUT)
+    }
+
+    /// `${VAR}` references in `env`, `headers` and `cwd` that are not set
+    pub fn missing_env_vars(&self) -> Vec<String> {
+        let mut missing: Vec<String> = self
+            .env
+            .values()
+            .chain(self.headers.values())
+            .chain(self.cwd.iter())
+            .flat_map(|value| expand_env_vars(value).1)
+            .collect();
+        missing.sort();
+        missing.dedup();
+        missing
+    }
+}
+
+/// How servers are (re)connected: failed HTTP connections and 5xx answers
+/// are retried `max_attempts` times, the delay doubling from
+/// `initial_delay_ms`. A server whose session is lost is reconnected on the
+/// next request under the same policy, and a stdio server that exits on its
+/// own is restarted up to `max_attempts` times in a row.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReconnectPolicy {
+    pub max_attempts: u32,
+    pub initial_delay_ms: u64,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_delay_ms: 500,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn delay(&self, attempt: u32) -> Duration {
+        Duration::from_millis(self.initial_delay_ms.saturating_mul(1 << attempt.min(10)))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MCPTool {
+    /// The name the server knows the tool by
     pub name: String,
     pub description: String,
     pub server: String,
+    /// JSON Schema for the tool's arguments, as reported by `tools/list`
+    #[serde(default)]
+    pub input_schema: serde_json::Value,
+}
+
+impl MCPTool {
+    /// `server.tool`, unique across servers; `server__tool` is accepted too
+    pub fn qualified_name(&self) -> String {
+        format!("{}.{}", self.server, self.name)
+    }
+
+    fn answers_to(&self, qualified: &str) -> bool {
+        [".", "__"].iter().any(|separator| {
+            qualified
+                .strip_prefix(self.server.as_str())
+                .and_then(|rest| rest.strip_prefix(separator))
+                == Some(self.name.as_str())
+        })
+    }
+}
+
+/// A concrete resource from `resources/list`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MCPResource {
+    pub uri: String,
+    pub name: String,
+    pub description: String,
+    pub mime_type: Option<String>,
+    pub server: String,
+}
+
+/// A parameterised resource from `resources/templates/list`, e.g. `file:///{path}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MCPResourceTemplate {
+    pub uri_template: String,
+    pub name: String,
+    pub description: String,
+    pub server: String,
+}
+
+impl MCPResourceTemplate {
+    /// Whether `uri` could have been expanded from this template: the literal
+    /// text before the first `{` must be a prefix of it
+    pub fn matches(&self, uri: &str) -> bool {
+        let prefix = self.uri_template.split('{').next().unwrap_or("");
+        !prefix.is_empty() && uri.starts_with(prefix)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptArgument {
+    pub name: String,
+    pub description: String,
+    pub required: bool,
+}
+
+/// A prompt template from `prompts/list`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MCPPrompt {
+    pub name: String,
+    pub description: String,
+    pub arguments: Vec<PromptArgument>,
+    pub server: String,
+}
+
+impl MCPPrompt {
+    /// `name <required> [optional]`, for listings and usage errors
+    pub fn usage(&self) -> String {
+        let mut usage = self.name.clone();
+        for argument in &self.arguments {
+            if argument.required {
+                usage.push_str(&format!(" <{}>", argument.name));
+            } else {
+                usage.push_str(&format!(" [{}]", argument.name));
+            }
+        }
+        usage
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct MCPRequest {
     jsonrpc: String,
-    id: u32,
+    id: u64,
     method: String,
     params: serde_json::Value,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct MCPResponse {
-    jsonrpc: String,
-    id: u32,
-    result: Option<serde_json::Value>,
-    error: Option<serde_json::Value>,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct MCPResponse {
+    jsonrpc: String,
+    id: u64,
+    result: Option<serde_json::Value>,
+    error: Option<serde_json::Value>,
+}
+
+/// What a server told us about itself during `initialize`
+#[derive(Debug, Clone)]
+pub struct ServerInfo {
+    pub protocol_version: String,
+    pub name: String,
+    pub version: String,
+    pub capabilities: serde_json::Value,
+    pub instructions: Option<String>,
+}
+
+impl ServerInfo {
+    fn from_initialize(result: &serde_json::Value) -> Result<Self, String> {
+        let protocol_version = result
+            .get("protocolVersion")
+            .and_then(|v| v.as_str())
+            .ok_or("initialize result has no protocolVersion")?;
+        if !SUPPORTED_PROTOCOL_VERSIONS.contains(&protocol_version) {
+            return Err(format!(
+                "unsupported protocol version {} (supported: {})",
+                protocol_version,
+                SUPPORTED_PROTOCOL_VERSIONS.join(", ")
+            ));
+        }
+
+        let info = result.get("serverInfo");
+        let field = |key: &str| {
+            info.and_then(|i| i.get(key))
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string()
+        };
+        Ok(Self {
+            protocol_version: protocol_version.to_string(),
+            name: field("name"),
+            version: field("version"),
+            capabilities: result
+                .get("capabilities")
+                .cloned()
+                .unwrap_or_else(|| serde_json::json!({})),
+            instructions: result
+                .get("instructions")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string()),
+        })
+    }
+
+    pub fn has_capability(&self, name: &str) -> bool {
+        self.capabilities.get(name).is_some_and(|c| !c.is_null())
+    }
+
+    pub fn display(&self) -> String {
+        let mut capabilities: Vec<&str> = self
+            .capabilities
+            .as_object()
+            .map(|c| c.keys().map(|k| k.as_str()).collect())
+            .unwrap_or_default();
+        capabilities.sort();
+        format!(
+            "{} {} (protocol {}; {})",
+            if self.name.is_empty() {
+                "unnamed server"
+            } else {
+                &self.name
+            },
+            self.version,
+            self.protocol_version,
+            if capabilities.is_empty() {
+                "no capabilities".to_string()
+            } else {
+                capabilities.join(", ")
+            }
+        )
+    }
+}
+
+type PendingRequests = Arc<StdMutex<HashMap<u64, oneshot::Sender<MCPResponse>>>>;
+type Connections = Arc<Mutex<HashMap<String, Arc<Connection>>>>;
+/// Where `notifications/progress` go, by progress token (the request's id)
+type ProgressListeners = Arc<StdMutex<HashMap<u64, UnboundedSender<Progress>>>>;
+
+/// One `notifications/progress` update for a running request
+#[derive(Debug, Clone, PartialEq)]
+pub struct Progress {
+    pub progress: f64,
+    pub total: Option<f64>,
+    pub message: Option<String>,
+}
+
+/// Severity of server log messages (`notifications/message`), lowest first
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Notice,
+    Warning,
+    Error,
+    Critical,
+    Alert,
+    Emergency,
+}
+
+impl LogLevel {
+    const ALL: [LogLevel; 8] = [
+        LogLevel::Debug,
+        LogLevel::Info,
+        LogLevel::Notice,
+        LogLevel::Warning,
+        LogLevel::Error,
+        LogLevel::Critical,
+        LogLevel::Alert,
+        LogLevel::Emergency,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Notice => "notice",
+            LogLevel::Warning => "warning",
+            LogLevel::Error => "error",
+            LogLevel::Critical => "critical",
+            LogLevel::Alert => "alert",
+            LogLevel::Emergency => "emergency",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|level| level.name() == name)
+    }
+
+    /// `mcp_log_level` from .wiseowlcli/config.json (`/config set mcp_log_level info`),
+    /// warning by default
+    pub async fn load() -> Self {
+        let configured = match std::env::current_dir() {
+            Ok(dir) => tokio::fs::read_to_string(dir.join(".wiseowlcli").join("config.json"))
+                .await
+                .ok()
+                .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+                .and_then(|config| config["mcp_log_level"].as_str().and_then(Self::from_name)),
+            Err(_) => None,
+        };
+        configured.unwrap_or(LogLevel::Warning)
+    }
+}
+
+/// How messages reach a server: the stdio pipes of a process we launched, or
+/// HTTP for servers configured with a `url`
+enum Transport {
+    Stdio {
+        child: Mutex<Child>,
+        stdin: Mutex<Option<ChildStdin>>,
+    },
+    Http(HttpTransport),
+}
+
+impl Transport {
+    async fn open(
+        server: &MCPServer,
+        inbox: UnboundedSender<serde_json::Value>,
+        supervisor: &Supervisor,
+    ) -> Result<Self, String> {
+        if let Some(url) = &server.url {
+            let kind = HttpKind::from_config(server.transport.as_deref())?;
+            let headers = server
+                .headers
+                .iter()
+                .map(|(name, value)| (name.clone(), expand_env_vars(value).0))
+                .collect();
+            let http = HttpTransport::connect(kind, url, headers, server.reconnect.clone(), inbox)
+                .await
+                .map_err(|e| format!("Failed to connect to {}: {}", server.name, e))?;
+            return Ok(Transport::Http(http));
+        }
+
+        let mut command = Command::new(&server.command);
+        command
+            .args(&server.args)
+            .envs(
+                server
+                    .env
+                    .iter()
+                    .map(|(name, value)| (name, expand_env_vars(value).0)),
+            )
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        if let Some(cwd) = &server.cwd {
+            command.current_dir(expand_env_vars(cwd).0);
+        }
+        let mut child = command
+            .spawn()
+            .map_err(|e| format!("Failed to start {}: {}", server.name, e))?;
+        let stdout = child.stdout.take().ok_or("Failed to open stdout")?;
+        tokio::spawn(read_lines(BufReader::new(stdout), inbox));
+        if let Some(stderr) = child.stderr.take() {
+            supervisor.capture_stderr(&server.name, stderr);
+        }
+        Ok(Transport::Stdio {
+            stdin: Mutex::new(child.stdin.take()),
+            child: Mutex::new(child),
+        })
+    }
+
+    async fn send(&self, server: &str, message: &serde_json::Value) -> Result<(), String> {
+        match self {
+            Transport::Stdio { stdin, .. } => {
+                let mut line = message.to_string();
+                line.push('\n');
+                let mut stdin = stdin.lock().await;
+                let pipe = stdin
+                    .as_mut()
+                    .ok_or_else(|| format!("{} is shut down", server))?;
+                pipe.write_all(line.as_bytes())
+                    .await
+                    .map_err(|e| format!("Failed to write to {}: {}", server, e))?;
+                pipe.flush()
+                    .await
+                    .map_err(|e| format!("Failed to write to {}: {}", server, e))
+            }
+            Transport::Http(http) => http
+                .send(message)
+                .await
+                .map_err(|e| format!("{}: {}", server, e)),
+        }
+    }
+}
+
+/// A connected server. Requests share its transport and are matched to
+/// responses by JSON-RPC id, so several may be in flight at once.
+struct Connection {
+    server: String,
+    transport: Arc<Transport>,
+    pending: PendingRequests,
+    progress: ProgressListeners,
+    next_id: AtomicU64,
+    timeout: Duration,
+    log_level: LogLevel,
+    info: std::sync::OnceLock<ServerInfo>,
+    /// Signalled once the server's messages stop, e.g. because it exited
+    closed: Arc<Notify>,
+}
+
+impl Connection {
+    async fn spawn(
+        server: &MCPServer,
+        log_level: LogLevel,
+        supervisor: &Supervisor,
+        sampler: Option<Sampler>,
+    ) -> Result<Arc<Self>, String> {
+        let (inbox, incoming) = mpsc::unbounded_channel();
+        let transport = Arc::new(Transport::open(server, inbox, supervisor).await?);
+        let pending: PendingRequests = Arc::new(StdMutex::new(HashMap::new()));
+        let progress: ProgressListeners = Arc::new(StdMutex::new(HashMap::new()));
+        let closed = Arc::new(Notify::new());
+
+        let routes = Routes {
+            server: server.name.clone(),
+            pending: pending.clone(),
+            progress: progress.clone(),
+            log_level,
+            transport: transport.clone(),
+            closed: closed.clone(),
+            sampler: sampler.clone(),
+        };
+        tokio::spawn(routes.run(incoming));
+
+        let connection = Arc::new(Self {
+            server: server.name.clone(),
+            transport,
+            pending,
+            progress,
+            next_id: AtomicU64::new(1),
+            timeout: server.timeout(),
+            log_level,
+            info: std::sync::OnceLock::new(),
+            closed,
+        });
+        if let Err(e) = connection.initialize(sampler.is_some()).await {
+            connection.shutdown().await;
+            return Err(format!("{} failed to initialize: {}", server.name, e));
+        }
+        Ok(connection)
+    }
+
+    /// The lifecycle handshake: `initialize`, then `notifications/initialized`.
+    /// Servers reject every other request until both have happened. With
+    /// `sampling` we offer to answer `sampling/createMessage`.
+    async fn initialize(&self, sampling: bool) -> Result<(), String> {
+        let capabilities = if sampling {
+            serde_json::json!({ "sampling": {} })
+        } else {
+            serde_json::json!({})
+        };
+        let response = self
+            .request(
+                "initialize",
+                serde_json::json!({
+                    "protocolVersion": SUPPORTED_PROTOCOL_VERSIONS[0],
+                    "capabilities": capabilities,
+                    "clientInfo": {
+                        "name": "wiseowlcli",
+                        "version": env!("CARGO_PKG_VERSION")
+                    }
+                }),
+            )
+            .await?;
+        if let Some(error) = response.error {
+            return Err(error_message(&error));
+        }
+        let info = ServerInfo::from_initialize(&response.result.unwrap_or_default())?;
+        if let Transport::Http(http) = &*self.transport {
+            http.set_protocol_version(&info.protocol_version);
+        }
+        let logging = info.has_capability("logging");
+        let _ = self.info.set(info);
+
+        self.write_message(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/initialized"
+        }))
+        .await?;
+
+        // Servers that log can filter at the source; messages are still
+        // filtered here for servers that ignore the level
+        if logging {
+            let _ = self
+                .request(
+                    "logging/setLevel",
+                    serde_json::json!({ "level": self.log_level.name() }),
+                )
+                .await;
+        }
+        Ok(())
+    }
+
+    async fn is_alive(&self) -> bool {
+        match &*self.transport {
+            Transport::Stdio { child, .. } => matches!(child.lock().await.try_wait(), Ok(None)),
+            Transport::Http(http) => http.is_open(),
+        }
+    }
+
+    fn is_stdio(&self) -> bool {
+        matches!(&*self.transport, Transport::Stdio { .. })
+    }
+
+    /// How a stdio server ended, once its output has closed
+    async fn exit_status(&self) -> String {
+        let Transport::Stdio { child, .. } = &*self.transport else {
+            return "closed the connection".to_string();
+        };
+        match tokio::time::timeout(SHUTDOWN_GRACE, child.lock().await.wait()).await {
+            Ok(Ok(status)) => format!("exited ({})", status),
+            Ok(Err(e)) => format!("exited ({})", e),
+            Err(_) => "closed its output".to_string(),
+        }
+    }
+
+    async fn write_message(&self, message: &serde_json::Value) -> Result<(), String> {
+        self.transport.send(&self.server, message).await
+    }
+
+    async fn request(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<MCPResponse, String> {
+        self.request_until(method, params, None, std::future::pending())
+            .await
+    }
+
+    /// Send a request, forwarding its progress notifications to `progress`
+    /// and giving up when `cancel` completes or the timeout passes. Either
+    /// way the server is told with `notifications/cancelled`.
+    async fn request_until<F>(
+        &self,
+        method: &str,
+        mut params: serde_json::Value,
+        progress: Option<UnboundedSender<Progress>>,
+        cancel: F,
+    ) -> Result<MCPResponse, String>
+    where
+        F: std::future::Future<Output = ()>,
+    {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id, tx);
+        if let Some(listener) = progress {
+            if let Some(params) = params.as_object_mut() {
+                params.insert(
+                    "_meta".to_string(),
+                    serde_json::json!({ "progressToken": id }),
+                );
+                self.progress.lock().unwrap().insert(id, listener);
+            }
+        }
+        let result = self.exchange(id, method, params, rx, cancel).await;
+        self.pending.lock().unwrap().remove(&id);
+        self.progress.lock().unwrap().remove(&id);
+        result
+    }
+
+    async fn exchange<F>(
+        &self,
+        id: u64,
+        method: &str,
+        params: serde_json::Value,
+        rx: oneshot::Receiver<MCPResponse>,
+        cancel: F,
+    ) -> Result<MCPResponse, String>
+    where
+        F: std::future::Future<Output = ()>,
+    {
+        let request = MCPRequest {
+            jsonrpc: "2.0".to_string(),
+            id,
+            method: method.to_string(),
+            params,
+        };
+        let message = serde_json::to_value(&request).map_err(|e| e.to_string())?;
+        self.write_message(&message).await?;
+
+        tokio::select! {
+            response = tokio::time::timeout(self.timeout, rx) => match response {
+                Ok(Ok(response)) => Ok(response),
+                Ok(Err(_)) => Err(format!("{} closed the connection", self.server)),
+                Err(_) => {
+                    self.cancel(id, method, "timed out").await;
+                    Err(format!(
+                        "{} did not answer {} within {}s",
+                        self.server,
+                        method,
+                        self.timeout.as_secs()
+                    ))
+                }
+            },
+            _ = cancel => {
+                self.cancel(id, method, "cancelled by the user").await;
+                Err(format!("{} cancelled", method))
+            }
+        }
+    }
+
+    async fn cancel(&self, id: u64, method: &str, reason: &str) {
+        // The spec forbids cancelling initialize
+        if method == "initialize" {
+            return;
+        }
+        let _ = self
+            .write_message(&serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": "notifications/cancelled",
+                "params": { "requestId": id, "reason": reason }
+            }))
+            .await;
+    }
+
+    /// Stdio shutdown as the spec describes it: close stdin and let the server
+    /// exit, then SIGTERM, then SIGKILL if it still lingers. HTTP servers are
+    /// told the session is over.
+    async fn shutdown(&self) {
+        let (child, stdin) = match &*self.transport {
+            Transport::Stdio { child, stdin } => (child, stdin),
+            Transport::Http(http) => return http.close().await,
+        };
+        stdin.lock().await.take();
+        let mut child = child.lock().await;
+        if tokio::time::timeout(SHUTDOWN_GRACE, child.wait())
+            .await
+            .is_ok()
+        {
+            return;
+        }
+        #[cfg(unix)]
+        if let Some(pid) = child.id() {
+            let _ = Command::new("kill")
+                .arg("-TERM")
+                .arg(pid.to_string())
+                .status()
+                .await;
+            if tokio::time::timeout(SHUTDOWN_GRACE, child.wait())
+                .await
+                .is_ok()
+            {
+                return;
+            }
+        }
+        let _ = child.kill().await;
+    }
+}
+
+/// Flatten a `tools/call` result's content blocks into text for the model
+pub fn render_content(result: &serde_json::Value) -> String {
+    let blocks = result
+        .get("content")
+        .and_then(|c| c.as_array())
+        .cloned()
+        .unwrap_or_default();
+    if blocks.is_empty() {
+        return match result.get("structuredContent") {
+            Some(structured) => serde_json::to_string_pretty(structured).unwrap_or_default(),
+            None => String::new(),
+        };
+    }
+
+    let mut parts = Vec::new();
+    for block in &blocks {
+        let field = |key: &str| block.get(key).and_then(|v| v.as_str()).unwrap_or("");
+        let part = match field("type") {
+            "text" => field("text").to_string(),
+            "image" | "audio" => format!(
+                "[{} {}, {} bytes base64]",
+                field("type"),
+                field("mimeType"),
+                field("data").len()
+            ),
+            "resource" => {
+                let resource = block.get("resource").cloned().unwrap_or_default();
+                match resource.get("text").and_then(|t| t.as_str()) {
+                    Some(text) => text.to_string(),
+                    None => format!(
+                        "[resource {}]",
+                        resource.get("uri").and_then(|u| u.as_str()).unwrap_or("")
+                    ),
+                }
+            }
+            "resource_link" => format!("[resource link {} {}]", field("uri"), field("name")),
+            _ => block.to_string(),
+        };
+        parts.push(part);
+    }
+    parts.join("\n")
+}
+
+/// Flatten `resources/read` contents: text as-is, binary as a placeholder
+fn render_resource_contents(result: &serde_json::Value) -> String {
+    let contents = result
+        .get("contents")
+        .and_then(|c| c.as_array())
+        .cloned()
+        .unwrap_or_default();
+    contents
+        .iter()
+        .map(
+            |content| match content.get("text").and_then(|t| t.as_str()) {
+                Some(text) => text.to_string(),
+                None => format!(
+                    "[binary {} {}, {} bytes base64]",
+                    str_field(content, "uri"),
+                    str_field(content, "mimeType"),
+                    str_field(content, "blob").len()
+                ),
+            },
+        )
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Flatten `prompts/get` messages into `role: text` lines
+fn render_prompt_messages(result: &serde_json::Value) -> String {
+    let messages = result
+        .get("messages")
+        .and_then(|m| m.as_array())
+        .cloned()
+        .unwrap_or_default();
+    messages
+        .iter()
+        .map(|message| {
+            let content = message.get("content").cloned().unwrap_or_default();
+            format!(
+                "{}: {}",
+                str_field(message, "role"),
+                render_content(&serde_json::json!({ "content": [content] }))
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Parse `key=value` words into prompt arguments. A word without `=`
+/// continues the previous value, so `code=fn main() {}` stays one argument.
+pub fn parse_prompt_arguments(words: &[&str]) -> HashMap<String, String> {
+    let mut arguments = HashMap::new();
+    let mut last: Option<String> = None;
+    for word in words {
+        match word.split_once('=') {
+            Some((key, value)) if !key.is_empty() => {
+                arguments.insert(key.to_string(), value.to_string());
+                last = Some(key.to_string());
+            }
+            _ => {
+                if let Some(value) = last.as_ref().and_then(|key| arguments.get_mut(key)) {
+                    value.push(' ');
+                    value.push_str(word);
+                }
+            }
+        }
+    }
+    arguments
+}
+
+fn str_field(value: &serde_json::Value, key: &str) -> String {
+    value
+        .get(key)
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string()
+}
+
+fn error_message(error: &serde_json::Value) -> String {
+    match error.get("message").and_then(|m| m.as_str()) {
+        Some(message) => match error.get("code") {
+            Some(code) => format!("{} ({})", message, code),
+            None => message.to_string(),
+        },
+        None => error.to_string(),
+    }
+}
+
+/// Parse a stdio server's stdout into messages until it closes
+async fn read_lines<R>(reader: BufReader<R>, inbox: UnboundedSender<serde_json::Value>)
+where
+    R: tokio::io::AsyncRead + Unpin,
+{
+    let mut lines = reader.lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if let Ok(message) = serde_json::from_str::<serde_json::Value>(&line) {
+            let _ = inbox.send(message);
+        }
+    }
+}
+
+/// Bars of the tool calls running now, hidden while the user is asked something
+static ACTIVE_BARS: StdMutex<Vec<indicatif::ProgressBar>> = StdMutex::new(Vec::new());
+
+/// Run `f` with the progress bars hidden, e.g. for an approval prompt
+pub fn suspend_progress<R>(f: impl FnOnce() -> R) -> R {
+    let bar = ACTIVE_BARS.lock().unwrap().last().cloned();
+    match bar {
+        Some(bar) => bar.suspend(f),
+        None => f(),
+    }
+}
+
+/// Progress bar positions are per mille of the reported total
+const PROGRESS_SCALE: u64 = 1000;
+
+fn show_progress(bar: &indicatif::ProgressBar, tool_name: &str, update: &Progress) {
+    let message = match &update.message {
+        Some(message) => format!("{}: {}", tool_name, message),
+        None => format!("Calling {}", tool_name),
+    };
+    match update.total.filter(|total| *total > 0.0) {
+        Some(total) => {
+            if bar.length().is_none() {
+                bar.set_style(
+                    indicatif::ProgressStyle::default_bar()
+                        .template("{spinner:.blue} {msg} [{bar:40.cyan/blue}] {percent}%")
+                        .unwrap()
+                        .progress_chars("#>-"),
+                );
+                bar.set_length(PROGRESS_SCALE);
+            }
+            let fraction = (update.progress / total).clamp(0.0, 1.0);
+            bar.set_position((fraction * PROGRESS_SCALE as f64) as u64);
+            bar.set_message(message);
+        }
+        None => bar.set_message(format!("{} ({})", message, update.progress)),
+    }
+}
+
+/// Calls waiting for Ctrl+C. Once tokio takes over SIGINT it keeps it, so a
+/// single listener cancels the waiting calls, or hands the interrupt to
+/// `idle_interrupt` when there are none.
+struct Interrupts {
+    waiting: AtomicUsize,
+    notify: Notify,
+    idle: Notify,
+}
+
+fn interrupts() -> &'static Interrupts {
+    static INTERRUPTS: std::sync::OnceLock<Interrupts> = std::sync::OnceLock::new();
+    INTERRUPTS.get_or_init(|| {
+        tokio::spawn(async {
+            while tokio::signal::ctrl_c().await.is_ok() {
+                let interrupts = interrupts();
+                if interrupts.waiting.load(Ordering::SeqCst) == 0 {
+                    interrupts.idle.notify_one();
+                } else {
+                    interrupts.notify.notify_waiters();
+                }
+            }
+        });
+        Interrupts {
+            waiting: AtomicUsize::new(0),
+            notify: Notify::new(),
+            idle: Notify::new(),
+        }
+    })
+}
+
+struct Waiting;
+
+impl Drop for Waiting {
+    fn drop(&mut self) {
+        interrupts().waiting.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Completes when the user presses Ctrl+C
+async fn until_interrupted() {
+    let interrupts = interrupts();
+    let notified = interrupts.notify.notified();
+    interrupts.waiting.fetch_add(1, Ordering::SeqCst);
+    let _waiting = Waiting;
+    notified.await;
+}
+
+/// Completes when the user presses Ctrl+C while no MCP call is waiting for
+/// it. Whoever awaits this owns shutting down; the process does not exit.
+pub async fn idle_interrupt() {
+    interrupts().idle.notified().await;
+}
+
+/// Watch a stdio server and restart it with backoff when it exits on its
+/// own. The watch ends once the connection is shut down or replaced.
+async fn supervise(
+    connections: Connections,
+    supervisor: Supervisor,
+    server: MCPServer,
+    log_level: LogLevel,
+    sampler: Option<Sampler>,
+    mut connection: Arc<Connection>,
+) {
+    let is_current = |connection: &Arc<Connection>| {
+        let connections = connections.clone();
+        let connection = connection.clone();
+        let name = server.name.clone();
+        async move {
+            connections
+                .lock()
+                .await
+                .get(&name)
+                .is_some_and(|c| Arc::ptr_eq(c, &connection))
+        }
+    };
+
+    loop {
+        connection.closed.notified().await;
+        if !is_current(&connection).await {
+            return;
+        }
+        let mut error = format!("{} {}", server.name, connection.exit_status().await);
+        loop {
+            let crashes = supervisor.crashed(&server.name, error);
+            if crashes > server.reconnect.max_attempts {
+                eprintln!(
+                    "❌ MCP server {} keeps crashing; not restarting it (see {})",
+                    server.name,
+                    supervisor.log_path(&server.name).display()
+                );
+                return;
+            }
+            let delay = server.reconnect.delay(crashes - 1);
+            eprintln!(
+                "⚠️  MCP server {} stopped; restarting in {}",
+                server.name,
+                crate::mcp_supervisor::format_duration(delay)
+            );
+            tokio::time::sleep(delay).await;
+            if !is_current(&connection).await {
+                return;
+            }
+
+            match Connection::spawn(&server, log_level, &supervisor, sampler.clone()).await {
+                Ok(restarted) => {
+                    let mut current = connections.lock().await;
+                    if !current
+                        .get(&server.name)
+                        .is_some_and(|c| Arc::ptr_eq(c, &connection))
+                    {
+                        drop(current);
+                        restarted.shutdown().await;
+                        return;
+                    }
+                    current.insert(server.name.clone(), restarted.clone());
+                    supervisor.restarted(&server.name);
+                    connection = restarted;
+                    break;
+                }
+                Err(e) => error = e,
+            }
+        }
+    }
+}
+
+/// What a connection's routing task shares with it
+struct Routes {
+    server: String,
+    pending: PendingRequests,
+    progress: ProgressListeners,
+    log_level: LogLevel,
+    transport: Arc<Transport>,
+    closed: Arc<Notify>,
+    sampler: Option<Sampler>,
+}
+
+impl Routes {
+    /// Route responses to their waiting requests and handle notifications
+    /// until the transport closes. Of the requests a server can send us only
+    /// sampling is supported; the rest get "method not found".
+    async fn run(self, mut incoming: UnboundedReceiver<serde_json::Value>) {
+        while let Some(message) = incoming.recv().await {
+            if let Some(method) = message.get("method").and_then(|m| m.as_str()) {
+                match message.get("id") {
+                    Some(id) => self.server_request(method, id.clone(), &message["params"]),
+                    None => self.notification(method, &message["params"]),
+                }
+                continue;
+            }
+
+            if let Ok(response) = serde_json::from_value::<MCPResponse>(message) {
+                if let Some(tx) = self.pending.lock().unwrap().remove(&response.id) {
+                    let _ = tx.send(response);
+                }
+            }
+        }
+        // Dropping the senders fails every request still waiting
+        self.pending.lock().unwrap().clear();
+        self.progress.lock().unwrap().clear();
+        self.closed.notify_one();
+    }
+
+    /// Answer in the background: sampling waits for the user and the model,
+    /// and responses to our own requests must keep flowing meanwhile
+    fn server_request(&self, method: &str, id: serde_json::Value, params: &serde_json::Value) {
+        let sampler = match (method, &self.sampler) {
+            // Servers may only sample while handling one of our requests; at
+            // other times the chat prompt may be waiting for the user's input
+            ("sampling/createMessage", Some(_)) if self.pending.lock().unwrap().is_empty() => {
+                Err((
+                    -1,
+                    "Sampling is only allowed while handling a request".to_string(),
+                ))
+            }
+            ("sampling/createMessage", Some(sampler)) => Ok(sampler.clone()),
+            _ => Err((-32601, "Method not found".to_string())),
+        };
+        let server = self.server.clone();
+        let transport = self.transport.clone();
+        let params = params.clone();
+        tokio::spawn(async move {
+            let result = match sampler {
+                Ok(sampler) => sampler.create_message(&server, &params).await,
+                Err(error) => Err(error),
+            };
+            let reply = match result {
+                Ok(result) => serde_json::json!({"jsonrpc": "2.0", "id": id, "result": result}),
+                Err((code, message)) => serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": {"code": code, "message": message}
+                }),
+            };
+            let _ = transport.send(&server, &reply).await;
+        });
+    }
+
+    fn notification(&self, method: &str, params: &serde_json::Value) {
+        match method {
+            "notifications/progress" => {
+                let Some(token) = params["progressToken"].as_u64() else {
+                    return;
+                };
+                if let Some(listener) = self.progress.lock().unwrap().get(&token) {
+                    let _ = listener.send(Progress {
+                        progress: params["progress"].as_f64().unwrap_or(0.0),
+                        total: params["total"].as_f64(),
+                        message: params["message"].as_str().map(|m| m.to_string()),
+                    });
+                }
+            }
+            "notifications/message" => {
+                let level = params["level"]
+                    .as_str()
+                    .and_then(LogLevel::from_name)
+                    .unwrap_or(LogLevel::Info);
+                if level < self.log_level {
+                    return;
+                }
+                let data = match &params["data"] {
+                    serde_json::Value::String(text) => text.clone(),
+                    other => other.to_string(),
+                };
+                match params["logger"].as_str() {
+                    Some(logger) => {
+                        eprintln!("📝 [{}/{}] {}: {}", self.server, logger, level.name(), data)
+                    }
+                    None => eprintln!("📝 [{}] {}: {}", self.server, level.name(), data),
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The session's MCP client: servers are started once and shared by the chat
+/// loop, the system prompt and the /mcp commands
+pub async fn session_client() -> &'static Mutex<MCPClient> {
+    static CLIENT: OnceCell<Mutex<MCPClient>> = OnceCell::const_new();
+    CLIENT
+        .get_or_init(|| async {
+            let mut client = MCPClient::new();
+            if client.load_config().await.is_ok() {
+                let _ = client.discover_tools().await;
+            }
+            Mutex::new(client)
+        })
+        .await
 }
 
+#[derive(Clone)]
 pub struct MCPClient {
     servers: Vec<MCPServer>,
     tools: Vec<MCPTool>,
+    connections: Connections,
+    log_level: LogLevel,
+    supervisor: Supervisor,
+    sampler: Option<Sampler>,
+}
+
+impl Default for MCPClient {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl MCPClient {
@@ -42,40 +1183,46 @@ impl MCPClient {
         Self {
             servers: Vec::new(),
             tools: Vec::new(),
+            connections: Arc::new(Mutex::new(HashMap::new())),
+            log_level: LogLevel::Warning,
+            supervisor: Supervisor::in_current_dir(),
+            sampler: Sampler::configured(),
         }
     }
 
+    /// Load `~/.wiseowlcli/mcp_servers.json` and the project's
+    /// `.wiseowlcli/mcp_servers.json`, project entries winning by name
     pub async fn load_config(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        let config_path = std::env::current_dir()?
-            .join(".ocli")
-            .join("mcp_servers.json");
-
-        if !config_path.exists() {
-            // Create default config
-            let default_config = serde_json::json!({
-                "servers": []
-            });
-            tokio::fs::create_dir_all(config_path.parent().unwrap()).await?;
-            tokio::fs::write(&config_path, serde_json::to_string_pretty(&default_config)?).await?;
-            return Ok(());
-        }
-
-        let content = tokio::fs::read_to_string(&config_path).await?;
-        let config: serde_json::Value = serde_json::from_str(&content)?;
-
-        if let Some(servers) = config.get("servers").and_then(|s| s.as_array()) {
-            for server in servers {
-                if let Ok(mcp_server) = serde_json::from_value::<MCPServer>(server.clone()) {
-                    self.servers.push(mcp_server);
-                }
+        let home = std::env::var("HOME").ok().map(std::path::PathBuf::from);
+        let project = std::env::current_dir()?;
+        self.servers = load_servers(home.as_deref(), &project).await?;
+        self.log_level = LogLevel::load().await;
+
+        for server in self.servers.iter().filter(|s| s.enabled) {
+            let missing = server.missing_env_vars();
+            if !missing.is_empty() {
+                eprintln!(
+                    "⚠️  MCP server {} refers to unset variable(s): {}",
+                    server.name,
+                    missing.join(", ")
+                );
             }
         }
-
         Ok(())
     }
 
+    /// Stop all servers and start over from the config files, e.g. after
+    /// `/mcp add` or `/mcp disable`
+    pub async fn reload(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.shutdown().await;
+        self.servers.clear();
+        self.tools.clear();
+        self.load_config().await?;
+        self.discover_tools().await
+    }
+
     pub async fn discover_tools(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        for server in &self.servers {
+        for server in self.servers.iter().filter(|s| s.enabled) {
             match self.list_tools(&server.name).await {
                 Ok(tools) => {
                     for tool in tools {
@@ -91,12 +1238,46 @@ impl MCPClient {
                                 .unwrap_or("")
                                 .to_string(),
                             server: server.name.clone(),
+                            input_schema: tool
+                                .get("inputSchema")
+                                .cloned()
+                                .unwrap_or_else(|| serde_json::json!({"type": "object"})),
                         });
                     }
                 }
-                Err(e) => eprintln!("Failed to discover tools from {}: {}", server.name, e),
+                Err(e) => {
+                    eprintln!(
+                        "Failed to discover tools from {}: {} (see {})",
+                        server.name,
+                        e,
+                        self.supervisor.log_path(&server.name).display()
+                    );
+                    self.supervisor.failed(&server.name, e.to_string());
+                }
             }
         }
+
+        for (name, servers) in self.duplicate_tool_names() {
+            let qualified: Vec<String> =
+                servers.iter().map(|s| format!("{}.{}", s, name)).collect();
+            eprintln!(
+                "⚠️  MCP tool {} is offered by {}; call it as {}",
+                name,
+                servers.join(", "),
+                qualified.join(" or ")
+            );
+        }
+        for tool in self
+            .tools
+            .iter()
+            .filter(|t| crate::tools::is_builtin(&t.name))
+        {
+            eprintln!(
+                "⚠️  MCP tool {} is shadowed by the built-in tool; call it as {}",
+                tool.name,
+                tool.qualified_name()
+            );
+        }
         Ok(())
     }
 
@@ -110,14 +1291,9 @@ impl MCPClient {
             .find(|s| s.name == server_name)
             .ok_or("Server not found")?;
 
-        let request = MCPRequest {
-            jsonrpc: "2.0".to_string(),
-            id: 1,
-            method: "tools/list".to_string(),
-            params: serde_json::json!({}),
-        };
-
-        let response = self.send_request(server, &request).await?;
+        let response = self
+            .send_request(server, "tools/list", serde_json::json!({}))
+            .await?;
 
         if let Some(result) = response.result {
             if let Some(tools) = result.get("tools").and_then(|t| t.as_array()) {
@@ -128,50 +1304,450 @@ impl MCPClient {
         Ok(Vec::new())
     }
 
+    /// Send a request over the server's connection, starting the server on first use
     async fn send_request(
         &self,
         server: &MCPServer,
-        request: &MCPRequest,
+        method: &str,
+        params: serde_json::Value,
     ) -> Result<MCPResponse, Box<dyn std::error::Error>> {
-        let mut child = Command::new(&server.command)
-            .args(&server.args)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .spawn()?;
-
-        let mut stdin = child.stdin.take().ok_or("Failed to open stdin")?;
-        let request_json = serde_json::to_string(request)?;
-        stdin.write_all(request_json.as_bytes())?;
-        stdin.write_all(b"\n")?;
-        drop(stdin);
-
-        let stdout = child.stdout.take().ok_or("Failed to open stdout")?;
-        let reader = BufReader::new(stdout);
+        let connection = self.connection(server).await?;
+        Ok(connection.request(method, params).await?)
+    }
 
-        for line in reader.lines() {
-            let line = line?;
-            if let Ok(response) = serde_json::from_str::<MCPResponse>(&line) {
-                return Ok(response);
+    async fn connection(&self, server: &MCPServer) -> Result<Arc<Connection>, String> {
+        if !server.enabled {
+            return Err(format!("{} is disabled", server.name));
+        }
+        let mut connections = self.connections.lock().await;
+        if let Some(connection) = connections.get(&server.name) {
+            if connection.is_alive().await {
+                return Ok(connection.clone());
+            }
+        }
+        let connection = match Connection::spawn(
+            server,
+            self.log_level,
+            &self.supervisor,
+            self.sampler.clone(),
+        )
+        .await
+        {
+            Ok(connection) => connection,
+            Err(e) => {
+                self.supervisor.failed(&server.name, e.clone());
+                return Err(e);
             }
+        };
+        connections.insert(server.name.clone(), connection.clone());
+        self.supervisor.started(&server.name);
+        if connection.is_stdio() {
+            tokio::spawn(supervise(
+                self.connections.clone(),
+                self.supervisor.clone(),
+                server.clone(),
+                self.log_level,
+                self.sampler.clone(),
+                connection.clone(),
+            ));
         }
+        Ok(connection)
+    }
+
+    /// Configured servers with what each reported at `initialize`; `None` for
+    /// servers that have not been started
+    pub async fn server_infos(&self) -> Vec<(MCPServer, Option<ServerInfo>)> {
+        let connections = self.connections.lock().await;
+        self.servers
+            .iter()
+            .map(|server| {
+                let info = connections
+                    .get(&server.name)
+                    .and_then(|c| c.info.get().cloned());
+                (server.clone(), info)
+            })
+            .collect()
+    }
+
+    /// Health of each configured server, for `/mcp status`
+    pub fn health(&self) -> Vec<(MCPServer, ServerHealth)> {
+        self.servers
+            .iter()
+            .map(|server| (server.clone(), self.supervisor.health(&server.name)))
+            .collect()
+    }
+
+    pub fn log_path(&self, server: &str) -> std::path::PathBuf {
+        self.supervisor.log_path(server)
+    }
+
+    pub fn find_server(&self, name: &str) -> Option<&MCPServer> {
+        self.servers.iter().find(|s| s.name == name)
+    }
 
-        Err("No valid response received".into())
+    /// Stop every server started this session; returns how many were running
+    pub async fn shutdown(&self) -> usize {
+        let connections: Vec<Arc<Connection>> = self
+            .connections
+            .lock()
+            .await
+            .drain()
+            .map(|(_, c)| c)
+            .collect();
+        for connection in &connections {
+            connection.shutdown().await;
+            self.supervisor.stopped(&connection.server);
+        }
+        connections.len()
     }
 
     pub fn list_available_tools(&self) -> Vec<&MCPTool> {
         self.tools.iter().collect()
     }
 
+    pub fn find_tool(&self, name: &str) -> Option<&MCPTool> {
+        self.resolve_tool(name).ok()
+    }
+
+    /// Look a tool up by `server.tool`, `server__tool`, or its bare name when
+    /// only one server has a tool by that name
+    pub fn resolve_tool(&self, name: &str) -> Result<&MCPTool, String> {
+        if let Some(tool) = self.tools.iter().find(|t| t.answers_to(name)) {
+            return Ok(tool);
+        }
+        let matches: Vec<&MCPTool> = self.tools.iter().filter(|t| t.name == name).collect();
+        match matches.as_slice() {
+            [] => Err(format!("Unknown MCP tool: {}", name)),
+            [tool] => Ok(tool),
+            _ => Err(format!(
+                "{} is ambiguous; use one of {}",
+                name,
+                matches
+                    .iter()
+                    .map(|t| t.qualified_name())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    }
+
+    /// Bare tool names offered by more than one server, with those servers
+    pub fn duplicate_tool_names(&self) -> Vec<(String, Vec<String>)> {
+        let mut servers_by_name: Vec<(String, Vec<String>)> = Vec::new();
+        for tool in &self.tools {
+            match servers_by_name
+                .iter_mut()
+                .find(|(name, _)| *name == tool.name)
+            {
+                Some((_, servers)) => servers.push(tool.server.clone()),
+                None => servers_by_name.push((tool.name.clone(), vec![tool.server.clone()])),
+            }
+        }
+        servers_by_name.retain(|(_, servers)| servers.len() > 1);
+        servers_by_name
+    }
+
+    /// Send a request and unwrap its result, turning a JSON-RPC error into `Err`
+    async fn server_request(
+        &self,
+        server: &MCPServer,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value, String> {
+        let response = self
+            .connection(server)
+            .await?
+            .request(method, params)
+            .await?;
+        if let Some(error) = response.error {
+            return Err(format!("{}: {}", server.name, error_message(&error)));
+        }
+        Ok(response.result.unwrap_or_default())
+    }
+
+    /// Every page of a `*/list` method, following `nextCursor`
+    async fn list_all(
+        &self,
+        server: &MCPServer,
+        method: &str,
+        key: &str,
+    ) -> Result<Vec<serde_json::Value>, String> {
+        let mut items = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let params = match &cursor {
+                Some(cursor) => serde_json::json!({ "cursor": cursor }),
+                None => serde_json::json!({}),
+            };
+            let result = self.server_request(server, method, params).await?;
+            if let Some(page) = result.get(key).and_then(|i| i.as_array()) {
+                items.extend(page.iter().cloned());
+            }
+            match result.get("nextCursor").and_then(|c| c.as_str()) {
+                Some(next) if !next.is_empty() && cursor.as_deref() != Some(next) => {
+                    cursor = Some(next.to_string())
+                }
+                _ => return Ok(items),
+            }
+        }
+    }
+
+    /// Servers that advertised `capability` at `initialize`, starting any
+    /// that are not running yet. Servers that fail to start are reported and
+    /// skipped.
+    async fn servers_with(&self, capability: &str) -> Vec<MCPServer> {
+        let mut servers = Vec::new();
+        for server in self.servers.iter().filter(|s| s.enabled) {
+            match self.connection(server).await {
+                Ok(connection) => {
+                    if connection
+                        .info
+                        .get()
+                        .is_some_and(|info| info.has_capability(capability))
+                    {
+                        servers.push(server.clone());
+                    }
+                }
+                Err(e) => eprintln!("Failed to start {}: {}", server.name, e),
+            }
+        }
+        servers
+    }
+
+    pub async fn list_resources(&self) -> Vec<MCPResource> {
+        let mut resources = Vec::new();
+        for server in self.servers_with("resources").await {
+            match self.list_all(&server, "resources/list", "resources").await {
+                Ok(items) => resources.extend(items.iter().map(|r| {
+                    MCPResource {
+                        uri: str_field(r, "uri"),
+                        name: str_field(r, "name"),
+                        description: str_field(r, "description"),
+                        mime_type: r
+                            .get("mimeType")
+                            .and_then(|m| m.as_str())
+                            .map(|m| m.to_string()),
+                        server: server.name.clone(),
+                    }
+                })),
+                Err(e) => eprintln!("Failed to list resources from {}: {}", server.name, e),
+            }
+        }
+        resources
+    }
+
+    pub async fn list_resource_templates(&self) -> Vec<MCPResourceTemplate> {
+        let mut templates = Vec::new();
+        for server in self.servers_with("resources").await {
+            match self
+                .list_all(&server, "resources/templates/list", "resourceTemplates")
+                .await
+            {
+                Ok(items) => templates.extend(items.iter().map(|t| MCPResourceTemplate {
+                    uri_template: str_field(t, "uriTemplate"),
+                    name: str_field(t, "name"),
+                    description: str_field(t, "description"),
+                    server: server.name.clone(),
+                })),
+                // Templates are optional; servers without them answer "method not found"
+                Err(_) => continue,
+            }
+        }
+        templates
+    }
+
+    /// Read a resource as text. The owning server is the one that listed the
+    /// URI, else one with a matching template, else the first that can read it.
+    pub async fn read_resource(&self, uri: &str) -> Result<String, String> {
+        let owner = match self
+            .list_resources()
+            .await
+            .into_iter()
+            .find(|r| r.uri == uri)
+        {
+            Some(resource) => Some(resource.server),
+            None => self
+                .list_resource_templates()
+                .await
+                .into_iter()
+                .find(|t| t.matches(uri))
+                .map(|t| t.server),
+        };
+        let candidates: Vec<MCPServer> = match owner {
+            Some(owner) => self
+                .servers
+                .iter()
+                .filter(|s| s.name == owner)
+                .cloned()
+                .collect(),
+            None => self.servers_with("resources").await,
+        };
+        if candidates.is_empty() {
+            return Err("No MCP server provides resources".to_string());
+        }
+
+        let mut last_error = String::new();
+        for server in &candidates {
+            match self
+                .server_request(server, "resources/read", serde_json::json!({ "uri": uri }))
+                .await
+            {
+                Ok(result) => return Ok(render_resource_contents(&result)),
+                Err(e) => last_error = e,
+            }
+        }
+        Err(last_error)
+    }
+
+    pub async fn list_prompts(&self) -> Vec<MCPPrompt> {
+        let mut prompts = Vec::new();
+        for server in self.servers_with("prompts").await {
+            match self.list_all(&server, "prompts/list", "prompts").await {
+                Ok(items) => prompts.extend(items.iter().map(|p| {
+                    MCPPrompt {
+                        name: str_field(p, "name"),
+                        description: str_field(p, "description"),
+                        arguments: p
+                            .get("arguments")
+                            .and_then(|a| a.as_array())
+                            .map(|arguments| {
+                                arguments
+                                    .iter()
+                                    .map(|a| PromptArgument {
+                                        name: str_field(a, "name"),
+                                        description: str_field(a, "description"),
+                                        required: a
+                                            .get("required")
+                                            .and_then(|r| r.as_bool())
+                                            .unwrap_or(false),
+                                    })
+                                    .collect()
+                            })
+                            .unwrap_or_default(),
+                        server: server.name.clone(),
+                    }
+                })),
+                Err(e) => eprintln!("Failed to list prompts from {}: {}", server.name, e),
+            }
+        }
+        prompts
+    }
+
+    /// Fill in a prompt template and return its messages as text
+    pub async fn get_prompt(
+        &self,
+        name: &str,
+        arguments: &HashMap<String, String>,
+    ) -> Result<String, String> {
+        let prompt = self
+            .list_prompts()
+            .await
+            .into_iter()
+            .find(|p| p.name == name)
+            .ok_or_else(|| format!("Prompt not found: {}", name))?;
+        let missing: Vec<&str> = prompt
+            .arguments
+            .iter()
+            .filter(|a| a.required && !arguments.contains_key(&a.name))
+            .map(|a| a.name.as_str())
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!(
+                "Missing argument(s) {}; usage: {}",
+                missing.join(", "),
+                prompt.usage()
+            ));
+        }
+
+        let server = self
+            .servers
+            .iter()
+            .find(|s| s.name == prompt.server)
+            .ok_or("Server not found")?;
+        let result = self
+            .server_request(
+                server,
+                "prompts/get",
+                serde_json::json!({ "name": name, "arguments": arguments }),
+            )
+            .await?;
+        Ok(render_prompt_messages(&result))
+    }
+
     pub async fn call_tool(
         &self,
         tool_name: &str,
         params: serde_json::Value,
     ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
-        let tool = self
-            .tools
-            .iter()
-            .find(|t| t.name == tool_name)
-            .ok_or("Tool not found")?;
+        self.call_tool_with(tool_name, params, None, std::future::pending())
+            .await
+    }
+
+    /// `call_tool`, forwarding the server's progress notifications to
+    /// `progress` and cancelling the call when `cancel` completes
+    pub async fn call_tool_with<F>(
+        &self,
+        tool_name: &str,
+        params: serde_json::Value,
+        progress: Option<UnboundedSender<Progress>>,
+        cancel: F,
+    ) -> Result<serde_json::Value, Box<dyn std::error::Error>>
+    where
+        F: std::future::Future<Output = ()>,
+    {
+        let start = std::time::Instant::now();
+        let arguments = params.clone();
+        // Box<dyn Error> is not Send, so flatten it before awaiting the audit write
+        let result = self
+            .call_tool_unaudited(tool_name, params, progress, cancel)
+            .await
+            .map_err(|e| e.to_string());
+
+        let (server, name) = match self.resolve_tool(tool_name) {
+            Ok(tool) => (tool.server.as_str(), tool.name.as_str()),
+            Err(_) => ("unknown", tool_name),
+        };
+        let (success, bytes_out) = match &result {
+            Ok(value) => (
+                !value
+                    .get("isError")
+                    .and_then(|e| e.as_bool())
+                    .unwrap_or(false),
+                value.to_string().len(),
+            ),
+            Err(e) => (false, e.len()),
+        };
+        let elapsed = start.elapsed();
+        if server != "unknown" {
+            self.supervisor.record_call(server, name, elapsed, success);
+            if let Err(e) = &result {
+                self.supervisor.failed(server, e.clone());
+            }
+        }
+        crate::audit::record(crate::audit::AuditRecord::new(
+            &format!("mcp:{}", server),
+            name,
+            &arguments,
+            success,
+            elapsed,
+            bytes_out,
+        ))
+        .await;
+
+        result.map_err(|e| e.into())
+    }
+
+    async fn call_tool_unaudited<F>(
+        &self,
+        tool_name: &str,
+        params: serde_json::Value,
+        progress: Option<UnboundedSender<Progress>>,
+        cancel: F,
+    ) -> Result<serde_json::Value, Box<dyn std::error::Error>>
+    where
+        F: std::future::Future<Output = ()>,
+    {
+        let tool = self.resolve_tool(tool_name)?;
 
         let server = self
             .servers
@@ -179,64 +1755,73 @@ impl MCPClient {
             .find(|s| s.name == tool.server)
             .ok_or("Server not found")?;
 
-        let request = MCPRequest {
-            jsonrpc: "2.0".to_string(),
-            id: 2,
-            method: "tools/call".to_string(),
-            params: serde_json::json!({
-                "name": tool_name,
-                "arguments": params
-            }),
-        };
-
-        let response = self.send_request(server, &request).await?;
+        let connection = self.connection(server).await?;
+        let response = connection
+            .request_until(
+                "tools/call",
+                serde_json::json!({
+                    "name": tool.name,
+                    "arguments": params
+                }),
+                progress,
+                cancel,
+            )
+            .await?;
 
         if let Some(result) = response.result {
             return Ok(result);
         }
 
         if let Some(error) = response.error {
-            return Err(format!("MCP error: {:?}", error).into());
+            return Err(format!("MCP error: {}", error_message(&error)).into());
         }
 
         Err("No result from MCP server".into())
     }
-    pub async fn call_tool_streaming<F>(
+    /// `call_tool` for the terminal: a spinner that becomes a progress bar
+    /// once the server reports a total, and Ctrl+C cancels the call
+    pub async fn call_tool_streaming(
         &self,
         tool_name: &str,
         params: serde_json::Value,
-        mut callback: F,
-    ) -> Result<serde_json::Value, Box<dyn std::error::Error>>
-    where
-        F: FnMut(&str),
-    {
-        callback(&format!("⏳ Calling MCP tool: {}\n", tool_name));
-        let result = self.call_tool(tool_name, params).await?;
-        callback(&"✅ Tool completed\n".to_string());
-        Ok(result)
+    ) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        let bar = crate::progress::create_spinner(&format!("Calling {}", tool_name));
+        ACTIVE_BARS.lock().unwrap().push(bar.clone());
+        let (tx, mut updates) = mpsc::unbounded_channel();
+        let call = self.call_tool_with(tool_name, params, Some(tx), until_interrupted());
+        tokio::pin!(call);
+
+        let result = loop {
+            tokio::select! {
+                result = &mut call => break result,
+                Some(update) = updates.recv() => show_progress(&bar, tool_name, &update),
+            }
+        };
+        bar.finish_and_clear();
+        ACTIVE_BARS
+            .lock()
+            .unwrap()
+            .retain(|active| !active.is_finished());
+        result
     }
 
     pub async fn call_tools_parallel(
         &self,
         tool_calls: Vec<(&str, serde_json::Value)>,
     ) -> Vec<Result<serde_json::Value, String>> {
-        use tokio::time::{timeout, Duration};
-        
+        // Each request is bounded by its server's `timeout_secs`
         let handles: Vec<_> = tool_calls
             .into_iter()
             .map(|(name, params)| {
                 let name = name.to_string();
                 let params = params.clone();
-                let tools = self.tools.clone();
-                let servers = self.servers.clone();
-                
+                let client = self.clone();
+
                 tokio::spawn(async move {
-                    let client = MCPClient { servers, tools };
-                    match timeout(Duration::from_secs(30), client.call_tool(&name, params)).await {
-                        Ok(Ok(result)) => Ok(result),
-                        Ok(Err(e)) => Err(e.to_string()),
-                        Err(_) => Err(format!("Timeout calling {}", name)),
-                    }
+                    client
+                        .call_tool(&name, params)
+                        .await
+                        .map_err(|e| e.to_string())
                 })
             })
             .collect();
@@ -264,6 +1849,601 @@ mod tests {
         assert_eq!(results.len(), 2);
     }
 
+    fn stub_client_with(extra_args: &[&str]) -> MCPClient {
+        let mut args = vec![format!(
+            "{}/tests/fixtures/mcp_stub_server.py",
+            env!("CARGO_MANIFEST_DIR")
+        )];
+        args.extend(extra_args.iter().map(|a| a.to_string()));
+
+        let mut client = MCPClient::new();
+        client.servers.push(MCPServer {
+            name: "stub".to_string(),
+            command: "python3".to_string(),
+            args,
+            ..Default::default()
+        });
+        let log_dir = std::env::temp_dir().join(format!("wiseowl_mcp_logs_{}", std::process::id()));
+        client.supervisor = Supervisor::new(log_dir);
+        client
+    }
+
+    fn stub_client() -> MCPClient {
+        stub_client_with(&[])
+    }
+
+    #[tokio::test]
+    async fn test_initialize_handshake() {
+        let mut client = stub_client();
+        assert!(client.server_infos().await[0].1.is_none());
+
+        // The stub rejects tools/list unless the handshake happened first
+        client.discover_tools().await.unwrap();
+        assert_eq!(client.list_available_tools().len(), 5);
+        let echo = client.find_tool("echo").unwrap();
+        assert_eq!(echo.input_schema["required"][0], "text");
+
+        let infos = client.server_infos().await;
+        let info = infos[0].1.as_ref().unwrap();
+        assert_eq!(info.name, "stub-server");
+        assert_eq!(info.protocol_version, SUPPORTED_PROTOCOL_VERSIONS[0]);
+        assert!(info.has_capability("tools"));
+        assert!(!info.has_capability("sampling"));
+        assert_eq!(info.instructions.as_deref(), Some("Test fixture"));
+        client.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_protocol_version_is_rejected() {
+        let client = stub_client_with(&["--protocol", "1999-01-01"]);
+        let server = client.servers[0].clone();
+        let error = client.connection(&server).await.err().unwrap();
+        assert!(error.contains("unsupported protocol version 1999-01-01"));
+        assert_eq!(client.shutdown().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_server_process_is_reused() {
+        crate::audit::use_test_dir();
+        let mut client = stub_client();
+        client.discover_tools().await.unwrap();
+        assert_eq!(client.list_available_tools().len(), 5);
+
+        // The counter lives in the server process, so it only grows if every
+        // call reaches the same process
+        for expected in ["1", "2"] {
+            let result = client
+                .call_tool("counter", serde_json::json!({}))
+                .await
+                .unwrap();
+            assert_eq!(result["content"][0]["text"], expected);
+        }
+
+        let results = client
+            .call_tools_parallel(vec![
+                ("echo", serde_json::json!({"text": "a"})),
+                ("echo", serde_json::json!({"text": "b"})),
+            ])
+            .await;
+        assert_eq!(results[0].as_ref().unwrap()["content"][0]["text"], "a");
+        assert_eq!(results[1].as_ref().unwrap()["content"][0]["text"], "b");
+
+        assert_eq!(client.shutdown().await, 1);
+        assert_eq!(client.shutdown().await, 0);
+    }
+
+    #[test]
+    fn test_render_content_blocks() {
+        let result = serde_json::json!({
+            "content": [
+                {"type": "text", "text": "first"},
+                {"type": "image", "mimeType": "image/png", "data": "aGk="},
+                {"type": "resource", "resource": {"uri": "file:///a.txt", "text": "inline"}},
+                {"type": "resource_link", "uri": "file:///b.txt", "name": "b.txt"}
+            ]
+        });
+        assert_eq!(
+            render_content(&result),
+            "first\n[image image/png, 4 bytes base64]\ninline\n[resource link file:///b.txt b.txt]"
+        );
+        assert_eq!(
+            render_content(&serde_json::json!({"content": [], "structuredContent": {"n": 1}})),
+            "{\n  \"n\": 1\n}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_server_env_cwd_and_enabled() {
+        crate::audit::use_test_dir();
+        std::env::set_var("WISEOWL_STUB_SECRET", "s3cret");
+        let mut client = stub_client();
+        client.servers[0].env = HashMap::from([(
+            "STUB_TOKEN".to_string(),
+            "token-${WISEOWL_STUB_SECRET}".to_string(),
+        )]);
+        client.servers[0].cwd = Some(std::env::temp_dir().display().to_string());
+        client.servers[0].timeout_secs = Some(5);
+
+        let mut disabled = client.servers[0].clone();
+        disabled.name = "off".to_string();
+        disabled.enabled = false;
+        client.servers.push(disabled.clone());
+
+        client.discover_tools().await.unwrap();
+        assert!(client
+            .list_available_tools()
+            .iter()
+            .all(|t| t.server == "stub"));
+        let error = client.connection(&disabled).await.err().unwrap();
+        assert_eq!(error, "off is disabled");
+
+        let result = client
+            .call_tool("environment", serde_json::json!({"name": "STUB_TOKEN"}))
+            .await
+            .unwrap();
+        let cwd = std::fs::canonicalize(std::env::temp_dir()).unwrap();
+        assert_eq!(
+            result["content"][0]["text"],
+            format!("{}|token-s3cret", cwd.display())
+        );
+        assert_eq!(client.shutdown().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_resources_and_prompts() {
+        let client = stub_client();
+
+        // The stub serves resources/list in two pages
+        let resources = client.list_resources().await;
+        let uris: Vec<&str> = resources.iter().map(|r| r.uri.as_str()).collect();
+        assert_eq!(uris, ["stub://notes.txt", "stub://logo.png"]);
+        assert_eq!(resources[0].mime_type.as_deref(), Some("text/plain"));
+
+        let templates = client.list_resource_templates().await;
+        assert_eq!(templates[0].uri_template, "stub://greeting/{name}");
+        assert!(templates[0].matches("stub://greeting/owl"));
+        assert!(!templates[0].matches("stub://notes.txt"));
+
+        assert_eq!(
+            client.read_resource("stub://notes.txt").await.unwrap(),
+            "remember the milk"
+        );
+        assert_eq!(
+            client.read_resource("stub://greeting/owl").await.unwrap(),
+            "Hello, owl!"
+        );
+        assert!(client
+            .read_resource("stub://logo.png")
+            .await
+            .unwrap()
+            .starts_with("[binary stub://logo.png image/png"));
+        assert!(client.read_resource("stub://missing").await.is_err());
+
+        let prompts = client.list_prompts().await;
+        assert_eq!(prompts[0].usage(), "review <code> [focus]");
+
+        let error = client
+            .get_prompt("review", &HashMap::new())
+            .await
+            .unwrap_err();
+        assert!(error.contains("Missing argument(s) code"));
+        let arguments = parse_prompt_arguments(&["code=fn", "main()", "{}"]);
+        assert_eq!(
+            client.get_prompt("review", &arguments).await.unwrap(),
+            "user: Review this code:\nfn main() {}"
+        );
+        client.shutdown().await;
+    }
+
+    /// Start the HTTP stand-in and return it with its base URL
+    async fn http_stub(args: &[&str]) -> (Child, String) {
+        let mut child = Command::new("python3")
+            .arg(format!(
+                "{}/tests/fixtures/mcp_http_stub_server.py",
+                env!("CARGO_MANIFEST_DIR")
+            ))
+            .args(args)
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let port = BufReader::new(stdout)
+            .lines()
+            .next_line()
+            .await
+            .unwrap()
+            .unwrap();
+        (child, format!("http://127.0.0.1:{}", port))
+    }
+
+    fn http_client(url: String, transport: Option<&str>, token: Option<&str>) -> MCPClient {
+        let mut client = MCPClient::new();
+        client.servers.push(MCPServer {
+            name: "stub".to_string(),
+            url: Some(url),
+            transport: transport.map(|t| t.to_string()),
+            headers: token
+                .map(|t| HashMap::from([("Authorization".to_string(), format!("Bearer {}", t))]))
+                .unwrap_or_default(),
+            ..Default::default()
+        });
+        client
+    }
+
+    async fn counter(client: &MCPClient) -> Result<serde_json::Value, String> {
+        client
+            .call_tool("counter", serde_json::json!({}))
+            .await
+            .map(|r| r["content"][0]["text"].clone())
+            .map_err(|e| e.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_streamable_http_sessions_and_headers() {
+        crate::audit::use_test_dir();
+        let (_server, base) = http_stub(&["--token", "secret"]).await;
+
+        let unauthorized = http_client(format!("{}/mcp", base), None, None);
+        let server = unauthorized.servers[0].clone();
+        let error = unauthorized.connection(&server).await.err().unwrap();
+        assert!(error.contains("401"), "{}", error);
+
+        let mut client = http_client(format!("{}/mcp", base), None, Some("secret"));
+        client.discover_tools().await.unwrap();
+        assert_eq!(client.list_available_tools().len(), 5);
+        assert_eq!(counter(&client).await.unwrap(), "1");
+
+        // The server forgets the session: that request fails, the next one
+        // starts a new session
+        let connection = client.connection(&client.servers[0]).await.unwrap();
+        let Transport::Http(http) = &*connection.transport else {
+            panic!("expected an HTTP transport");
+        };
+        let session_id = http.session_id().unwrap();
+        reqwest::Client::new()
+            .delete(format!("{}/mcp", base))
+            .header("Authorization", "Bearer secret")
+            .header("Mcp-Session-Id", &session_id)
+            .send()
+            .await
+            .unwrap();
+        assert!(counter(&client).await.unwrap_err().contains("expired"));
+        assert_eq!(counter(&client).await.unwrap(), "2");
+
+        let connection = client.connection(&client.servers[0]).await.unwrap();
+        let Transport::Http(http) = &*connection.transport else {
+            panic!("expected an HTTP transport");
+        };
+        assert_ne!(http.session_id().unwrap(), session_id);
+        assert_eq!(client.shutdown().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_streamable_http_event_stream_responses() {
+        crate::audit::use_test_dir();
+        let (_server, base) = http_stub(&["--stream-responses"]).await;
+        let mut client = http_client(format!("{}/mcp", base), Some("http"), None);
+        client.discover_tools().await.unwrap();
+        let result = client
+            .call_tool("echo", serde_json::json!({"text": "streamed"}))
+            .await
+            .unwrap();
+        assert_eq!(result["content"][0]["text"], "streamed");
+        client.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_legacy_sse_transport_reconnects() {
+        crate::audit::use_test_dir();
+        // Each event stream closes after three messages: initialize,
+        // tools/list and one tool call
+        let (_server, base) = http_stub(&["--drop-after", "3"]).await;
+        let mut client = http_client(format!("{}/sse", base), Some("sse"), None);
+        client.discover_tools().await.unwrap();
+        let connection = client.connection(&client.servers[0]).await.unwrap();
+        assert_eq!(counter(&client).await.unwrap(), "1");
+
+        for _ in 0..50 {
+            if !connection.is_alive().await {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(!connection.is_alive().await);
+        assert_eq!(counter(&client).await.unwrap(), "2");
+        client.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_http_reconnect_policy_gives_up() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let mut client = http_client(format!("http://127.0.0.1:{}/sse", port), Some("sse"), None);
+        client.servers[0].reconnect = ReconnectPolicy {
+            max_attempts: 2,
+            initial_delay_ms: 10,
+        };
+        let server = client.servers[0].clone();
+        let error = client.connection(&server).await.err().unwrap();
+        assert!(error.contains("after 3 attempt(s)"), "{}", error);
+        assert_eq!(ReconnectPolicy::default().delay(2), Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_namespaced_tool_names() {
+        crate::audit::use_test_dir();
+        let mut client = stub_client();
+        let mut other = client.servers[0].clone();
+        other.name = "other".to_string();
+        client.servers.push(other);
+        client.discover_tools().await.unwrap();
+
+        assert_eq!(client.list_available_tools().len(), 10);
+        assert_eq!(
+            client.duplicate_tool_names(),
+            vec![
+                (
+                    "counter".to_string(),
+                    vec!["stub".to_string(), "other".to_string()]
+                ),
+                (
+                    "echo".to_string(),
+                    vec!["stub".to_string(), "other".to_string()]
+                ),
+                (
+                    "environment".to_string(),
+                    vec!["stub".to_string(), "other".to_string()]
+                ),
+                (
+                    "work".to_string(),
+                    vec!["stub".to_string(), "other".to_string()]
+                ),
+                (
+                    "summarize".to_string(),
+                    vec!["stub".to_string(), "other".to_string()]
+                ),
+            ]
+        );
+        let error = client.resolve_tool("echo").unwrap_err();
+        assert_eq!(error, "echo is ambiguous; use one of stub.echo, other.echo");
+        assert_eq!(client.resolve_tool("other.echo").unwrap().server, "other");
+        assert_eq!(client.resolve_tool("stub__echo").unwrap().server, "stub");
+        assert!(client.resolve_tool("stub.missing").is_err());
+
+        // Each server keeps its own counter, and is sent the bare tool name
+        for (name, expected) in [
+            ("stub.counter", "1"),
+            ("other__counter", "1"),
+            ("stub.counter", "2"),
+        ] {
+            let result = client.call_tool(name, serde_json::json!({})).await.unwrap();
+            assert_eq!(result["content"][0]["text"], expected);
+        }
+
+        // With one server left, the bare name is unambiguous again
+        client.tools.retain(|t| t.server == "stub");
+        assert_eq!(
+            client.resolve_tool("echo").unwrap().qualified_name(),
+            "stub.echo"
+        );
+        client.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_progress_logging_and_cancellation() {
+        crate::audit::use_test_dir();
+        let mut client = stub_client();
+        client.log_level = LogLevel::Error;
+        client.servers[0].timeout_secs = Some(1);
+        client.discover_tools().await.unwrap();
+
+        let (tx, mut updates) = mpsc::unbounded_channel();
+        client
+            .call_tool_with(
+                "work",
+                serde_json::json!({}),
+                Some(tx),
+                std::future::pending(),
+            )
+            .await
+            .unwrap();
+        let steps: Vec<Progress> = std::iter::from_fn(|| updates.try_recv().ok()).collect();
+        assert_eq!(
+            steps,
+            [1.0, 2.0]
+                .map(|step| Progress {
+                    progress: step,
+                    total: Some(2.0),
+                    message: Some(format!("step {}", step)),
+                })
+                .to_vec()
+        );
+
+        let cancelled = client
+            .call_tool_with(
+                "work",
+                serde_json::json!({"hang": true}),
+                None,
+                tokio::time::sleep(Duration::from_millis(50)),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(cancelled.to_string(), "tools/call cancelled");
+        let timed_out = client
+            .call_tool("work", serde_json::json!({"hang": true}))
+            .await
+            .unwrap_err();
+        assert!(timed_out.to_string().contains("within 1s"), "{}", timed_out);
+
+        // The server was told the level and about both abandoned requests
+        let result = client
+            .call_tool("work", serde_json::json!({}))
+            .await
+            .unwrap();
+        let report: serde_json::Value =
+            serde_json::from_str(result["content"][0]["text"].as_str().unwrap()).unwrap();
+        assert_eq!(report["level"], "error");
+        assert_eq!(report["cancelled"].as_array().unwrap().len(), 2);
+        client.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_crashed_server_is_logged_and_restarted() {
+        crate::audit::use_test_dir();
+        let mut client = stub_client_with(&["--crash-on", "counter"]);
+        let log_dir =
+            std::env::temp_dir().join(format!("wiseowl_mcp_crash_logs_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&log_dir);
+        client.supervisor = Supervisor::new(log_dir.clone());
+        client.servers[0].reconnect.initial_delay_ms = 10;
+        client.discover_tools().await.unwrap();
+        client
+            .call_tool("echo", serde_json::json!({"text": "hi"}))
+            .await
+            .unwrap();
+
+        let crashed = client.call_tool("counter", serde_json::json!({})).await;
+        assert!(crashed.is_err());
+        let mut health = client.health().remove(0).1;
+        for _ in 0..100 {
+            if health.restarts == 1 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            health = client.health().remove(0).1;
+        }
+        assert_eq!(health.restarts, 1);
+        assert_eq!(health.state, crate::mcp_supervisor::ServerState::Running);
+        let last_error = health.last_error.unwrap();
+        assert!(last_error.contains("exit status: 3"), "{}", last_error);
+        assert_eq!(health.tools["echo"].calls, 1);
+        assert_eq!(health.tools["counter"].errors, 1);
+
+        // The restarted server answers, and both runs' stderr is in the log
+        client
+            .call_tool("echo", serde_json::json!({"text": "again"}))
+            .await
+            .unwrap();
+        assert_eq!(client.health()[0].1.tools["echo"].calls, 2);
+        let log = std::fs::read_to_string(client.log_path("stub")).unwrap();
+        assert!(log.contains("fatal: simulated crash in counter"), "{}", log);
+        assert_eq!(log.matches("stub-server starting").count(), 2, "{}", log);
+
+        client.shutdown().await;
+        assert_eq!(
+            client.health()[0].1.state,
+            crate::mcp_supervisor::ServerState::Stopped
+        );
+        let _ = std::fs::remove_dir_all(&log_dir);
+    }
+
+    /// Answers one Ollama `/api/chat` request with `content`, handing the
+    /// request body to the test
+    async fn fake_ollama(content: &str) -> (String, oneshot::Receiver<serde_json::Value>) {
+        use tokio::io::AsyncReadExt;
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let body = serde_json::json!({
+            "message": {"role": "assistant", "content": content},
+            "done_reason": "stop"
+        })
+        .to_string();
+        let (tx, rx) = oneshot::channel();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut chunk = [0u8; 4096];
+            let request = loop {
+                let n = socket.read(&mut chunk).await.unwrap();
+                request.extend_from_slice(&chunk[..n]);
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                    let length = head
+                        .lines()
+                        .find_map(|l| {
+                            l.to_lowercase()
+                                .strip_prefix("content-length: ")
+                                .map(|v| v.parse::<usize>().unwrap())
+                        })
+                        .unwrap_or(0);
+                    if body.len() >= length {
+                        break serde_json::from_str(body).unwrap();
+                    }
+                }
+            };
+            let _ = tx.send(request);
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+        (url, rx)
+    }
+
+    #[tokio::test]
+    async fn test_sampling_uses_local_model_after_approval() {
+        crate::audit::use_test_dir();
+        let mut client = stub_client();
+        client.discover_tools().await.unwrap();
+        let text = |result: serde_json::Value| result["content"][0]["text"].clone();
+
+        // Without a model the capability is not advertised
+        let result = client
+            .call_tool("summarize", serde_json::json!({"text": "long"}))
+            .await
+            .unwrap();
+        assert_eq!(text(result), "client does not support sampling");
+        client.shutdown().await;
+
+        let (url, request) = fake_ollama("It is short.").await;
+        client.sampler = Some(Sampler {
+            model: "llama3".to_string(),
+            url,
+            approve: |_| true,
+        });
+        let result = client
+            .call_tool("summarize", serde_json::json!({"text": "a long document"}))
+            .await
+            .unwrap();
+        assert_eq!(text(result), "It is short. (llama3)");
+        let request = request.await.unwrap();
+        assert_eq!(request["model"], "llama3");
+        assert_eq!(request["options"]["num_predict"], 50);
+        assert_eq!(
+            request["messages"],
+            serde_json::json!([
+                {"role": "system", "content": "You summarize."},
+                {"role": "user", "content": "a long document"}
+            ])
+        );
+
+        client.sampler.as_mut().unwrap().approve = |_| false;
+        client.shutdown().await;
+        let result = client
+            .call_tool("summarize", serde_json::json!({"text": "secret"}))
+            .await
+            .unwrap();
+        assert_eq!(
+            text(result),
+            "sampling failed: User rejected sampling request"
+        );
+        client.shutdown().await;
+    }
+
+    #[test]
+    fn test_log_levels() {
+        assert_eq!(LogLevel::from_name("notice"), Some(LogLevel::Notice));
+        assert_eq!(LogLevel::from_name("verbose"), None);
+        assert!(LogLevel::Debug < LogLevel::Warning);
+        assert!(LogLevel::Emergency > LogLevel::Error);
+    }
+
     #[test]
     fn test_mcp_client_new() {
         let client = MCPClient::new();
@@ -0,0 +1,302 @@
+use crate::mcp::parse_prompt_arguments;
+use serde_json::{Map, Value};
+
+/// Arguments for `/mcp call`: a JSON object as-is, or `key=value` words
+/// converted to the types the tool's `inputSchema` declares
+pub fn parse_tool_arguments(schema: &Value, text: &str) -> Result<Value, String> {
+    let text = text.trim();
+    let arguments = if text.starts_with('{') {
+        match serde_json::from_str::<Value>(text) {
+            Ok(Value::Object(arguments)) => arguments,
+            Ok(_) => return Err("Arguments must be a JSON object".to_string()),
+            Err(e) => return Err(format!("Invalid JSON arguments: {}", e)),
+        }
+    } else {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        if let Some(word) = words.first().filter(|w| !w.contains('=')) {
+            return Err(format!("Expected key=value or a JSON object, got {}", word));
+        }
+        let mut arguments = Map::new();
+        for (key, raw) in parse_prompt_arguments(&words) {
+            let value = coerce(schema, &key, &raw)?;
+            arguments.insert(key, value);
+        }
+        arguments
+    };
+
+    let missing = missing_required(schema, &arguments);
+    if !missing.is_empty() {
+        return Err(format!(
+            "Missing required argument(s): {}",
+            missing.join(", ")
+        ));
+    }
+    Ok(Value::Object(arguments))
+}
+
+/// Convert one `key=value` to the type of `key` in `schema`
+pub fn coerce(schema: &Value, key: &str, raw: &str) -> Result<Value, String> {
+    let properties = schema["properties"].as_object();
+    let property = match properties.and_then(|p| p.get(key)) {
+        Some(property) => property,
+        None if properties.is_some_and(|p| !p.is_empty())
+            && schema["additionalProperties"] != Value::Bool(true) =>
+        {
+            let known: Vec<&str> = properties.unwrap().keys().map(|k| k.as_str()).collect();
+            return Err(format!(
+                "Unknown argument {}; expected one of {}",
+                key,
+                known.join(", ")
+            ));
+        }
+        None => &Value::Null,
+    };
+    let value = coerce_to(property, key, unquote(raw.trim()))?;
+
+    if let Some(choices) = property["enum"].as_array().filter(|c| !c.is_empty()) {
+        if !choices.contains(&value) {
+            let choices: Vec<String> = choices.iter().map(display).collect();
+            return Err(format!("{} must be one of {}", key, choices.join(", ")));
+        }
+    }
+    Ok(value)
+}
+
+fn coerce_to(property: &Value, key: &str, raw: &str) -> Result<Value, String> {
+    match property_type(property) {
+        Some("integer") => raw
+            .parse::<i64>()
+            .map(Value::from)
+            .map_err(|_| format!("{} must be an integer, got {}", key, raw)),
+        Some("number") => raw
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(Value::Number)
+            .ok_or_else(|| format!("{} must be a number, got {}", key, raw)),
+        Some("boolean") => match raw.to_lowercase().as_str() {
+            "true" | "yes" | "y" | "1" | "on" => Ok(Value::Bool(true)),
+            "false" | "no" | "n" | "0" | "off" => Ok(Value::Bool(false)),
+            _ => Err(format!("{} must be true or false, got {}", key, raw)),
+        },
+        Some("array") if raw.starts_with('[') => match serde_json::from_str(raw) {
+            Ok(Value::Array(items)) => Ok(Value::Array(items)),
+            _ => Err(format!("{} must be a JSON array or a, b, c", key)),
+        },
+        Some("array") => raw
+            .split(',')
+            .map(|item| item.trim())
+            .filter(|item| !item.is_empty())
+            .map(|item| coerce_to(&property["items"], key, unquote(item)))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array),
+        Some("object") => match serde_json::from_str(raw) {
+            Ok(Value::Object(object)) => Ok(Value::Object(object)),
+            _ => Err(format!("{} must be a JSON object", key)),
+        },
+        Some("null") => Ok(Value::Null),
+        _ => Ok(Value::String(raw.to_string())),
+    }
+}
+
+/// `"type": "string"`, or the first non-null type of `"type": ["string", "null"]`
+fn property_type(property: &Value) -> Option<&str> {
+    match &property["type"] {
+        Value::String(kind) => Some(kind),
+        Value::Array(kinds) => kinds
+            .iter()
+            .filter_map(|k| k.as_str())
+            .find(|k| *k != "null"),
+        _ => None,
+    }
+}
+
+fn unquote(raw: &str) -> &str {
+    for quote in ['"', '\''] {
+        if raw.len() >= 2 && raw.starts_with(quote) && raw.ends_with(quote) {
+            return &raw[1..raw.len() - 1];
+        }
+    }
+    raw
+}
+
+fn display(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn required(schema: &Value) -> Vec<&str> {
+    schema["required"]
+        .as_array()
+        .map(|r| r.iter().filter_map(|name| name.as_str()).collect())
+        .unwrap_or_default()
+}
+
+fn missing_required(schema: &Value, arguments: &Map<String, Value>) -> Vec<String> {
+    required(schema)
+        .into_iter()
+        .filter(|name| !arguments.contains_key(*name))
+        .map(|name| name.to_string())
+        .collect()
+}
+
+/// Ask for each required argument with `ask`, which shows a prompt and
+/// returns the answer, or `None` to give up. Invalid answers are asked again;
+/// an empty answer takes the property's default if it has one.
+pub fn prompt_for_arguments<F>(schema: &Value, mut ask: F) -> Result<Value, String>
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    let mut arguments = Map::new();
+    for name in required(schema) {
+        let property = &schema["properties"][name];
+        let prompt = field_prompt(name, property);
+        loop {
+            let answer = ask(&prompt).ok_or("Cancelled")?;
+            if answer.trim().is_empty() {
+                match property.get("default") {
+                    Some(default) => {
+                        arguments.insert(name.to_string(), default.clone());
+                        break;
+                    }
+                    None => {
+                        println!("{} is required", name);
+                        continue;
+                    }
+                }
+            }
+            match coerce(schema, name, &answer) {
+                Ok(value) => {
+                    arguments.insert(name.to_string(), value);
+                    break;
+                }
+                Err(e) => println!("❌ {}", e),
+            }
+        }
+    }
+    Ok(Value::Object(arguments))
+}
+
+/// e.g. "path (string) - File to read: " or "mode (one of r, w) [r]: "
+fn field_prompt(name: &str, property: &Value) -> String {
+    let kind = match property["enum"].as_array() {
+        Some(choices) => format!(
+            "one of {}",
+            choices.iter().map(display).collect::<Vec<_>>().join(", ")
+        ),
+        None => property_type(property).unwrap_or("string").to_string(),
+    };
+    let mut prompt = format!("{} ({})", name, kind);
+    if let Some(description) = property["description"].as_str() {
+        prompt.push_str(&format!(" - {}", description));
+    }
+    if let Some(default) = property.get("default") {
+        prompt.push_str(&format!(" [{}]", display(default)));
+    }
+    prompt.push_str(": ");
+    prompt
+}
+
+/// A `tools/call` result for the terminal: structured content as pretty
+/// JSON, otherwise the content blocks, with JSON text pretty-printed
+pub fn render_tool_result(result: &Value) -> String {
+    if let Some(structured) = result.get("structuredContent") {
+        return serde_json::to_string_pretty(structured).unwrap_or_default();
+    }
+    let text = crate::mcp::render_content(result);
+    match serde_json::from_str::<Value>(text.trim()) {
+        Ok(json) if json.is_object() || json.is_array() => {
+            serde_json::to_string_pretty(&json).unwrap_or(text)
+        }
+        _ => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "path": {"type": "string", "description": "File to read"},
+                "limit": {"type": "integer"},
+                "ratio": {"type": ["number", "null"]},
+                "recursive": {"type": "boolean"},
+                "tags": {"type": "array", "items": {"type": "integer"}},
+                "mode": {"type": "string", "enum": ["r", "w"], "default": "r"}
+            },
+            "required": ["path", "mode"]
+        })
+    }
+
+    #[test]
+    fn test_parse_key_value_and_json_arguments() {
+        let arguments = parse_tool_arguments(
+            &schema(),
+            r#"path="my notes.txt" limit=5 ratio=0.5 recursive=yes tags=1,2 mode=w"#,
+        )
+        .unwrap();
+        assert_eq!(
+            arguments,
+            json!({"path": "my notes.txt", "limit": 5, "ratio": 0.5,
+                   "recursive": true, "tags": [1, 2], "mode": "w"})
+        );
+
+        let arguments =
+            parse_tool_arguments(&schema(), r#"{"path": "a  b", "mode": "r", "x": 1}"#).unwrap();
+        assert_eq!(arguments["path"], "a  b");
+
+        for (text, error) in [
+            (
+                "path=a limit=five mode=r",
+                "limit must be an integer, got five",
+            ),
+            ("path=a mode=x", "mode must be one of r, w"),
+            ("path=a mode=r colour=red", "Unknown argument colour"),
+            ("limit=1", "Missing required argument(s): path, mode"),
+            (
+                "some words",
+                "Expected key=value or a JSON object, got some",
+            ),
+            ("[1, 2]", "Expected key=value"),
+        ] {
+            let message = parse_tool_arguments(&schema(), text).unwrap_err();
+            assert!(message.starts_with(error), "{}: {}", text, message);
+        }
+    }
+
+    #[test]
+    fn test_prompt_for_required_arguments() {
+        let mut answers = vec!["", "notes.txt", "x", ""].into_iter();
+        let mut prompts = Vec::new();
+        let arguments = prompt_for_arguments(&schema(), |prompt| {
+            prompts.push(prompt.to_string());
+            answers.next().map(|a| a.to_string())
+        })
+        .unwrap();
+        assert_eq!(arguments, json!({"path": "notes.txt", "mode": "r"}));
+        assert_eq!(prompts[0], "path (string) - File to read: ");
+        assert_eq!(prompts[2], "mode (one of r, w) [r]: ");
+        assert_eq!(prompts.len(), 4);
+
+        assert!(prompt_for_arguments(&schema(), |_| None).is_err());
+    }
+
+    #[test]
+    fn test_render_tool_result() {
+        let structured = json!({
+            "content": [{"type": "text", "text": "{\"count\":1}"}],
+            "structuredContent": {"count": 1}
+        });
+        assert_eq!(render_tool_result(&structured), "{\n  \"count\": 1\n}");
+        let json_text = json!({"content": [{"type": "text", "text": "[1,2]"}]});
+        assert_eq!(render_tool_result(&json_text), "[\n  1,\n  2\n]");
+        let plain = json!({"content": [{"type": "text", "text": "done"}]});
+        assert_eq!(render_tool_result(&plain), "done");
+    }
+}
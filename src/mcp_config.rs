@@ -0,0 +1,318 @@
+use crate::mcp::MCPServer;
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+const CONFIG_FILE: &str = "mcp_servers.json";
+
+/// Which config file a server entry comes from. Project entries replace
+/// global entries with the same name.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ConfigScope {
+    /// `~/.wiseowlcli/mcp_servers.json`
+    Global,
+    /// `.wiseowlcli/mcp_servers.json` in the working directory
+    #[default]
+    Project,
+}
+
+impl ConfigScope {
+    pub fn name(self) -> &'static str {
+        match self {
+            ConfigScope::Global => "global",
+            ConfigScope::Project => "project",
+        }
+    }
+
+    pub fn path(self) -> Result<PathBuf, String> {
+        let base = match self {
+            ConfigScope::Global => std::env::var("HOME")
+                .map(PathBuf::from)
+                .map_err(|_| "HOME is not set".to_string())?,
+            ConfigScope::Project => std::env::current_dir().map_err(|e| e.to_string())?,
+        };
+        Ok(config_path(&base))
+    }
+}
+
+pub fn config_path(base: &Path) -> PathBuf {
+    base.join(".wiseowlcli").join(CONFIG_FILE)
+}
+
+/// Where the project config used to live, still read when there is no
+/// `.wiseowlcli/mcp_servers.json`. The first edit migrates it.
+fn legacy_config_path(base: &Path) -> PathBuf {
+    base.join(".ocli").join(CONFIG_FILE)
+}
+
+/// The legacy file `path` replaces, for a `.wiseowlcli/mcp_servers.json` path
+fn legacy_for(path: &Path) -> Option<PathBuf> {
+    let dir = path.parent()?;
+    if dir.file_name()? != ".wiseowlcli" {
+        return None;
+    }
+    Some(legacy_config_path(dir.parent()?))
+}
+
+/// Merge the global and project configs; `home` is `None` when there is no
+/// home directory to read
+pub async fn load_servers(home: Option<&Path>, project: &Path) -> Result<Vec<MCPServer>, String> {
+    let mut servers = match home {
+        Some(home) => read_scope(&config_path(home), ConfigScope::Global).await?,
+        None => Vec::new(),
+    };
+
+    let mut project_path = config_path(project);
+    if !project_path.exists() && legacy_config_path(project).exists() {
+        project_path = legacy_config_path(project);
+    }
+    for server in read_scope(&project_path, ConfigScope::Project).await? {
+        match servers.iter_mut().find(|s| s.name == server.name) {
+            Some(existing) => *existing = server,
+            None => servers.push(server),
+        }
+    }
+    Ok(servers)
+}
+
+async fn read_scope(path: &Path, scope: ConfigScope) -> Result<Vec<MCPServer>, String> {
+    let entries = read_entries(path).await?;
+    let mut servers = Vec::new();
+    for entry in entries {
+        match serde_json::from_value::<MCPServer>(entry) {
+            Ok(mut server) => {
+                server.scope = scope;
+                servers.push(server);
+            }
+            Err(e) => eprintln!("⚠️  Skipping MCP server entry in {}: {}", path.display(), e),
+        }
+    }
+    Ok(servers)
+}
+
+/// The raw `servers` array of a config file; empty if the file does not exist
+async fn read_entries(path: &Path) -> Result<Vec<serde_json::Value>, String> {
+    let content = match tokio::fs::read_to_string(path).await {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    let config: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Invalid JSON in {}: {}", path.display(), e))?;
+    Ok(config["servers"].as_array().cloned().unwrap_or_default())
+}
+
+/// Rewrite a config file's `servers` array. Entries are edited as raw JSON so
+/// fields this version does not know about survive. A file that doesn't exist
+/// yet starts from its legacy `.ocli` entries, so editing never drops them.
+async fn edit_entries<F>(path: &Path, edit: F) -> Result<(), String>
+where
+    F: FnOnce(&mut Vec<serde_json::Value>) -> Result<(), String>,
+{
+    let mut entries = match legacy_for(path) {
+        Some(legacy) if !path.exists() => read_entries(&legacy).await?,
+        _ => read_entries(path).await?,
+    };
+    edit(&mut entries)?;
+
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir)
+            .await
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let config = serde_json::json!({ "servers": entries });
+    let content = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
+    tokio::fs::write(path, content + "\n")
+        .await
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+pub async fn add_server(path: &Path, server: &MCPServer) -> Result<(), String> {
+    let entry = serde_json::to_value(server).map_err(|e| e.to_string())?;
+    edit_entries(path, |entries| {
+        if entries.iter().any(|e| e["name"] == server.name.as_str()) {
+            return Err(format!(
+                "{} already has a server named {}",
+                path.display(),
+                server.name
+            ));
+        }
+        entries.push(entry);
+        Ok(())
+    })
+    .await
+}
+
+pub async fn remove_server(path: &Path, name: &str) -> Result<(), String> {
+    edit_entries(path, |entries| {
+        let before = entries.len();
+        entries.retain(|e| e["name"] != name);
+        if entries.len() == before {
+            return Err(format!("No server named {} in {}", name, path.display()));
+        }
+        Ok(())
+    })
+    .await
+}
+
+pub async fn set_server_enabled(path: &Path, name: &str, enabled: bool) -> Result<(), String> {
+    edit_entries(path, |entries| {
+        let entry = entries
+            .iter_mut()
+            .find(|e| e["name"] == name)
+            .and_then(|e| e.as_object_mut())
+            .ok_or_else(|| format!("No server named {} in {}", name, path.display()))?;
+        if enabled {
+            entry.remove("enabled");
+        } else {
+            entry.insert("enabled".to_string(), serde_json::Value::Bool(false));
+        }
+        Ok(())
+    })
+    .await
+}
+
+/// Replace `${VAR}` with the variable's value. Unset variables become empty
+/// and are returned so the caller can warn about them.
+pub fn expand_env_vars(value: &str) -> (String, Vec<String>) {
+    static VAR: OnceLock<Regex> = OnceLock::new();
+    let pattern = VAR.get_or_init(|| Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)\}").unwrap());
+
+    let mut missing = Vec::new();
+    let expanded = pattern.replace_all(value, |captures: &regex::Captures| {
+        std::env::var(&captures[1]).unwrap_or_else(|_| {
+            missing.push(captures[1].to_string());
+            String::new()
+        })
+    });
+    (expanded.into_owned(), missing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "wiseowl_mcp_config_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_config(base: &Path, servers: serde_json::Value) {
+        let path = config_path(base);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, serde_json::json!({ "servers": servers }).to_string()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_project_config_overrides_global() {
+        let home = scratch_dir("layer_home");
+        let project = scratch_dir("layer_project");
+        write_config(
+            &home,
+            serde_json::json!([
+                {"name": "github", "command": "npx", "env": {"GITHUB_TOKEN": "${GITHUB_TOKEN}"}},
+                {"name": "search", "url": "http://localhost:8931/mcp"}
+            ]),
+        );
+        write_config(
+            &project,
+            serde_json::json!([
+                {"name": "search", "url": "http://devbox:8931/mcp", "enabled": false},
+                {"name": "local", "command": "python3", "cwd": "tools", "timeout_secs": 5}
+            ]),
+        );
+
+        let servers = load_servers(Some(&home), &project).await.unwrap();
+        let names: Vec<&str> = servers.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["github", "search", "local"]);
+        assert_eq!(servers[0].scope, ConfigScope::Global);
+        assert!(servers[0].enabled);
+        assert_eq!(servers[0].env["GITHUB_TOKEN"], "${GITHUB_TOKEN}");
+        assert_eq!(servers[1].scope, ConfigScope::Project);
+        assert_eq!(servers[1].url.as_deref(), Some("http://devbox:8931/mcp"));
+        assert!(!servers[1].enabled);
+        assert_eq!(servers[2].cwd.as_deref(), Some("tools"));
+        assert_eq!(servers[2].timeout_secs, Some(5));
+
+        // Without a .wiseowlcli config the old .ocli location is still read
+        let legacy = scratch_dir("layer_legacy");
+        std::fs::create_dir_all(legacy.join(".ocli")).unwrap();
+        std::fs::write(
+            legacy.join(".ocli").join(CONFIG_FILE),
+            r#"{"servers": [{"name": "old", "command": "old-server"}]}"#,
+        )
+        .unwrap();
+        let servers = load_servers(None, &legacy).await.unwrap();
+        assert_eq!(servers[0].name, "old");
+
+        // The first edit moves the legacy servers into the new file
+        let server = MCPServer {
+            name: "new".to_string(),
+            command: "new-server".to_string(),
+            ..Default::default()
+        };
+        add_server(&config_path(&legacy), &server).await.unwrap();
+        set_server_enabled(&config_path(&legacy), "old", false)
+            .await
+            .unwrap();
+        let servers = load_servers(None, &legacy).await.unwrap();
+        let names: Vec<&str> = servers.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["old", "new"]);
+        assert!(!servers[0].enabled);
+        for dir in [home, project, legacy] {
+            let _ = std::fs::remove_dir_all(dir);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_edit_config_keeps_unknown_fields() {
+        let dir = scratch_dir("edit");
+        let path = config_path(&dir);
+        write_config(
+            &dir,
+            serde_json::json!([{"name": "a", "command": "a-server", "comment": "keep me"}]),
+        );
+
+        let server = MCPServer {
+            name: "b".to_string(),
+            url: Some("http://localhost:1/mcp".to_string()),
+            ..Default::default()
+        };
+        add_server(&path, &server).await.unwrap();
+        assert!(add_server(&path, &server).await.is_err());
+        set_server_enabled(&path, "a", false).await.unwrap();
+
+        let entries = read_entries(&path).await.unwrap();
+        assert_eq!(entries[0]["comment"], "keep me");
+        assert_eq!(entries[0]["enabled"], false);
+        assert_eq!(
+            entries[1],
+            serde_json::json!({"name": "b", "url": "http://localhost:1/mcp"})
+        );
+
+        set_server_enabled(&path, "a", true).await.unwrap();
+        remove_server(&path, "b").await.unwrap();
+        assert!(remove_server(&path, "b").await.is_err());
+        let entries = read_entries(&path).await.unwrap();
+        assert_eq!(
+            entries,
+            vec![serde_json::json!({"name": "a", "command": "a-server", "comment": "keep me"})]
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_expand_env_vars() {
+        std::env::set_var("WISEOWL_TEST_TOKEN", "abc123");
+        let (value, missing) =
+            expand_env_vars("Bearer ${WISEOWL_TEST_TOKEN} ${WISEOWL_TEST_UNSET} $HOME");
+        assert_eq!(value, "Bearer abc123  $HOME");
+        assert_eq!(missing, ["WISEOWL_TEST_UNSET"]);
+    }
+}
@@ -0,0 +1,449 @@
+use crate::mcp::ReconnectPolicy;
+use futures_util::StreamExt;
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+use reqwest::StatusCode;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::task::JoinHandle;
+
+const SESSION_HEADER: &str = "Mcp-Session-Id";
+const PROTOCOL_HEADER: &str = "MCP-Protocol-Version";
+const ENDPOINT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Which HTTP flavour a `url` server speaks
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HttpKind {
+    /// Streamable HTTP (2025-03-26 and later): every message is a POST and
+    /// the answer comes back as JSON or as an event stream
+    Streamable,
+    /// The 2024-11-05 HTTP+SSE transport: a long-lived GET event stream
+    /// carries server messages, and client messages are POSTed to the
+    /// endpoint it announces
+    Sse,
+}
+
+impl HttpKind {
+    pub fn from_config(transport: Option<&str>) -> Result<Self, String> {
+        match transport {
+            None | Some("http") | Some("streamable-http") => Ok(HttpKind::Streamable),
+            Some("sse") => Ok(HttpKind::Sse),
+            Some(other) => Err(format!(
+                "unknown transport \"{}\" (expected \"http\" or \"sse\")",
+                other
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SseEvent {
+    pub event: String,
+    pub data: String,
+}
+
+/// Incremental `text/event-stream` parser; chunks may split lines anywhere
+#[derive(Default)]
+pub struct SseParser {
+    buffer: Vec<u8>,
+    event: String,
+    data: Vec<String>,
+}
+
+impl SseParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.iter().position(|b| *b == b'\n') {
+            let raw: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&raw);
+            let line = line.trim_end_matches(['\n', '\r']);
+
+            if line.is_empty() {
+                if !self.data.is_empty() {
+                    events.push(SseEvent {
+                        event: if self.event.is_empty() {
+                            "message".to_string()
+                        } else {
+                            self.event.clone()
+                        },
+                        data: self.data.join("\n"),
+                    });
+                }
+                self.event.clear();
+                self.data.clear();
+                continue;
+            }
+            if line.starts_with(':') {
+                continue;
+            }
+            let (field, value) = match line.split_once(':') {
+                Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+                None => (line, ""),
+            };
+            match field {
+                "event" => self.event = value.to_string(),
+                "data" => self.data.push(value.to_string()),
+                _ => {}
+            }
+        }
+        events
+    }
+}
+
+/// Where parsed server messages go. Emptied when the transport closes, which
+/// ends the connection's routing task and fails its pending requests.
+type Inbox = Arc<StdMutex<Option<UnboundedSender<serde_json::Value>>>>;
+
+fn deliver(inbox: &Inbox, data: &str) {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(data) else {
+        return;
+    };
+    let messages = match value {
+        serde_json::Value::Array(batch) => batch,
+        message => vec![message],
+    };
+    if let Some(tx) = inbox.lock().unwrap().as_ref() {
+        for message in messages {
+            let _ = tx.send(message);
+        }
+    }
+}
+
+/// Whether sending `message` twice is harmless: the handshake, pings and
+/// reads. Tool calls, notifications and replies to the server never are.
+fn is_idempotent(message: &serde_json::Value) -> bool {
+    match message["method"].as_str() {
+        Some(method) => {
+            matches!(
+                method,
+                "initialize" | "ping" | "resources/read" | "prompts/get"
+            ) || method.ends_with("/list")
+        }
+        None => false,
+    }
+}
+
+/// Forward every `message` event until the stream ends
+async fn forward_events<S, B>(mut stream: S, mut parser: SseParser, inbox: Inbox)
+where
+    S: futures_util::Stream<Item = reqwest::Result<B>> + Unpin,
+    B: AsRef<[u8]>,
+{
+    while let Some(Ok(chunk)) = stream.next().await {
+        for event in parser.push(chunk.as_ref()) {
+            if event.event == "message" {
+                deliver(&inbox, &event.data);
+            }
+        }
+    }
+}
+
+pub struct HttpTransport {
+    kind: HttpKind,
+    url: String,
+    headers: HashMap<String, String>,
+    reconnect: ReconnectPolicy,
+    client: reqwest::Client,
+    session_id: StdMutex<Option<String>>,
+    protocol_version: StdMutex<Option<String>>,
+    /// Legacy SSE only: the URL announced by the `endpoint` event
+    endpoint: StdMutex<Option<String>>,
+    inbox: Inbox,
+    listener: StdMutex<Option<JoinHandle<()>>>,
+}
+
+impl HttpTransport {
+    /// Streamable HTTP has nothing to open until the first POST; legacy SSE
+    /// opens its event stream and waits for the message endpoint
+    pub async fn connect(
+        kind: HttpKind,
+        url: &str,
+        headers: HashMap<String, String>,
+        reconnect: ReconnectPolicy,
+        inbox: UnboundedSender<serde_json::Value>,
+    ) -> Result<Self, String> {
+        let transport = Self {
+            kind,
+            url: url.to_string(),
+            headers,
+            reconnect,
+            client: reqwest::Client::new(),
+            session_id: StdMutex::new(None),
+            protocol_version: StdMutex::new(None),
+            endpoint: StdMutex::new(None),
+            inbox: Arc::new(StdMutex::new(Some(inbox))),
+            listener: StdMutex::new(None),
+        };
+        if kind == HttpKind::Sse {
+            transport.open_event_stream().await?;
+        }
+        Ok(transport)
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.inbox.lock().unwrap().is_some()
+    }
+
+    pub fn session_id(&self) -> Option<String> {
+        self.session_id.lock().unwrap().clone()
+    }
+
+    /// Sent as `MCP-Protocol-Version` on every request after `initialize`
+    pub fn set_protocol_version(&self, version: &str) {
+        *self.protocol_version.lock().unwrap() = Some(version.to_string());
+    }
+
+    pub async fn send(&self, message: &serde_json::Value) -> Result<(), String> {
+        if !self.is_open() {
+            return Err(format!("{} is closed", self.url));
+        }
+        match self.kind {
+            HttpKind::Streamable => self.post_streamable(message).await,
+            HttpKind::Sse => self.post_legacy(message).await,
+        }
+    }
+
+    /// Stop listening and, for Streamable HTTP, end the session on the server
+    pub async fn close(&self) {
+        if let Some(listener) = self.listener.lock().unwrap().take() {
+            listener.abort();
+        }
+        self.inbox.lock().unwrap().take();
+        if self.kind == HttpKind::Streamable && self.session_id().is_some() {
+            let _ = self
+                .with_headers(self.client.delete(&self.url))
+                .timeout(Duration::from_secs(2))
+                .send()
+                .await;
+        }
+    }
+
+    fn with_headers(&self, mut request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        if let Some(session_id) = self.session_id() {
+            request = request.header(SESSION_HEADER, session_id);
+        }
+        if let Some(version) = self.protocol_version.lock().unwrap().as_ref() {
+            request = request.header(PROTOCOL_HEADER, version);
+        }
+        request
+    }
+
+    /// Send with the reconnect policy, retrying with a doubling delay.
+    /// Failures to connect are always retried since nothing reached the
+    /// server; 5xx answers and timeouts only when `idempotent`, as the server
+    /// may already have acted on the request.
+    async fn send_with_retry<F>(
+        &self,
+        idempotent: bool,
+        build: F,
+    ) -> Result<reqwest::Response, String>
+    where
+        F: Fn() -> reqwest::RequestBuilder,
+    {
+        let mut attempt = 0;
+        loop {
+            let error = match self.with_headers(build()).send().await {
+                Ok(response) if !idempotent || !response.status().is_server_error() => {
+                    return Ok(response)
+                }
+                Ok(response) => format!("HTTP {}", response.status()),
+                Err(e) if e.is_connect() || (idempotent && e.is_timeout()) => e.to_string(),
+                Err(e) => return Err(e.to_string()),
+            };
+            if attempt >= self.reconnect.max_attempts {
+                return Err(format!(
+                    "{} after {} attempt(s): {}",
+                    self.url,
+                    attempt + 1,
+                    error
+                ));
+            }
+            tokio::time::sleep(self.reconnect.delay(attempt)).await;
+            attempt += 1;
+        }
+    }
+
+    /// A 404 on a request that carried a session ID means the server dropped
+    /// the session; close so the next request starts a new one
+    fn check_session(&self, status: StatusCode) -> Result<(), String> {
+        if status == StatusCode::NOT_FOUND && self.session_id().is_some() {
+            self.inbox.lock().unwrap().take();
+            return Err(format!(
+                "{} expired the session; it will be re-established on the next request",
+                self.url
+            ));
+        }
+        if !status.is_success() {
+            return Err(format!("{} answered HTTP {}", self.url, status));
+        }
+        Ok(())
+    }
+
+    async fn post_streamable(&self, message: &serde_json::Value) -> Result<(), String> {
+        let response = self
+            .send_with_retry(is_idempotent(message), || {
+                self.client
+                    .post(&self.url)
+                    .header(ACCEPT, "application/json, text/event-stream")
+                    .json(message)
+            })
+            .await?;
+        self.check_session(response.status())?;
+
+        if let Some(session_id) = response
+            .headers()
+            .get(SESSION_HEADER)
+            .and_then(|v| v.to_str().ok())
+        {
+            *self.session_id.lock().unwrap() = Some(session_id.to_string());
+        }
+        if response.status() == StatusCode::ACCEPTED {
+            return Ok(());
+        }
+
+        let is_stream = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("text/event-stream"));
+        if is_stream {
+            // The response (and anything the server sends first) arrives on
+            // the stream; read it in the background so the request timeout
+            // still applies
+            tokio::spawn(forward_events(
+                Box::pin(response.bytes_stream()),
+                SseParser::new(),
+                self.inbox.clone(),
+            ));
+        } else {
+            let body = response.text().await.map_err(|e| e.to_string())?;
+            deliver(&self.inbox, &body);
+        }
+        Ok(())
+    }
+
+    async fn post_legacy(&self, message: &serde_json::Value) -> Result<(), String> {
+        let endpoint = self
+            .endpoint
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or("no message endpoint announced")?;
+        let response = self
+            .send_with_retry(is_idempotent(message), || {
+                self.client.post(&endpoint).json(message)
+            })
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            self.inbox.lock().unwrap().take();
+            return Err(format!("{} no longer knows this session", endpoint));
+        }
+        self.check_session(response.status())
+    }
+
+    async fn open_event_stream(&self) -> Result<(), String> {
+        let response = self
+            .send_with_retry(true, || {
+                self.client
+                    .get(&self.url)
+                    .header(ACCEPT, "text/event-stream")
+            })
+            .await?;
+        if !response.status().is_success() {
+            return Err(format!("{} answered HTTP {}", self.url, response.status()));
+        }
+
+        let mut stream = Box::pin(response.bytes_stream());
+        let mut parser = SseParser::new();
+        let endpoint = tokio::time::timeout(ENDPOINT_TIMEOUT, async {
+            let mut endpoint = None;
+            while endpoint.is_none() {
+                let chunk = match stream.next().await {
+                    Some(Ok(chunk)) => chunk,
+                    Some(Err(e)) => return Err(e.to_string()),
+                    None => return Err("event stream closed before announcing an endpoint".into()),
+                };
+                for event in parser.push(&chunk) {
+                    if endpoint.is_none() && event.event == "endpoint" {
+                        endpoint = Some(event.data);
+                    } else if event.event == "message" {
+                        deliver(&self.inbox, &event.data);
+                    }
+                }
+            }
+            Ok(endpoint.unwrap_or_default())
+        })
+        .await
+        .map_err(|_| format!("{} did not announce an endpoint", self.url))??;
+
+        let endpoint = reqwest::Url::parse(&self.url)
+            .and_then(|base| base.join(&endpoint))
+            .map_err(|e| format!("bad endpoint {}: {}", endpoint, e))?;
+        *self.endpoint.lock().unwrap() = Some(endpoint.to_string());
+
+        let inbox = self.inbox.clone();
+        let listener = tokio::spawn(async move {
+            forward_events(stream, parser, inbox.clone()).await;
+            // The session lives and dies with this stream
+            inbox.lock().unwrap().take();
+        });
+        *self.listener.lock().unwrap() = Some(listener);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sse_parser_handles_split_chunks() {
+        let mut parser = SseParser::new();
+        assert!(parser.push(b"event: endpoint\r\nda").is_empty());
+        let events =
+            parser.push(b"ta: /messages?id=1\r\n\r\n: ping\n\ndata: {\"a\":\ndata: 1}\n\n");
+        assert_eq!(
+            events,
+            vec![
+                SseEvent {
+                    event: "endpoint".to_string(),
+                    data: "/messages?id=1".to_string()
+                },
+                SseEvent {
+                    event: "message".to_string(),
+                    data: "{\"a\":\n1}".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_only_idempotent_messages_are_retried() {
+        let request =
+            |method: &str| serde_json::json!({"jsonrpc": "2.0", "id": 1, "method": method});
+        assert!(is_idempotent(&request("initialize")));
+        assert!(is_idempotent(&request("tools/list")));
+        assert!(is_idempotent(&request("ping")));
+        assert!(!is_idempotent(&request("tools/call")));
+        assert!(!is_idempotent(&request("notifications/initialized")));
+        assert!(!is_idempotent(
+            &serde_json::json!({"jsonrpc": "2.0", "id": 1, "result": {}})
+        ));
+    }
+
+    #[test]
+    fn test_transport_from_config() {
+        assert_eq!(HttpKind::from_config(None), Ok(HttpKind::Streamable));
+        assert_eq!(HttpKind::from_config(Some("sse")), Ok(HttpKind::Sse));
+        assert!(HttpKind::from_config(Some("websocket")).is_err());
+    }
+}
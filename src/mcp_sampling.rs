@@ -0,0 +1,209 @@
+use serde_json::{json, Value};
+use std::sync::Mutex;
+
+const OLLAMA_URL: &str = "http://localhost:11434";
+
+/// The chat session's model, used for servers' sampling requests
+static MODEL: Mutex<Option<String>> = Mutex::new(None);
+
+/// Let MCP servers sample from `model`; clients created afterwards advertise
+/// the `sampling` capability
+pub fn set_model(model: &str) {
+    *MODEL.lock().unwrap() = Some(model.to_string());
+}
+
+/// Answers `sampling/createMessage` requests with the local Ollama model,
+/// once the user has approved each one
+#[derive(Clone)]
+pub struct Sampler {
+    pub model: String,
+    pub url: String,
+    pub approve: fn(&str) -> bool,
+}
+
+impl Sampler {
+    pub fn new(model: &str) -> Self {
+        Self {
+            model: model.to_string(),
+            url: OLLAMA_URL.to_string(),
+            approve: crate::tools::approve_on_tty,
+        }
+    }
+
+    /// A sampler for the model given to `set_model`, if any
+    pub fn configured() -> Option<Self> {
+        MODEL.lock().unwrap().as_deref().map(Self::new)
+    }
+
+    /// The `sampling/createMessage` result, or a JSON-RPC error code and message
+    pub async fn create_message(
+        &self,
+        server: &str,
+        params: &Value,
+    ) -> Result<Value, (i64, String)> {
+        let messages = chat_messages(params).map_err(|e| (-32602, e))?;
+
+        let request = describe_request(server, &self.model, params);
+        let approve = self.approve;
+        let approved =
+            tokio::task::spawn_blocking(move || crate::mcp::suspend_progress(|| approve(&request)))
+                .await
+                .unwrap_or(false);
+        if !approved {
+            return Err((-1, "User rejected sampling request".to_string()));
+        }
+
+        let mut options = serde_json::Map::new();
+        if let Some(max_tokens) = params["maxTokens"].as_u64() {
+            options.insert("num_predict".to_string(), json!(max_tokens));
+        }
+        if let Some(temperature) = params["temperature"].as_f64() {
+            options.insert("temperature".to_string(), json!(temperature));
+        }
+        if let Some(stop) = params["stopSequences"].as_array() {
+            options.insert("stop".to_string(), json!(stop));
+        }
+
+        let response = reqwest::Client::new()
+            .post(format!("{}/api/chat", self.url))
+            .json(&json!({
+                "model": self.model,
+                "messages": messages,
+                "options": options,
+                "stream": false
+            }))
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| (-32603, format!("Ollama request failed: {}", e)))?;
+        let reply: Value = response
+            .json()
+            .await
+            .map_err(|e| (-32603, format!("Invalid Ollama response: {}", e)))?;
+
+        let stop_reason = match reply["done_reason"].as_str() {
+            Some("length") => "maxTokens",
+            _ => "endTurn",
+        };
+        Ok(json!({
+            "role": "assistant",
+            "content": {"type": "text", "text": reply["message"]["content"].as_str().unwrap_or("")},
+            "model": self.model,
+            "stopReason": stop_reason
+        }))
+    }
+}
+
+/// The request's system prompt and messages as Ollama chat messages. Images
+/// are passed along; other content (e.g. audio) is not supported.
+fn chat_messages(params: &Value) -> Result<Vec<Value>, String> {
+    let mut messages = Vec::new();
+    if let Some(system) = params["systemPrompt"].as_str() {
+        messages.push(json!({"role": "system", "content": system}));
+    }
+    for message in params["messages"].as_array().into_iter().flatten() {
+        let role = message["role"].as_str().unwrap_or("user");
+        let blocks = match &message["content"] {
+            Value::Array(blocks) => blocks.clone(),
+            block => vec![block.clone()],
+        };
+        let mut text = Vec::new();
+        let mut images = Vec::new();
+        for block in &blocks {
+            match block["type"].as_str() {
+                Some("text") => text.push(block["text"].as_str().unwrap_or("")),
+                Some("image") => images.push(block["data"].clone()),
+                other => {
+                    return Err(format!(
+                        "Unsupported sampling content: {}",
+                        other.unwrap_or("none")
+                    ))
+                }
+            }
+        }
+        let mut chat = json!({"role": role, "content": text.join("\n")});
+        if !images.is_empty() {
+            chat["images"] = json!(images);
+        }
+        messages.push(chat);
+    }
+    if messages.iter().all(|m| m["role"] == "system") {
+        return Err("Sampling request has no messages".to_string());
+    }
+    Ok(messages)
+}
+
+/// What the approval prompt shows: who is asking, for what, and with which model
+fn describe_request(server: &str, model: &str, params: &Value) -> String {
+    let mut lines = vec![format!(
+        "MCP server {} wants to sample from {}",
+        server, model
+    )];
+    if let Some(system) = params["systemPrompt"].as_str() {
+        lines.push(format!("  system: {}", system));
+    }
+    for message in params["messages"].as_array().into_iter().flatten() {
+        let content = match &message["content"] {
+            Value::Array(blocks) => blocks
+                .iter()
+                .map(describe_block)
+                .collect::<Vec<_>>()
+                .join(" "),
+            block => describe_block(block),
+        };
+        lines.push(format!(
+            "  {}: {}",
+            message["role"].as_str().unwrap_or("user"),
+            content
+        ));
+    }
+    if let Some(max_tokens) = params["maxTokens"].as_u64() {
+        lines.push(format!("  max tokens: {}", max_tokens));
+    }
+    lines.join("\n")
+}
+
+fn describe_block(block: &Value) -> String {
+    match block["type"].as_str() {
+        Some("text") => block["text"].as_str().unwrap_or("").to_string(),
+        Some(kind) => format!("[{} {}]", kind, block["mimeType"].as_str().unwrap_or("")),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chat_messages_and_description() {
+        let params = json!({
+            "systemPrompt": "You summarize.",
+            "messages": [
+                {"role": "user", "content": {"type": "text", "text": "Summarize this"}},
+                {"role": "user", "content": [
+                    {"type": "text", "text": "and this"},
+                    {"type": "image", "data": "iVBORw0K", "mimeType": "image/png"}
+                ]}
+            ],
+            "maxTokens": 100
+        });
+        assert_eq!(
+            chat_messages(&params).unwrap(),
+            vec![
+                json!({"role": "system", "content": "You summarize."}),
+                json!({"role": "user", "content": "Summarize this"}),
+                json!({"role": "user", "content": "and this", "images": ["iVBORw0K"]}),
+            ]
+        );
+        assert_eq!(
+            describe_request("docs", "llama3", &params),
+            "MCP server docs wants to sample from llama3\n  system: You summarize.\n  \
+             user: Summarize this\n  user: and this [image image/png]\n  max tokens: 100"
+        );
+
+        let audio = json!({"messages": [{"role": "user", "content": {"type": "audio"}}]});
+        assert!(chat_messages(&audio).is_err());
+        assert!(chat_messages(&json!({"systemPrompt": "x"})).is_err());
+    }
+}
@@ -0,0 +1,317 @@
+use crate::mcp::SUPPORTED_PROTOCOL_VERSIONS;
+use crate::multi_file::MultiFileEditor;
+use crate::tools::{execute_tool, get_available_tools, is_builtin, Tool, ToolCall, ToolResult};
+use crate::wiseowl::WiseOwl;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+/// WiseOwl memory files served as resources: URI, file and title
+const MEMORY_RESOURCES: &[(&str, &str, &str)] = &[
+    ("wiseowl://todo", "TODO.md", "Project TODO list"),
+    (
+        "wiseowl://rules",
+        "RULES.md",
+        "Project rules and conventions",
+    ),
+    (
+        "wiseowl://context",
+        "CONTEXT.md",
+        "Project context and current state",
+    ),
+];
+
+/// Serves the built-in tools and WiseOwl memory to an MCP client. Calls go
+/// through `execute_tool`, so they are audited and approvals still apply;
+/// with `staging` set, writes and commands are only recorded, as in dry-run.
+pub struct McpServer {
+    owl: WiseOwl,
+    staging: Option<MultiFileEditor>,
+    initialized: bool,
+}
+
+impl McpServer {
+    pub fn new(owl: WiseOwl, dry_run: bool) -> Self {
+        Self {
+            owl,
+            staging: dry_run.then(MultiFileEditor::new),
+            initialized: false,
+        }
+    }
+
+    /// Answer messages from `reader` on `writer` until the client disconnects
+    pub async fn serve<R, W>(
+        &mut self,
+        reader: R,
+        mut writer: W,
+    ) -> Result<(), Box<dyn std::error::Error>>
+    where
+        R: AsyncRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        let mut lines = BufReader::new(reader).lines();
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let reply = match serde_json::from_str::<Value>(&line) {
+                Ok(message) => self.handle_message(&message).await,
+                Err(e) => Some(error_reply(
+                    &Value::Null,
+                    -32700,
+                    &format!("Parse error: {}", e),
+                )),
+            };
+            if let Some(reply) = reply {
+                writer.write_all(format!("{}\n", reply).as_bytes()).await?;
+                writer.flush().await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The reply to one JSON-RPC message; `None` for notifications
+    pub async fn handle_message(&mut self, message: &Value) -> Option<Value> {
+        let method = message["method"].as_str().unwrap_or("");
+        if method == "notifications/initialized" {
+            self.initialized = true;
+            return None;
+        }
+        let id = message.get("id")?;
+        let params = &message["params"];
+
+        let result = match method {
+            "initialize" => Ok(initialize_result(params)),
+            "ping" => Ok(json!({})),
+            _ if !self.initialized => Err((-32002, "Server not initialized".to_string())),
+            "tools/list" => Ok(json!({
+                "tools": get_available_tools().iter().map(tool_definition).collect::<Vec<_>>()
+            })),
+            "tools/call" => self.call_tool(params).await,
+            "resources/list" => Ok(json!({
+                "resources": MEMORY_RESOURCES
+                    .iter()
+                    .map(|(uri, file, title)| json!({
+                        "uri": uri,
+                        "name": file,
+                        "description": title,
+                        "mimeType": "text/markdown"
+                    }))
+                    .collect::<Vec<_>>()
+            })),
+            "resources/read" => self.read_resource(params).await,
+            _ => Err((-32601, format!("Method not found: {}", method))),
+        };
+
+        Some(match result {
+            Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+            Err((code, message)) => error_reply(id, code, &message),
+        })
+    }
+
+    async fn call_tool(&mut self, params: &Value) -> Result<Value, (i64, String)> {
+        let name = params["name"].as_str().unwrap_or("");
+        if !is_builtin(name) {
+            return Err((-32602, format!("Unknown tool: {}", name)));
+        }
+        let call = ToolCall {
+            tool: name.to_string(),
+            parameters: params
+                .get("arguments")
+                .cloned()
+                .unwrap_or_else(|| json!({})),
+        };
+
+        let (text, is_error) = match execute_tool(&call, self.staging.as_mut()).await {
+            ToolResult::Success(text) => (text, false),
+            ToolResult::Error(text) => (text, true),
+        };
+        Ok(json!({
+            "content": [{"type": "text", "text": text}],
+            "isError": is_error
+        }))
+    }
+
+    async fn read_resource(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let uri = params["uri"].as_str().unwrap_or("");
+        let (_, file, _) = MEMORY_RESOURCES
+            .iter()
+            .find(|(known, _, _)| *known == uri)
+            .ok_or_else(|| (-32002, format!("Resource not found: {}", uri)))?;
+        let text = tokio::fs::read_to_string(self.owl.path(file))
+            .await
+            .map_err(|e| (-32603, format!("Failed to read {}: {}", file, e)))?;
+        Ok(json!({
+            "contents": [{"uri": uri, "mimeType": "text/markdown", "text": text}]
+        }))
+    }
+}
+
+/// `wiseowlcli mcp-serve`: serve over stdin/stdout until the client exits
+pub async fn serve_stdio(dry_run: bool) -> Result<(), Box<dyn std::error::Error>> {
+    crate::audit::set_session("mcp-serve");
+    crate::tools::prompt_on_tty(true);
+    let mut server = McpServer::new(WiseOwl::init().await?, dry_run);
+    let served = tokio::select! {
+        result = server.serve(tokio::io::stdin(), tokio::io::stdout()) => Some(result),
+        _ = crate::mcp::idle_interrupt() => None,
+        _ = crate::process::terminated() => None,
+    };
+    crate::process::processes()
+        .lock()
+        .await
+        .shutdown_all()
+        .await;
+    match served {
+        Some(result) => result,
+        // stdin may still be blocked in a read, which would keep the runtime
+        // from shutting down
+        None => std::process::exit(130),
+    }
+}
+
+fn initialize_result(params: &Value) -> Value {
+    let requested = params["protocolVersion"].as_str().unwrap_or("");
+    let version = if SUPPORTED_PROTOCOL_VERSIONS.contains(&requested) {
+        requested
+    } else {
+        SUPPORTED_PROTOCOL_VERSIONS[0]
+    };
+    json!({
+        "protocolVersion": version,
+        "capabilities": {
+            "tools": {"listChanged": false},
+            "resources": {"listChanged": false}
+        },
+        "serverInfo": {"name": "wiseowlcli", "version": env!("CARGO_PKG_VERSION")},
+        "instructions": "wiseowlcli's built-in coding tools. The wiseowl:// resources hold the project's TODO list, rules and context."
+    })
+}
+
+/// A built-in tool as an MCP tool definition with a JSON Schema for its parameters
+fn tool_definition(tool: &Tool) -> Value {
+    let mut properties = serde_json::Map::new();
+    for param in &tool.parameters {
+        properties.insert(
+            param.name.clone(),
+            json!({"type": param.param_type, "description": param.description}),
+        );
+    }
+    let required: Vec<&str> = tool
+        .parameters
+        .iter()
+        .filter(|p| p.required)
+        .map(|p| p.name.as_str())
+        .collect();
+    json!({
+        "name": tool.name,
+        "description": tool.description,
+        "inputSchema": {"type": "object", "properties": properties, "required": required},
+        "annotations": {"readOnlyHint": tool.read_only}
+    })
+}
+
+fn error_reply(id: &Value, code: i64, message: &str) -> Value {
+    json!({"jsonrpc": "2.0", "id": id, "error": {"code": code, "message": message}})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(id: u64, method: &str, params: Value) -> Value {
+        json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params})
+    }
+
+    #[tokio::test]
+    async fn test_serves_tools_and_memory() {
+        crate::audit::use_test_dir();
+        let mut server = McpServer::new(WiseOwl::init().await.unwrap(), true);
+
+        let early = server
+            .handle_message(&request(1, "tools/list", json!({})))
+            .await
+            .unwrap();
+        assert_eq!(early["error"]["code"], -32002);
+
+        let init = server
+            .handle_message(&request(
+                2,
+                "initialize",
+                json!({"protocolVersion": "2024-11-05"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(init["result"]["protocolVersion"], "2024-11-05");
+        assert!(server
+            .handle_message(&json!({"jsonrpc": "2.0", "method": "notifications/initialized"}))
+            .await
+            .is_none());
+
+        let tools = server
+            .handle_message(&request(3, "tools/list", json!({})))
+            .await
+            .unwrap();
+        let read_file = tools["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|t| t["name"] == "read_file")
+            .unwrap();
+        assert_eq!(read_file["inputSchema"]["required"], json!(["path"]));
+        assert_eq!(
+            read_file["inputSchema"]["properties"]["limit"]["type"],
+            "integer"
+        );
+        assert_eq!(read_file["annotations"]["readOnlyHint"], true);
+
+        let read = server
+            .handle_message(&request(
+                4,
+                "tools/call",
+                json!({"name": "read_file", "arguments": {"path": "Cargo.toml", "limit": 1}}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(read["result"]["isError"], false);
+        assert!(read["result"]["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("[package]"));
+
+        // Dry-run staging applies to served calls too
+        let write = server
+            .handle_message(&request(
+                5,
+                "tools/call",
+                json!({"name": "write_file", "arguments": {"path": "never-written.txt", "content": "x"}}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(write["result"]["isError"], false);
+        assert!(!std::path::Path::new("never-written.txt").exists());
+
+        let unknown = server
+            .handle_message(&request(6, "tools/call", json!({"name": "rm_rf"})))
+            .await
+            .unwrap();
+        assert_eq!(unknown["error"]["code"], -32602);
+
+        let resources = server
+            .handle_message(&request(7, "resources/list", json!({})))
+            .await
+            .unwrap();
+        assert_eq!(resources["result"]["resources"][0]["uri"], "wiseowl://todo");
+        let todo = server
+            .handle_message(&request(
+                8,
+                "resources/read",
+                json!({"uri": "wiseowl://todo"}),
+            ))
+            .await
+            .unwrap();
+        assert!(todo["result"]["contents"][0]["text"]
+            .as_str()
+            .unwrap()
+            .starts_with("# TODO"));
+    }
+}
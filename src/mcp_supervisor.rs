@@ -0,0 +1,284 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader};
+
+/// A server that stays up this long counts as healthy again, so its next
+/// crash starts the restart backoff over
+const STABLE_UPTIME: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ServerState {
+    #[default]
+    NotStarted,
+    Running,
+    Crashed,
+    Stopped,
+}
+
+impl ServerState {
+    pub fn name(self) -> &'static str {
+        match self {
+            ServerState::NotStarted => "not started",
+            ServerState::Running => "running",
+            ServerState::Crashed => "crashed",
+            ServerState::Stopped => "stopped",
+        }
+    }
+}
+
+/// Latency of one tool's calls this session
+#[derive(Debug, Clone, Default)]
+pub struct ToolStats {
+    pub calls: u32,
+    pub errors: u32,
+    pub total: Duration,
+    pub max: Duration,
+}
+
+impl ToolStats {
+    pub fn average(&self) -> Duration {
+        if self.calls == 0 {
+            return Duration::ZERO;
+        }
+        self.total / self.calls
+    }
+
+    pub fn display(&self) -> String {
+        let mut line = format!(
+            "{} call(s), avg {}, max {}",
+            self.calls,
+            format_duration(self.average()),
+            format_duration(self.max)
+        );
+        if self.errors > 0 {
+            line.push_str(&format!(", {} failed", self.errors));
+        }
+        line
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ServerHealth {
+    pub state: ServerState,
+    pub started_at: Option<Instant>,
+    pub restarts: u32,
+    /// Crashes since the server last stayed up for `STABLE_UPTIME`
+    pub crashes_in_a_row: u32,
+    pub last_error: Option<String>,
+    /// The last line the server wrote to stderr
+    pub last_stderr: Option<String>,
+    pub tools: BTreeMap<String, ToolStats>,
+}
+
+impl ServerHealth {
+    pub fn uptime(&self) -> Option<Duration> {
+        if self.state != ServerState::Running {
+            return None;
+        }
+        self.started_at.map(|started| started.elapsed())
+    }
+
+    /// e.g. "running, up 3m 05s, 1 restart(s)"
+    pub fn summary(&self) -> String {
+        let mut summary = self.state.name().to_string();
+        if let Some(uptime) = self.uptime() {
+            summary.push_str(&format!(", up {}", format_duration(uptime)));
+        }
+        if self.restarts > 0 {
+            summary.push_str(&format!(", {} restart(s)", self.restarts));
+        }
+        summary
+    }
+}
+
+/// Health of every server started this session, and where their stderr is
+/// logged. Clones share the same records.
+#[derive(Clone)]
+pub struct Supervisor {
+    health: Arc<Mutex<HashMap<String, ServerHealth>>>,
+    log_dir: PathBuf,
+}
+
+impl Supervisor {
+    pub fn new(log_dir: PathBuf) -> Self {
+        Self {
+            health: Arc::new(Mutex::new(HashMap::new())),
+            log_dir,
+        }
+    }
+
+    /// Logs go to `.wiseowlcli/logs` in the working directory
+    pub fn in_current_dir() -> Self {
+        let base = std::env::current_dir().unwrap_or_default();
+        Self::new(base.join(".wiseowlcli").join("logs"))
+    }
+
+    pub fn log_path(&self, server: &str) -> PathBuf {
+        self.log_dir.join(format!("mcp-{}.log", server))
+    }
+
+    pub fn health(&self, server: &str) -> ServerHealth {
+        self.health
+            .lock()
+            .unwrap()
+            .get(server)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn update<F: FnOnce(&mut ServerHealth)>(&self, server: &str, update: F) {
+        update(
+            self.health
+                .lock()
+                .unwrap()
+                .entry(server.to_string())
+                .or_default(),
+        );
+    }
+
+    pub fn started(&self, server: &str) {
+        self.update(server, |health| {
+            health.state = ServerState::Running;
+            health.started_at = Some(Instant::now());
+        });
+    }
+
+    pub fn restarted(&self, server: &str) {
+        self.started(server);
+        self.update(server, |health| health.restarts += 1);
+    }
+
+    pub fn stopped(&self, server: &str) {
+        self.update(server, |health| health.state = ServerState::Stopped);
+    }
+
+    /// Record that the server died; returns how many times in a row it has
+    pub fn crashed(&self, server: &str, error: String) -> u32 {
+        let mut crashes = 0;
+        self.update(server, |health| {
+            if health
+                .uptime()
+                .is_some_and(|uptime| uptime >= STABLE_UPTIME)
+            {
+                health.crashes_in_a_row = 0;
+            }
+            health.crashes_in_a_row += 1;
+            health.state = ServerState::Crashed;
+            health.last_error = Some(error);
+            crashes = health.crashes_in_a_row;
+        });
+        crashes
+    }
+
+    /// Record a failed request. While a server is down its crash stays the
+    /// last error; the requests it took with it add nothing.
+    pub fn failed(&self, server: &str, error: String) {
+        self.update(server, |health| {
+            if health.state != ServerState::Crashed {
+                health.last_error = Some(error);
+            }
+        });
+    }
+
+    pub fn record_call(&self, server: &str, tool: &str, elapsed: Duration, success: bool) {
+        self.update(server, |health| {
+            let stats = health.tools.entry(tool.to_string()).or_default();
+            stats.calls += 1;
+            stats.total += elapsed;
+            stats.max = stats.max.max(elapsed);
+            if !success {
+                stats.errors += 1;
+            }
+        });
+    }
+
+    /// Append a server's stderr to its log file until the pipe closes
+    pub fn capture_stderr<R>(&self, server: &str, stderr: R)
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        let supervisor = self.clone();
+        let server = server.to_string();
+        tokio::spawn(async move {
+            let path = supervisor.log_path(&server);
+            let mut log = match open_log(&path).await {
+                Ok(log) => Some(log),
+                Err(e) => {
+                    eprintln!("⚠️  Cannot log {} to {}: {}", server, path.display(), e);
+                    None
+                }
+            };
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                if let Some(file) = log.as_mut() {
+                    let entry = format!("{} {}\n", chrono::Local::now().format("%H:%M:%S"), line);
+                    let _ = file.write_all(entry.as_bytes()).await;
+                }
+                if !line.trim().is_empty() {
+                    supervisor.update(&server, |health| health.last_stderr = Some(line));
+                }
+            }
+        });
+    }
+}
+
+async fn open_log(path: &std::path::Path) -> std::io::Result<tokio::fs::File> {
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+    let header = format!(
+        "--- started {} ---\n",
+        chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
+    );
+    file.write_all(header.as_bytes()).await?;
+    Ok(file)
+}
+
+/// "350ms", "12s", "3m 05s" or "2h 07m"
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0 => format!("{}ms", duration.as_millis()),
+        1..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m {:02}s", secs / 60, secs % 60),
+        _ => format!("{}h {:02}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_millis(350)), "350ms");
+        assert_eq!(format_duration(Duration::from_secs(12)), "12s");
+        assert_eq!(format_duration(Duration::from_secs(185)), "3m 05s");
+        assert_eq!(format_duration(Duration::from_secs(7620)), "2h 07m");
+    }
+
+    #[test]
+    fn test_tool_stats_and_crash_count() {
+        let supervisor = Supervisor::new(PathBuf::from("/tmp"));
+        supervisor.record_call("s", "echo", Duration::from_millis(10), true);
+        supervisor.record_call("s", "echo", Duration::from_millis(30), false);
+        let stats = &supervisor.health("s").tools["echo"];
+        assert_eq!(stats.display(), "2 call(s), avg 20ms, max 30ms, 1 failed");
+
+        supervisor.started("s");
+        assert_eq!(supervisor.crashed("s", "exit status: 1".to_string()), 1);
+        assert_eq!(supervisor.crashed("s", "exit status: 1".to_string()), 2);
+        supervisor.restarted("s");
+        let health = supervisor.health("s");
+        assert_eq!(health.restarts, 1);
+        assert!(health.summary().starts_with("running, up "));
+        assert_eq!(health.last_error.as_deref(), Some("exit status: 1"));
+    }
+}
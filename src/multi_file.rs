@@ -17,12 +17,20 @@ pub enum EditOperation {
 
 pub struct MultiFileEditor {
     pending_edits: HashMap<String, FileEdit>,
+    deferred_calls: Vec<(String, serde_json::Value)>,
+}
+
+impl Default for MultiFileEditor {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl MultiFileEditor {
     pub fn new() -> Self {
         Self {
             pending_edits: HashMap::new(),
+            deferred_calls: Vec::new(),
         }
     }
 
@@ -30,6 +38,20 @@ impl MultiFileEditor {
         self.pending_edits.insert(edit.path.clone(), edit);
     }
 
+    /// Staged edit for `path`, so later dry-run edits build on earlier ones
+    pub fn pending_edit(&self, path: &str) -> Option<&FileEdit> {
+        self.pending_edits.get(path)
+    }
+
+    /// Record a tool call (bash, process control) that a dry run held back
+    pub fn defer_call(&mut self, tool: &str, parameters: serde_json::Value) {
+        self.deferred_calls.push((tool.to_string(), parameters));
+    }
+
+    pub fn take_deferred_calls(&mut self) -> Vec<(String, serde_json::Value)> {
+        std::mem::take(&mut self.deferred_calls)
+    }
+
     pub fn show_preview(&self) -> String {
         let mut preview = String::from("📋 Pending Changes:\n\n");
 
@@ -41,6 +63,21 @@ impl MultiFileEditor {
             }
         }
 
+        if !self.deferred_calls.is_empty() {
+            // Edits are kept per file, so commands cannot be interleaved with them
+            preview.push_str(
+                "\n⚙️  Commands (not run yet; /apply runs them in order after all edits):\n",
+            );
+            for (tool, params) in &self.deferred_calls {
+                match params.get("command").and_then(|c| c.as_str()) {
+                    Some(command) if tool == "execute_bash" => {
+                        preview.push_str(&format!("  $ {}\n", command))
+                    }
+                    _ => preview.push_str(&format!("  {} {}\n", tool, params)),
+                }
+            }
+        }
+
         preview
     }
 
@@ -54,6 +91,10 @@ impl MultiFileEditor {
                     if std::path::Path::new(&path).exists() {
                         let backup = format!("{}.backup", path);
                         tokio::fs::copy(&path, &backup).await?;
+                    } else if let Some(parent) = std::path::Path::new(&path).parent() {
+                        if !parent.as_os_str().is_empty() {
+                            tokio::fs::create_dir_all(parent).await?;
+                        }
                     }
 
                     tokio::fs::write(&path, &edit.content).await?;
@@ -72,9 +113,10 @@ impl MultiFileEditor {
 
     pub fn clear(&mut self) {
         self.pending_edits.clear();
+        self.deferred_calls.clear();
     }
 
     pub fn has_pending(&self) -> bool {
-        !self.pending_edits.is_empty()
+        !self.pending_edits.is_empty() || !self.deferred_calls.is_empty()
     }
 }
@@ -0,0 +1,584 @@
+use ignore::WalkBuilder;
+use regex::Regex;
+use std::path::Path;
+
+const MAX_FILE_SIZE: u64 = 2 * 1024 * 1024;
+const DEFAULT_MAX_RESULTS: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Language {
+    Rust,
+    Python,
+    JavaScript,
+    TypeScript,
+    Go,
+}
+
+impl Language {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "rs" => Some(Language::Rust),
+            "py" | "pyi" => Some(Language::Python),
+            "js" | "jsx" | "mjs" | "cjs" => Some(Language::JavaScript),
+            "ts" | "tsx" | "mts" | "cts" => Some(Language::TypeScript),
+            "go" => Some(Language::Go),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub kind: &'static str,
+    pub name: String,
+    /// Enclosing impl, class or receiver type for methods
+    pub parent: Option<String>,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+impl Symbol {
+    /// Whether `name` is this symbol's name or its `Parent::name` form
+    pub fn is_named(&self, name: &str) -> bool {
+        self.name == name
+            || self
+                .parent
+                .as_ref()
+                .and_then(|p| name.strip_prefix(p.as_str()))
+                .and_then(|rest| rest.strip_prefix("::"))
+                == Some(self.name.as_str())
+    }
+}
+
+struct Pattern {
+    kind: &'static str,
+    regex: Regex,
+}
+
+fn pattern(kind: &'static str, regex: &str) -> Pattern {
+    Pattern {
+        kind,
+        regex: Regex::new(regex).unwrap(),
+    }
+}
+
+/// Top-level declarations; the first capture group is the name
+fn top_level_patterns(language: Language) -> Vec<Pattern> {
+    match language {
+        Language::Rust => {
+            let vis = r#"^(?:pub(?:\([^)]*\))?\s+)?"#;
+            vec![
+                pattern(
+                    "fn",
+                    &format!(
+                        r#"{}(?:(?:async|const|unsafe|extern\s+"[^"]*")\s+)*fn\s+(\w+)"#,
+                        vis
+                    ),
+                ),
+                pattern("struct", &format!(r"{}struct\s+(\w+)", vis)),
+                pattern("enum", &format!(r"{}enum\s+(\w+)", vis)),
+                pattern("trait", &format!(r"{}(?:unsafe\s+)?trait\s+(\w+)", vis)),
+                pattern(
+                    "impl",
+                    r"^(?:unsafe\s+)?impl(?:<[^{]*?>)?\s+([^{]+?)\s*(?:where\b.*)?\{?$",
+                ),
+                pattern("mod", &format!(r"{}mod\s+(\w+)", vis)),
+                pattern("type", &format!(r"{}type\s+(\w+)", vis)),
+                pattern(
+                    "const",
+                    &format!(r"{}(?:const|static)\s+(?:mut\s+)?(\w+)\s*:", vis),
+                ),
+                pattern("macro", r"^macro_rules!\s*(\w+)"),
+            ]
+        }
+        Language::Python => vec![
+            pattern("def", r"^(?:async\s+)?def\s+(\w+)"),
+            pattern("class", r"^class\s+(\w+)"),
+        ],
+        Language::JavaScript | Language::TypeScript => {
+            let export = r"^(?:export\s+)?(?:default\s+)?(?:declare\s+)?";
+            vec![
+                pattern(
+                    "function",
+                    &format!(r"{}(?:async\s+)?function\s*\*?\s*(\w+)", export),
+                ),
+                pattern(
+                    "class",
+                    &format!(r"{}(?:abstract\s+)?class\s+(\w+)", export),
+                ),
+                pattern(
+                    "function",
+                    &format!(
+                        r"{}(?:const|let|var)\s+(\w+)\s*(?::[^=]+)?=\s*(?:async\s+)?(?:function\b|(?:\([^)]*\)|\w+)\s*(?::[^=]+)?=>)",
+                        export
+                    ),
+                ),
+                pattern("interface", &format!(r"{}interface\s+(\w+)", export)),
+                pattern(
+                    "type",
+                    &format!(r"{}type\s+(\w+)\s*(?:<[^=]*>)?\s*=", export),
+                ),
+                pattern("enum", &format!(r"{}(?:const\s+)?enum\s+(\w+)", export)),
+            ]
+        }
+        Language::Go => vec![
+            pattern("func", r"^func\s+(\w+)"),
+            pattern("struct", r"^type\s+(\w+)(?:\[[^\]]*\])?\s+struct\b"),
+            pattern("interface", r"^type\s+(\w+)(?:\[[^\]]*\])?\s+interface\b"),
+            pattern("type", r"^type\s+(\w+)"),
+        ],
+    }
+}
+
+/// List the declarations in `source`: top-level symbols plus the methods of
+/// impls, traits and classes, each with its 1-based line range
+pub fn outline(source: &str, language: Language) -> Vec<Symbol> {
+    let lines: Vec<&str> = source.lines().collect();
+    let patterns = top_level_patterns(language);
+    let go_method =
+        Regex::new(r"^func\s+\(\s*\w*\s*\*?\s*(\w+)(?:\[[^\]]*\])?\s*\)\s*(\w+)").unwrap();
+    let mut symbols = Vec::new();
+
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let mut found = None;
+
+        if language == Language::Go {
+            if let Some(caps) = go_method.captures(line) {
+                found = Some(Symbol {
+                    kind: "method",
+                    name: caps[2].to_string(),
+                    parent: Some(caps[1].to_string()),
+                    start_line: i + 1,
+                    end_line: i + 1,
+                });
+            }
+        }
+        if found.is_none() {
+            found = patterns.iter().find_map(|p| {
+                p.regex.captures(line).map(|caps| Symbol {
+                    kind: p.kind,
+                    name: caps[1].trim().to_string(),
+                    parent: None,
+                    start_line: i + 1,
+                    end_line: i + 1,
+                })
+            });
+        }
+
+        let Some(mut symbol) = found else {
+            i += 1;
+            continue;
+        };
+        let end = match language {
+            Language::Python => indented_block_end(&lines, i),
+            _ => braced_block_end(&lines, i),
+        };
+        symbol.end_line = end + 1;
+
+        let container = matches!(symbol.kind, "impl" | "trait" | "class");
+        let parent = match symbol.kind {
+            "impl" => impl_self_type(&symbol.name),
+            _ => symbol.name.clone(),
+        };
+        symbols.push(symbol);
+        if container {
+            symbols.extend(members(&lines, i + 1, end, language, &parent));
+        }
+        i = end + 1;
+    }
+
+    symbols
+}
+
+/// The type an impl header is for: `fmt::Display for Point` and `Stack<T>`
+/// give `Point` and `Stack`
+fn impl_self_type(header: &str) -> String {
+    let self_type = header.rsplit_once(" for ").map_or(header, |(_, t)| t);
+    let self_type = self_type.split('<').next().unwrap_or(self_type).trim();
+    self_type
+        .rsplit("::")
+        .next()
+        .unwrap_or(self_type)
+        .to_string()
+}
+
+/// Methods declared directly inside a container spanning lines `start..=end`
+fn members(
+    lines: &[&str],
+    start: usize,
+    end: usize,
+    language: Language,
+    parent: &str,
+) -> Vec<Symbol> {
+    let method = match language {
+        Language::Rust => Regex::new(
+            r#"^\s+(?:pub(?:\([^)]*\))?\s+)?(?:(?:async|const|unsafe|extern\s+"[^"]*")\s+)*fn\s+(\w+)"#,
+        ),
+        Language::Python => Regex::new(r"^\s+(?:async\s+)?def\s+(\w+)"),
+        Language::JavaScript | Language::TypeScript => Regex::new(
+            r"^\s+(?:(?:public|private|protected|static|async|readonly|override|abstract|get|set)\s+)*\*?(\w+)\s*(?:<[^>]*>)?\s*\([^)]*\)?\s*(?::[^{]*)?\{?\s*$",
+        ),
+        Language::Go => return Vec::new(),
+    }
+    .unwrap();
+
+    let mut symbols = Vec::new();
+    let mut member_indent = None;
+    let mut i = start;
+    while i <= end && i < lines.len() {
+        let line = lines[i];
+        let indent = line.len() - line.trim_start().len();
+        let caps = match method.captures(line) {
+            Some(caps) if member_indent.is_none_or(|m| m == indent) => caps,
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        let name = caps[1].to_string();
+        if matches!(
+            name.as_str(),
+            "if" | "for" | "while" | "switch" | "catch" | "return"
+        ) {
+            i += 1;
+            continue;
+        }
+        member_indent = Some(indent);
+
+        let block_end = match language {
+            Language::Python => indented_block_end(lines, i),
+            _ => braced_block_end(lines, i),
+        }
+        .min(end);
+        symbols.push(Symbol {
+            kind: "method",
+            name,
+            parent: Some(parent.to_string()),
+            start_line: i + 1,
+            end_line: block_end + 1,
+        });
+        i = block_end + 1;
+    }
+    symbols
+}
+
+/// Last line of a `{ ... }` block starting on line `start`, or the line with
+/// the terminating `;` when the declaration has no body
+fn braced_block_end(lines: &[&str], start: usize) -> usize {
+    let mut depth = 0usize;
+    let mut opened = false;
+    let mut in_block_comment = false;
+
+    for (offset, line) in lines[start..].iter().enumerate() {
+        let chars: Vec<char> = line.chars().collect();
+        let mut quote: Option<char> = None;
+        let mut j = 0;
+        while j < chars.len() {
+            let c = chars[j];
+            let next = chars.get(j + 1).copied();
+            if in_block_comment {
+                if c == '*' && next == Some('/') {
+                    in_block_comment = false;
+                    j += 1;
+                }
+            } else if let Some(q) = quote {
+                if c == '\\' {
+                    j += 1;
+                } else if c == q {
+                    quote = None;
+                }
+            } else {
+                match c {
+                    '/' if next == Some('/') => break,
+                    '/' if next == Some('*') => {
+                        in_block_comment = true;
+                        j += 1;
+                    }
+                    // A lone quote is a Rust lifetime ('a), not a char literal
+                    '\'' if chars.get(j + 2) != Some(&'\'') && next != Some('\\') => {}
+                    '"' | '\'' | '`' => quote = Some(c),
+                    '{' => {
+                        depth += 1;
+                        opened = true;
+                    }
+                    '}' => {
+                        depth = depth.saturating_sub(1);
+                        if opened && depth == 0 {
+                            return start + offset;
+                        }
+                    }
+                    ';' if !opened => return start + offset,
+                    _ => {}
+                }
+            }
+            j += 1;
+        }
+    }
+
+    if opened {
+        lines.len().saturating_sub(1)
+    } else {
+        start
+    }
+}
+
+/// Last line of a Python block: everything indented deeper than its header
+fn indented_block_end(lines: &[&str], start: usize) -> usize {
+    let header_indent = lines[start].len() - lines[start].trim_start().len();
+    let mut end = start;
+    for (offset, line) in lines[start + 1..].iter().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let indent = line.len() - line.trim_start().len();
+        // Closing brackets of a multi-line signature sit at the header's indent
+        let continuation = line.trim_start().starts_with([')', ']', '}']);
+        if indent <= header_indent && !continuation {
+            break;
+        }
+        end = start + 1 + offset;
+    }
+    end
+}
+
+pub fn display_outline(path: &str, symbols: &[Symbol]) -> String {
+    if symbols.is_empty() {
+        return format!("{}: no symbols found\n", path);
+    }
+    let mut output = format!("{} ({} symbols)\n", path, symbols.len());
+    for symbol in symbols {
+        let indent = if symbol.parent.is_some() {
+            "    "
+        } else {
+            "  "
+        };
+        output.push_str(&format!(
+            "{}{} {}  {}-{}\n",
+            indent, symbol.kind, symbol.name, symbol.start_line, symbol.end_line
+        ));
+    }
+    output
+}
+
+pub struct SymbolMatch {
+    pub path: String,
+    pub symbol: Symbol,
+}
+
+/// Find definitions named `name` under `root`, honoring .gitignore. When
+/// nothing matches exactly, falls back to case-insensitive substring matches.
+pub fn find_symbol(name: &str, root: &str, kind: Option<&str>) -> Result<Vec<SymbolMatch>, String> {
+    let root_path = Path::new(root);
+    if !root_path.exists() {
+        return Err(format!("Path not found: {}", root));
+    }
+
+    let mut exact = Vec::new();
+    let mut partial = Vec::new();
+    let needle = name.to_lowercase();
+
+    let mut walker = WalkBuilder::new(root_path);
+    walker.require_git(false);
+    for entry in walker.build().filter_map(|e| e.ok()) {
+        let path = entry.path();
+        let Some(language) = Language::from_path(path) else {
+            continue;
+        };
+        if entry
+            .metadata()
+            .map(|m| m.len() > MAX_FILE_SIZE)
+            .unwrap_or(true)
+        {
+            continue;
+        }
+        let Ok(source) = std::fs::read_to_string(path) else {
+            continue;
+        };
+        let display = path
+            .strip_prefix("./")
+            .unwrap_or(path)
+            .display()
+            .to_string();
+
+        for symbol in outline(&source, language) {
+            if kind.is_some_and(|k| k != symbol.kind) {
+                continue;
+            }
+            if symbol.is_named(name) {
+                exact.push(SymbolMatch {
+                    path: display.clone(),
+                    symbol,
+                });
+            } else if exact.is_empty() && symbol.name.to_lowercase().contains(&needle) {
+                partial.push(SymbolMatch {
+                    path: display.clone(),
+                    symbol,
+                });
+            }
+        }
+    }
+
+    let mut matches = if exact.is_empty() { partial } else { exact };
+    matches.truncate(DEFAULT_MAX_RESULTS);
+    Ok(matches)
+}
+
+pub fn display_matches(name: &str, matches: &[SymbolMatch]) -> String {
+    if matches.is_empty() {
+        return format!("No definitions of {} found\n", name);
+    }
+    let mut output = String::new();
+    if !matches.iter().any(|m| m.symbol.is_named(name)) {
+        output.push_str(&format!("No exact match for {}; similar names:\n", name));
+    }
+    for m in matches {
+        let parent = m
+            .symbol
+            .parent
+            .as_ref()
+            .map(|p| format!(" (in {})", p))
+            .unwrap_or_default();
+        output.push_str(&format!(
+            "{}:{}-{} {} {}{}\n",
+            m.path, m.symbol.start_line, m.symbol.end_line, m.symbol.kind, m.symbol.name, parent
+        ));
+    }
+    if matches.len() == DEFAULT_MAX_RESULTS {
+        output.push_str("[results capped; add a kind or narrow the path]\n");
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(symbols: &[Symbol]) -> Vec<(&str, &str, usize, usize)> {
+        symbols
+            .iter()
+            .map(|s| (s.kind, s.name.as_str(), s.start_line, s.end_line))
+            .collect()
+    }
+
+    #[test]
+    fn test_rust_outline() {
+        let source = r#"use std::fmt;
+
+pub struct Point {
+    x: i32,
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", '}')
+    }
+}
+
+pub(crate) async fn load<'a>(s: &'a str) -> &'a str {
+    s
+}
+
+struct Marker;
+"#;
+        assert_eq!(
+            summary(&outline(source, Language::Rust)),
+            vec![
+                ("struct", "Point", 3, 5),
+                ("impl", "fmt::Display for Point", 7, 11),
+                ("method", "fmt", 8, 10),
+                ("fn", "load", 13, 15),
+                ("struct", "Marker", 17, 17),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_python_outline() {
+        let source = "import os\n\nclass Store:\n    def get(self, key):\n        return key\n\n    async def put(\n        self, key\n    ):\n        pass\n\ndef main():\n    Store()\n";
+        assert_eq!(
+            summary(&outline(source, Language::Python)),
+            vec![
+                ("class", "Store", 3, 10),
+                ("method", "get", 4, 5),
+                ("method", "put", 7, 10),
+                ("def", "main", 12, 13),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_typescript_outline() {
+        let source = "export interface Options {\n  verbose: boolean;\n}\n\nexport class Client {\n  private url: string;\n  async fetch(path: string): Promise<void> {\n    if (path) {\n    }\n  }\n}\n\nexport const handler = async (req) => {\n  return req;\n};\n";
+        assert_eq!(
+            summary(&outline(source, Language::TypeScript)),
+            vec![
+                ("interface", "Options", 1, 3),
+                ("class", "Client", 5, 11),
+                ("method", "fetch", 7, 10),
+                ("function", "handler", 13, 15),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_go_outline() {
+        let source = "package main\n\ntype Server struct {\n\taddr string\n}\n\nfunc (s *Server) Start() error {\n\treturn nil\n}\n\nfunc main() {\n}\n";
+        let symbols = outline(source, Language::Go);
+        assert_eq!(
+            summary(&symbols),
+            vec![
+                ("struct", "Server", 3, 5),
+                ("method", "Start", 7, 9),
+                ("func", "main", 11, 12),
+            ]
+        );
+        assert_eq!(symbols[1].parent.as_deref(), Some("Server"));
+    }
+
+    #[test]
+    fn test_find_symbol_across_files() {
+        let dir = std::env::temp_dir().join(format!("wiseowl_find_symbol_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(dir.join("src/lib.rs"), "pub fn parse_config() {}\n").unwrap();
+        std::fs::write(dir.join("tool.py"), "def parse_args():\n    pass\n").unwrap();
+
+        let root = dir.display().to_string();
+        let matches = find_symbol("parse_config", &root, None).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert!(matches[0].path.ends_with("src/lib.rs"));
+
+        let similar = find_symbol("parse", &root, None).unwrap();
+        assert_eq!(similar.len(), 2);
+        assert!(find_symbol("parse", &root, Some("def")).unwrap().len() == 1);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_find_symbol_qualified_by_impl_type() {
+        let dir = std::env::temp_dir().join(format!("wiseowl_find_method_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = "struct Point;\n\nimpl fmt::Display for Point {\n    fn fmt(&self) {}\n}\n\nimpl<T: Clone> Stack<T> {\n    pub fn new() -> Self {\n        todo!()\n    }\n}\n\nimpl Queue {\n    fn new() {}\n}\n";
+        std::fs::write(dir.join("lib.rs"), source).unwrap();
+        let root = dir.display().to_string();
+
+        let matches = find_symbol("Point::fmt", &root, None).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].symbol.parent.as_deref(), Some("Point"));
+
+        let matches = find_symbol("Stack::new", &root, Some("method")).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].symbol.start_line, 8);
+        let output = display_matches("Stack::new", &matches);
+        assert!(!output.contains("No exact match"), "{}", output);
+        assert!(output.contains("method new (in Stack)"), "{}", output);
+
+        let similar = find_symbol("ne", &root, None).unwrap();
+        assert_eq!(similar.len(), 2);
+        assert!(display_matches("ne", &similar).starts_with("No exact match"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,318 @@
+use reqwest::Client;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const DEFAULT_MAX_LINES: usize = 200;
+const DEFAULT_MAX_BYTES: usize = 16 * 1024;
+const SUMMARY_INPUT_CHARS: usize = 12_000;
+
+/// How much tool output is fed back to the model before it gets shortened
+pub struct OutputPolicy {
+    pub max_lines: usize,
+    pub max_bytes: usize,
+    pub summary_model: Option<String>,
+}
+
+pub struct ShapedOutput {
+    pub text: String,
+    pub stored_id: Option<String>,
+}
+
+impl Default for OutputPolicy {
+    fn default() -> Self {
+        Self {
+            max_lines: DEFAULT_MAX_LINES,
+            max_bytes: DEFAULT_MAX_BYTES,
+            summary_model: None,
+        }
+    }
+}
+
+impl OutputPolicy {
+    /// Read overrides from .wiseowlcli/config.json (`/config set tool_output_max_lines 300`)
+    pub async fn load() -> Self {
+        let mut policy = Self::default();
+        let Ok(dir) = std::env::current_dir() else {
+            return policy;
+        };
+        let Ok(content) =
+            tokio::fs::read_to_string(dir.join(".wiseowlcli").join("config.json")).await
+        else {
+            return policy;
+        };
+        let Ok(config) = serde_json::from_str::<serde_json::Value>(&content) else {
+            return policy;
+        };
+
+        let number = |key: &str| {
+            config.get(key).and_then(|v| {
+                v.as_u64()
+                    .or_else(|| v.as_str().and_then(|s| s.parse().ok()))
+            })
+        };
+        if let Some(lines) = number("tool_output_max_lines") {
+            policy.max_lines = lines.max(10) as usize;
+        }
+        if let Some(bytes) = number("tool_output_max_bytes") {
+            policy.max_bytes = bytes.max(1024) as usize;
+        }
+        policy.summary_model = config
+            .get("tool_output_summary_model")
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string());
+
+        policy
+    }
+
+    /// Shorten `output` for the conversation, saving the full text when anything is dropped
+    pub async fn shape(&self, client: &Client, tool: &str, output: &str) -> ShapedOutput {
+        // read_tool_output pages through saved output already; shaping it would
+        // only save it again
+        if tool == "read_tool_output" || self.fits(output) {
+            return ShapedOutput {
+                text: output.to_string(),
+                stored_id: None,
+            };
+        }
+
+        let stored_id = store_output(tool, output).await.ok();
+        let collapsed = collapse_repeats(output);
+
+        let mut text = if self.fits(&collapsed) {
+            collapsed
+        } else {
+            let (kept, omitted) = head_and_tail(&collapsed, self.max_lines, self.max_bytes);
+            match &self.summary_model {
+                Some(model) if !omitted.is_empty() => {
+                    match summarize(client, model, tool, &omitted).await {
+                        Some(summary) => {
+                            format!("Summary of omitted output: {}\n\n{}", summary, kept)
+                        }
+                        None => kept,
+                    }
+                }
+                _ => kept,
+            }
+        };
+
+        if let Some(id) = &stored_id {
+            text.push_str(&format!(
+                "\n[full output: {} lines saved as {}; call read_tool_output with id and offset/limit to see more]\n",
+                output.lines().count(),
+                id
+            ));
+        }
+
+        ShapedOutput { text, stored_id }
+    }
+
+    fn fits(&self, text: &str) -> bool {
+        text.len() <= self.max_bytes && text.lines().count() <= self.max_lines
+    }
+}
+
+/// Collapse runs of identical lines into one line plus a repeat count
+pub fn collapse_repeats(text: &str) -> String {
+    let mut output = String::new();
+    let mut lines = text.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        let mut repeats = 0;
+        while lines.peek() == Some(&line) {
+            lines.next();
+            repeats += 1;
+        }
+        output.push_str(line);
+        output.push('\n');
+        if repeats > 0 {
+            output.push_str(&format!(
+                "[previous line repeated {} more times]\n",
+                repeats
+            ));
+        }
+    }
+
+    output
+}
+
+/// Keep the start and end of `text` within the limits; returns (kept, omitted middle)
+pub fn head_and_tail(text: &str, max_lines: usize, max_bytes: usize) -> (String, String) {
+    let lines: Vec<&str> = text.lines().collect();
+    let budget_lines = max_lines.saturating_sub(1).max(2);
+    let half_bytes = max_bytes / 2;
+
+    let mut head = Vec::new();
+    let mut head_bytes = 0;
+    for line in lines.iter().take(budget_lines / 2) {
+        if head_bytes + line.len() + 1 > half_bytes {
+            break;
+        }
+        head_bytes += line.len() + 1;
+        head.push(*line);
+    }
+
+    let mut tail = Vec::new();
+    let mut tail_bytes = 0;
+    for line in lines
+        .iter()
+        .skip(head.len())
+        .rev()
+        .take(budget_lines - budget_lines / 2)
+    {
+        if tail_bytes + line.len() + 1 > half_bytes {
+            break;
+        }
+        tail_bytes += line.len() + 1;
+        tail.push(*line);
+    }
+    tail.reverse();
+
+    let omitted_lines = &lines[head.len()..lines.len() - tail.len()];
+    let mut head = head.join("\n");
+    let mut tail = tail.join("\n");
+    let mut omitted = omitted_lines.join("\n");
+    let mut marker = format!("[... {} lines omitted ...]", omitted_lines.len());
+
+    // A first or last line longer than half the budget would leave that end
+    // empty, so keep as many of its bytes as fit instead
+    if head.is_empty() || tail.is_empty() {
+        if head.is_empty() {
+            let cut = char_boundary_at_or_before(&omitted, half_bytes);
+            head = omitted[..cut].to_string();
+            omitted.drain(..cut);
+        }
+        if tail.is_empty() {
+            let mut cut = omitted.len().saturating_sub(half_bytes);
+            while !omitted.is_char_boundary(cut) {
+                cut += 1;
+            }
+            tail = omitted.split_off(cut);
+        }
+        marker = format!("[... {} bytes omitted ...]", omitted.len());
+    }
+
+    let kept = format!("{}\n{}\n{}\n", head, marker, tail);
+    (kept, omitted)
+}
+
+fn char_boundary_at_or_before(text: &str, index: usize) -> usize {
+    let mut index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+fn outputs_dir() -> std::io::Result<PathBuf> {
+    Ok(std::env::current_dir()?
+        .join(".wiseowlcli")
+        .join("tool-outputs"))
+}
+
+/// Path of a stored output, rejecting IDs that could escape the directory
+pub fn stored_output_path(id: &str) -> Result<PathBuf, String> {
+    if id.is_empty()
+        || !id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!("Invalid output id: {}", id));
+    }
+    outputs_dir()
+        .map(|dir| dir.join(format!("{}.log", id)))
+        .map_err(|e| e.to_string())
+}
+
+async fn store_output(tool: &str, output: &str) -> Result<String, Box<dyn std::error::Error>> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let id = format!(
+        "{}-{}-{}",
+        tool.replace(|c: char| !c.is_ascii_alphanumeric(), "_"),
+        chrono::Local::now().format("%Y%m%d%H%M%S"),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    );
+
+    tokio::fs::create_dir_all(outputs_dir()?).await?;
+    tokio::fs::write(stored_output_path(&id)?, output).await?;
+    Ok(id)
+}
+
+async fn summarize(client: &Client, model: &str, tool: &str, omitted: &str) -> Option<String> {
+    let input: String = omitted.chars().take(SUMMARY_INPUT_CHARS).collect();
+    let prompt = format!(
+        "Summarize this excerpt of `{}` tool output in at most 5 short lines. \
+         Keep error messages, file paths and counts verbatim.\n\n{}",
+        tool, input
+    );
+
+    let response = client
+        .post("http://localhost:11434/api/generate")
+        .json(&serde_json::json!({
+            "model": model,
+            "prompt": prompt,
+            "stream": false
+        }))
+        .send()
+        .await
+        .ok()?;
+    let json: serde_json::Value = response.json().await.ok()?;
+    json.get("response")
+        .and_then(|r| r.as_str())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collapse_repeats() {
+        let text = "a\nb\nb\nb\nc\n";
+        assert_eq!(
+            collapse_repeats(text),
+            "a\nb\n[previous line repeated 2 more times]\nc\n"
+        );
+    }
+
+    #[test]
+    fn test_head_and_tail() {
+        let text: String = (1..=100).map(|i| format!("line {}\n", i)).collect();
+        let (kept, omitted) = head_and_tail(&text, 11, 10_000);
+        assert!(kept.starts_with("line 1\n"));
+        assert!(kept.contains("[... 90 lines omitted ...]"));
+        assert!(kept.ends_with("line 100\n"));
+        assert_eq!(omitted.lines().count(), 90);
+    }
+
+    #[test]
+    fn test_head_and_tail_cuts_long_lines() {
+        let line = "é".repeat(1000);
+        let (kept, omitted) = head_and_tail(&line, 11, 1000);
+        let (head, rest) = kept.split_once('\n').unwrap();
+        assert_eq!(head.len(), 500);
+        assert!(rest.starts_with("[... 1000 bytes omitted ...]\n"));
+        assert_eq!(rest.len(), "[... 1000 bytes omitted ...]\n".len() + 500 + 1);
+        assert_eq!(omitted.len(), 1000);
+
+        let text = format!("short\n{}\n", "x".repeat(2000));
+        let (kept, _) = head_and_tail(&text, 11, 1000);
+        assert!(kept.starts_with("short\n[... 1500 bytes omitted ...]\nxxx"));
+    }
+
+    #[test]
+    fn test_stored_output_path_rejects_traversal() {
+        assert!(stored_output_path("../etc/passwd").is_err());
+        assert!(stored_output_path("").is_err());
+        assert!(stored_output_path("execute_bash-20260101-0").is_ok());
+    }
+
+    #[test]
+    fn test_default_policy_fits_small_output() {
+        let policy = OutputPolicy::default();
+        assert!(policy.fits("short output\n"));
+        let long: String = (0..500).map(|i| format!("{}\n", i)).collect();
+        assert!(!policy.fits(&long));
+    }
+}
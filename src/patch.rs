@@ -0,0 +1,513 @@
+// Unified diff parsing and fuzzy hunk application for the apply_patch tool
+
+const MAX_FUZZ: usize = 2;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HunkLine {
+    Context(String),
+    Add(String),
+    Remove(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Hunk {
+    pub old_start: usize,
+    pub new_start: usize,
+    pub lines: Vec<HunkLine>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FilePatch {
+    pub old_path: Option<String>,
+    pub new_path: Option<String>,
+    pub hunks: Vec<Hunk>,
+    pub no_newline_at_end: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct HunkFailure {
+    pub index: usize,
+    pub old_start: usize,
+    pub reason: String,
+}
+
+pub struct PatchResult {
+    pub content: String,
+    pub applied: usize,
+    pub fuzzed: usize,
+    pub failed: Vec<HunkFailure>,
+}
+
+impl FilePatch {
+    pub fn is_create(&self) -> bool {
+        self.old_path.is_none()
+    }
+
+    pub fn is_delete(&self) -> bool {
+        self.new_path.is_none()
+    }
+
+    /// Path the patch applies to, preferring the new name for renames
+    pub fn path(&self) -> &str {
+        self.new_path
+            .as_deref()
+            .or(self.old_path.as_deref())
+            .unwrap_or("")
+    }
+}
+
+impl Hunk {
+    fn old_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|l| match l {
+                HunkLine::Context(s) | HunkLine::Remove(s) => Some(s.as_str()),
+                HunkLine::Add(_) => None,
+            })
+            .collect()
+    }
+
+    fn new_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|l| match l {
+                HunkLine::Context(s) | HunkLine::Add(s) => Some(s.as_str()),
+                HunkLine::Remove(_) => None,
+            })
+            .collect()
+    }
+
+    /// Drop up to `fuzz` context lines from each edge of the hunk
+    fn trimmed(&self, fuzz: usize) -> Hunk {
+        let lead = self
+            .lines
+            .iter()
+            .take(fuzz)
+            .take_while(|l| matches!(l, HunkLine::Context(_)))
+            .count();
+        let trail = self
+            .lines
+            .iter()
+            .rev()
+            .take(fuzz)
+            .take_while(|l| matches!(l, HunkLine::Context(_)))
+            .count();
+        let end = self.lines.len().saturating_sub(trail).max(lead);
+        Hunk {
+            old_start: self.old_start + lead,
+            new_start: self.new_start + lead,
+            lines: self.lines[lead..end].to_vec(),
+        }
+    }
+}
+
+pub fn parse_unified_diff(text: &str) -> Result<Vec<FilePatch>, String> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut patches: Vec<FilePatch> = Vec::new();
+    let mut current: Option<FilePatch> = None;
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+
+        if let Some(header) = line.strip_prefix("diff --git ") {
+            if let Some(patch) = current.take() {
+                patches.push(patch);
+            }
+            let (old, new) = split_git_paths(header);
+            current = Some(FilePatch {
+                old_path: Some(old),
+                new_path: Some(new),
+                hunks: Vec::new(),
+                no_newline_at_end: false,
+            });
+            i += 1;
+            continue;
+        }
+
+        if line.starts_with("new file mode") {
+            if let Some(patch) = current.as_mut() {
+                patch.old_path = None;
+            }
+            i += 1;
+            continue;
+        }
+
+        if line.starts_with("deleted file mode") {
+            if let Some(patch) = current.as_mut() {
+                patch.new_path = None;
+            }
+            i += 1;
+            continue;
+        }
+
+        if is_file_header(&lines, i) {
+            let old = parse_header_path(&line[4..]);
+            let new = parse_header_path(&lines[i + 1][4..]);
+            // A `diff --git` header already opened this file; otherwise start a new one
+            let reuse = current.as_ref().is_some_and(|p| p.hunks.is_empty());
+            if !reuse {
+                if let Some(patch) = current.take() {
+                    patches.push(patch);
+                }
+            }
+            current = Some(FilePatch {
+                old_path: old,
+                new_path: new,
+                hunks: Vec::new(),
+                no_newline_at_end: false,
+            });
+            i += 2;
+            continue;
+        }
+
+        if line.starts_with("@@") {
+            let patch = current
+                .as_mut()
+                .ok_or_else(|| format!("Hunk at line {} has no file header", i + 1))?;
+            let (old_start, new_start) = parse_hunk_header(line)
+                .ok_or_else(|| format!("Malformed hunk header at line {}: {}", i + 1, line))?;
+
+            let mut hunk = Hunk {
+                old_start,
+                new_start,
+                lines: Vec::new(),
+            };
+            i += 1;
+
+            // Line counts in model-written diffs are often wrong, so read until the next boundary
+            while i < lines.len() {
+                let body = lines[i];
+                if body.starts_with("@@") || body.starts_with("diff --git ") {
+                    break;
+                }
+                if is_file_header(&lines, i) {
+                    break;
+                }
+                if body.starts_with('\\') {
+                    // "\ No newline at end of file" refers to the line just before it
+                    if matches!(
+                        hunk.lines.last(),
+                        Some(HunkLine::Add(_)) | Some(HunkLine::Context(_))
+                    ) {
+                        patch.no_newline_at_end = true;
+                    }
+                    i += 1;
+                    continue;
+                }
+                match body.chars().next() {
+                    Some('+') => hunk.lines.push(HunkLine::Add(body[1..].to_string())),
+                    Some('-') => hunk.lines.push(HunkLine::Remove(body[1..].to_string())),
+                    Some(' ') => hunk.lines.push(HunkLine::Context(body[1..].to_string())),
+                    None => hunk.lines.push(HunkLine::Context(String::new())),
+                    Some(_) => break,
+                }
+                i += 1;
+            }
+
+            // Trailing blank lines are usually separators rather than context
+            while matches!(hunk.lines.last(), Some(HunkLine::Context(s)) if s.is_empty()) {
+                hunk.lines.pop();
+            }
+
+            if hunk.lines.is_empty() {
+                return Err(format!("Empty hunk at line {}", i));
+            }
+            patch.hunks.push(hunk);
+            continue;
+        }
+
+        i += 1;
+    }
+
+    if let Some(patch) = current.take() {
+        patches.push(patch);
+    }
+
+    patches.retain(|p| !p.hunks.is_empty() || p.is_delete());
+
+    if patches.is_empty() {
+        return Err("No file changes found in patch".to_string());
+    }
+
+    Ok(patches)
+}
+
+/// `--- old` and `+++ new` followed by a hunk header. Requiring the `@@`
+/// keeps a removed "-- x" and an added "++ y" inside a hunk from ending it.
+fn is_file_header(lines: &[&str], i: usize) -> bool {
+    lines[i].starts_with("--- ")
+        && lines.get(i + 1).is_some_and(|l| l.starts_with("+++ "))
+        && lines.get(i + 2).is_none_or(|l| l.starts_with("@@"))
+}
+
+/// The two paths of "diff --git a/x b/x", which may contain spaces
+fn split_git_paths(header: &str) -> (String, String) {
+    let header = header.trim();
+    // Unrenamed files (the usual case) have the same path on both sides
+    let middle = header.len() / 2;
+    if header.len() % 2 == 1 && header.is_char_boundary(middle) && header[middle..].starts_with(' ')
+    {
+        let (old, new) = (
+            strip_prefix(&header[..middle]),
+            strip_prefix(&header[middle + 1..]),
+        );
+        if old == new {
+            return (old, new);
+        }
+    }
+    let split = header.rfind(" b/").or_else(|| header.find(' '));
+    match split {
+        Some(at) => (strip_prefix(&header[..at]), strip_prefix(&header[at + 1..])),
+        None => (strip_prefix(header), strip_prefix(header)),
+    }
+}
+
+fn strip_prefix(path: &str) -> String {
+    path.strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path)
+        .to_string()
+}
+
+fn parse_header_path(raw: &str) -> Option<String> {
+    // Drop a trailing timestamp ("file\t2024-01-01 ...")
+    let path = raw.split('\t').next().unwrap_or("").trim();
+    if path == "/dev/null" || path.is_empty() {
+        None
+    } else {
+        Some(strip_prefix(path))
+    }
+}
+
+fn parse_hunk_header(line: &str) -> Option<(usize, usize)> {
+    let inner = line.trim_start_matches('@').trim();
+    let inner = inner.split("@@").next()?.trim();
+    let mut parts = inner.split_whitespace();
+    let old = parts.next()?.strip_prefix('-')?;
+    let new = parts.next()?.strip_prefix('+')?;
+    let start = |range: &str| range.split(',').next()?.parse::<usize>().ok();
+    Some((start(old)?, start(new)?))
+}
+
+/// Apply hunks in order, tolerating shifted offsets, whitespace drift and
+/// up to MAX_FUZZ mismatched context lines at each hunk edge.
+pub fn apply_hunks(original: &str, hunks: &[Hunk], no_newline_at_end: bool) -> PatchResult {
+    let mut lines: Vec<String> = original.lines().map(|l| l.to_string()).collect();
+    let ends_with_newline = original.is_empty() || original.ends_with('\n');
+    let mut offset: isize = 0;
+    let mut floor = 0;
+    let mut applied = 0;
+    let mut fuzzed = 0;
+    let mut failed = Vec::new();
+
+    for (index, hunk) in hunks.iter().enumerate() {
+        let mut placed = None;
+        for fuzz in 0..=MAX_FUZZ {
+            let candidate = if fuzz == 0 {
+                hunk.clone()
+            } else {
+                hunk.trimmed(fuzz)
+            };
+            let expected =
+                (candidate.old_start.saturating_sub(1) as isize + offset).max(0) as usize;
+            let old = candidate.old_lines();
+            if let Some(pos) = find_block(&lines, &old, expected, floor) {
+                placed = Some((pos, candidate, fuzz));
+                break;
+            }
+        }
+
+        match placed {
+            Some((pos, candidate, fuzz)) => {
+                let old_len = candidate.old_lines().len();
+                let new: Vec<String> = candidate
+                    .new_lines()
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect();
+                let new_len = new.len();
+                lines.splice(pos..pos + old_len, new);
+                offset += new_len as isize - old_len as isize;
+                floor = pos + new_len;
+                applied += 1;
+                if fuzz > 0 {
+                    fuzzed += 1;
+                }
+            }
+            None => failed.push(HunkFailure {
+                index: index + 1,
+                old_start: hunk.old_start,
+                reason: "context does not match file contents".to_string(),
+            }),
+        }
+    }
+
+    let mut content = lines.join("\n");
+    if !lines.is_empty() && ends_with_newline && !no_newline_at_end {
+        content.push('\n');
+    }
+
+    PatchResult {
+        content,
+        applied,
+        fuzzed,
+        failed,
+    }
+}
+
+/// Find `block` in `lines` at or after `floor`, nearest to `expected` first
+fn find_block(lines: &[String], block: &[&str], expected: usize, floor: usize) -> Option<usize> {
+    if block.is_empty() {
+        return Some(expected.clamp(floor, lines.len()));
+    }
+    if block.len() > lines.len() {
+        return None;
+    }
+    let last = lines.len() - block.len();
+    if floor > last {
+        return None;
+    }
+
+    let matches_at = |pos: usize, loose: bool| {
+        block.iter().enumerate().all(|(k, b)| {
+            if loose {
+                lines[pos + k].trim() == b.trim()
+            } else {
+                lines[pos + k] == *b
+            }
+        })
+    };
+
+    let expected = expected.clamp(floor, last);
+    for loose in [false, true] {
+        for distance in 0..=(last - floor) {
+            let after = expected + distance;
+            if after <= last && matches_at(after, loose) {
+                return Some(after);
+            }
+            if distance > 0
+                && expected >= floor + distance
+                && matches_at(expected - distance, loose)
+            {
+                return Some(expected - distance);
+            }
+            if after > last && expected < floor + distance {
+                break;
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_multi_file_diff() {
+        let diff = "\
+diff --git a/src/a.rs b/src/a.rs
+--- a/src/a.rs
++++ b/src/a.rs
+@@ -1,2 +1,2 @@
+ fn a() {}
+-fn b() {}
++fn c() {}
+diff --git a/new.txt b/new.txt
+new file mode 100644
+--- /dev/null
++++ b/new.txt
+@@ -0,0 +1 @@
++hello
+diff --git a/old.txt b/old.txt
+deleted file mode 100644
+--- a/old.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-bye
+";
+        let patches = parse_unified_diff(diff).unwrap();
+        assert_eq!(patches.len(), 3);
+        assert_eq!(patches[0].path(), "src/a.rs");
+        assert!(patches[1].is_create());
+        assert_eq!(patches[1].path(), "new.txt");
+        assert!(patches[2].is_delete());
+        assert_eq!(patches[2].path(), "old.txt");
+    }
+
+    #[test]
+    fn test_apply_with_offset() {
+        let original = "x\ny\nfn a() {}\nfn b() {}\n";
+        let patches = parse_unified_diff(
+            "--- a/f\n+++ b/f\n@@ -1,2 +1,2 @@\n fn a() {}\n-fn b() {}\n+fn c() {}\n",
+        )
+        .unwrap();
+        let result = apply_hunks(original, &patches[0].hunks, false);
+        assert_eq!(result.applied, 1);
+        assert!(result.failed.is_empty());
+        assert_eq!(result.content, "x\ny\nfn a() {}\nfn c() {}\n");
+    }
+
+    #[test]
+    fn test_apply_with_fuzzy_context() {
+        let original = "one\ntwo\nthree\nfour\n";
+        let patches = parse_unified_diff(
+            "--- a/f\n+++ b/f\n@@ -1,4 +1,4 @@\n uno\n two\n-three\n+3\n four\n",
+        )
+        .unwrap();
+        let result = apply_hunks(original, &patches[0].hunks, false);
+        assert_eq!(result.applied, 1);
+        assert_eq!(result.fuzzed, 1);
+        assert_eq!(result.content, "one\ntwo\n3\nfour\n");
+    }
+
+    #[test]
+    fn test_failed_hunk_reported() {
+        let original = "alpha\nbeta\n";
+        let patches =
+            parse_unified_diff("--- a/f\n+++ b/f\n@@ -1,1 +1,1 @@\n-gamma\n+delta\n").unwrap();
+        let result = apply_hunks(original, &patches[0].hunks, false);
+        assert_eq!(result.applied, 0);
+        assert_eq!(result.failed.len(), 1);
+        assert_eq!(result.content, original);
+    }
+
+    #[test]
+    fn test_dashed_body_lines_and_spaced_paths() {
+        let diff = "\
+diff --git a/docs/my notes.md b/docs/my notes.md
+--- a/docs/my notes.md
++++ b/docs/my notes.md
+@@ -1,3 +1,3 @@
+ title
+--- rule
++++ bold
+ end
+";
+        let patches = parse_unified_diff(diff).unwrap();
+        assert_eq!(patches.len(), 1);
+        assert_eq!(patches[0].path(), "docs/my notes.md");
+        assert_eq!(
+            patches[0].hunks[0].lines,
+            vec![
+                HunkLine::Context("title".to_string()),
+                HunkLine::Remove("-- rule".to_string()),
+                HunkLine::Add("++ bold".to_string()),
+                HunkLine::Context("end".to_string()),
+            ]
+        );
+
+        assert_eq!(
+            split_git_paths("a/old name.txt b/new name.txt"),
+            ("old name.txt".to_string(), "new name.txt".to_string())
+        );
+    }
+
+    #[test]
+    fn test_no_file_header_is_error() {
+        assert!(parse_unified_diff("@@ -1 +1 @@\n-a\n+b\n").is_err());
+        assert!(parse_unified_diff("just some text").is_err());
+    }
+}
@@ -0,0 +1,358 @@
+use std::collections::{HashMap, VecDeque};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex as StdMutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::{Mutex, Notify};
+
+const MAX_BUFFERED_LINES: usize = 1000;
+
+/// Ring buffer of combined stdout/stderr lines with a read cursor
+pub struct OutputBuffer {
+    lines: VecDeque<String>,
+    total: usize,
+    read_upto: usize,
+}
+
+impl Default for OutputBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OutputBuffer {
+    pub fn new() -> Self {
+        Self {
+            lines: VecDeque::new(),
+            total: 0,
+            read_upto: 0,
+        }
+    }
+
+    pub fn push(&mut self, line: String) {
+        self.lines.push_back(line);
+        self.total += 1;
+        if self.lines.len() > MAX_BUFFERED_LINES {
+            self.lines.pop_front();
+        }
+    }
+
+    /// Lines added since the previous call, noting any that fell out of the buffer
+    pub fn read_new(&mut self) -> String {
+        let first_kept = self.total - self.lines.len();
+        let mut output = String::new();
+        if self.read_upto < first_kept {
+            output.push_str(&format!(
+                "[{} lines dropped from buffer]\n",
+                first_kept - self.read_upto
+            ));
+            self.read_upto = first_kept;
+        }
+        for line in self.lines.iter().skip(self.read_upto - first_kept) {
+            output.push_str(line);
+            output.push('\n');
+        }
+        self.read_upto = self.total;
+        output
+    }
+
+    pub fn tail(&self, count: usize) -> String {
+        let skip = self.lines.len().saturating_sub(count);
+        let mut output = String::new();
+        for line in self.lines.iter().skip(skip) {
+            output.push_str(line);
+            output.push('\n');
+        }
+        output
+    }
+}
+
+struct ManagedProcess {
+    command: String,
+    child: Child,
+    stdin: Option<ChildStdin>,
+    output: Arc<StdMutex<OutputBuffer>>,
+    started: Instant,
+}
+
+pub struct ProcessInfo {
+    pub name: String,
+    pub pid: Option<u32>,
+    pub command: String,
+    pub status: String,
+    pub uptime: Duration,
+}
+
+pub struct ProcessManager {
+    processes: HashMap<String, ManagedProcess>,
+}
+
+/// Background processes outlive a single tool call, so they live in one table per session
+pub fn processes() -> &'static Mutex<ProcessManager> {
+    static MANAGER: OnceLock<Mutex<ProcessManager>> = OnceLock::new();
+    MANAGER.get_or_init(|| Mutex::new(ProcessManager::new()))
+}
+
+impl Default for ProcessManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProcessManager {
+    pub fn new() -> Self {
+        Self {
+            processes: HashMap::new(),
+        }
+    }
+
+    pub fn start(&mut self, name: &str, command: &str, cwd: Option<&str>) -> Result<u32, String> {
+        if let Some(existing) = self.processes.get_mut(name) {
+            if matches!(existing.child.try_wait(), Ok(None)) {
+                return Err(format!("Process '{}' is already running", name));
+            }
+        }
+
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c")
+            .arg(command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        if let Some(dir) = cwd {
+            cmd.current_dir(dir);
+        }
+        // Own process group so shutdown also reaches grandchildren (npm -> node, etc.).
+        // The group doesn't see the terminal's Ctrl+C, so every way the session
+        // ends must go through `shutdown_all`.
+        #[cfg(unix)]
+        cmd.process_group(0);
+
+        let mut child = cmd
+            .spawn()
+            .map_err(|e| format!("Failed to start '{}': {}", name, e))?;
+        let pid = child.id().unwrap_or(0);
+        let output = Arc::new(StdMutex::new(OutputBuffer::new()));
+
+        if let Some(stdout) = child.stdout.take() {
+            spawn_reader(stdout, output.clone(), "");
+        }
+        if let Some(stderr) = child.stderr.take() {
+            spawn_reader(stderr, output.clone(), "[stderr] ");
+        }
+
+        self.processes.insert(
+            name.to_string(),
+            ManagedProcess {
+                command: command.to_string(),
+                stdin: child.stdin.take(),
+                child,
+                output,
+                started: Instant::now(),
+            },
+        );
+
+        Ok(pid)
+    }
+
+    pub fn read_output(&mut self, name: &str, tail: Option<usize>) -> Result<String, String> {
+        let process = self
+            .processes
+            .get_mut(name)
+            .ok_or_else(|| format!("No process named '{}'", name))?;
+        let status = status_of(&mut process.child);
+
+        let mut buffer = process.output.lock().unwrap();
+        let output = match tail {
+            Some(count) => buffer.tail(count),
+            None => buffer.read_new(),
+        };
+
+        Ok(format!("Status: {}\n{}", status, output))
+    }
+
+    pub async fn send_input(&mut self, name: &str, input: &str) -> Result<(), String> {
+        let process = self
+            .processes
+            .get_mut(name)
+            .ok_or_else(|| format!("No process named '{}'", name))?;
+        let stdin = process
+            .stdin
+            .as_mut()
+            .ok_or_else(|| format!("stdin of '{}' is closed", name))?;
+
+        let mut data = input.to_string();
+        if !data.ends_with('\n') {
+            data.push('\n');
+        }
+        stdin
+            .write_all(data.as_bytes())
+            .await
+            .map_err(|e| format!("Failed to write to '{}': {}", name, e))?;
+        stdin
+            .flush()
+            .await
+            .map_err(|e| format!("Failed to write to '{}': {}", name, e))
+    }
+
+    pub async fn kill(&mut self, name: &str) -> Result<String, String> {
+        let mut process = self
+            .processes
+            .remove(name)
+            .ok_or_else(|| format!("No process named '{}'", name))?;
+
+        if let Ok(Some(status)) = process.child.try_wait() {
+            return Ok(format!("'{}' had already exited ({})", name, status));
+        }
+        terminate(&mut process.child)
+            .await
+            .map_err(|e| format!("Failed to kill '{}': {}", name, e))?;
+        Ok(format!("Killed '{}'", name))
+    }
+
+    pub fn list(&mut self) -> Vec<ProcessInfo> {
+        let mut infos: Vec<ProcessInfo> = self
+            .processes
+            .iter_mut()
+            .map(|(name, process)| ProcessInfo {
+                name: name.clone(),
+                pid: process.child.id(),
+                command: process.command.clone(),
+                status: status_of(&mut process.child),
+                uptime: process.started.elapsed(),
+            })
+            .collect();
+        infos.sort_by(|a, b| a.name.cmp(&b.name));
+        infos
+    }
+
+    /// Kill every child; called when the chat session ends
+    pub async fn shutdown_all(&mut self) -> usize {
+        let mut killed = 0;
+        for (_, mut process) in self.processes.drain() {
+            if matches!(process.child.try_wait(), Ok(None))
+                && terminate(&mut process.child).await.is_ok()
+            {
+                killed += 1;
+            }
+        }
+        killed
+    }
+}
+
+/// Completes once the process is told to terminate or its terminal closes
+/// (SIGTERM or SIGHUP), so background processes can be shut down first
+pub async fn terminated() {
+    static TERMINATED: OnceLock<Notify> = OnceLock::new();
+    let notify = TERMINATED.get_or_init(|| {
+        #[cfg(unix)]
+        tokio::spawn(async {
+            use tokio::signal::unix::{signal, SignalKind};
+            if let (Ok(mut term), Ok(mut hup)) = (
+                signal(SignalKind::terminate()),
+                signal(SignalKind::hangup()),
+            ) {
+                tokio::select! {
+                    _ = term.recv() => {}
+                    _ = hup.recv() => {}
+                }
+                if let Some(notify) = TERMINATED.get() {
+                    notify.notify_one();
+                }
+            }
+        });
+        Notify::new()
+    });
+    notify.notified().await;
+}
+
+async fn terminate(child: &mut Child) -> std::io::Result<()> {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        let _ = Command::new("kill")
+            .arg("-TERM")
+            .arg(format!("-{}", pid))
+            .status()
+            .await;
+        if tokio::time::timeout(Duration::from_secs(2), child.wait())
+            .await
+            .is_ok()
+        {
+            return Ok(());
+        }
+    }
+    child.kill().await
+}
+
+fn status_of(child: &mut Child) -> String {
+    match child.try_wait() {
+        Ok(None) => "running".to_string(),
+        Ok(Some(status)) => match status.code() {
+            Some(code) => format!("exited ({})", code),
+            None => "terminated by signal".to_string(),
+        },
+        Err(e) => format!("unknown ({})", e),
+    }
+}
+
+fn spawn_reader<R>(stream: R, output: Arc<StdMutex<OutputBuffer>>, prefix: &'static str)
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(stream).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            output.lock().unwrap().push(format!("{}{}", prefix, line));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_buffer_read_new() {
+        let mut buffer = OutputBuffer::new();
+        buffer.push("one".to_string());
+        buffer.push("two".to_string());
+        assert_eq!(buffer.read_new(), "one\ntwo\n");
+        assert_eq!(buffer.read_new(), "");
+        buffer.push("three".to_string());
+        assert_eq!(buffer.read_new(), "three\n");
+    }
+
+    #[test]
+    fn test_output_buffer_drops_oldest() {
+        let mut buffer = OutputBuffer::new();
+        for i in 0..MAX_BUFFERED_LINES + 5 {
+            buffer.push(i.to_string());
+        }
+        let output = buffer.read_new();
+        assert!(output.starts_with("[5 lines dropped from buffer]\n5\n"));
+        assert_eq!(
+            buffer.tail(2),
+            format!("{}\n{}\n", MAX_BUFFERED_LINES + 3, MAX_BUFFERED_LINES + 4)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_start_input_and_kill() {
+        let mut manager = ProcessManager::new();
+        manager
+            .start("echo", "read line; echo got $line", None)
+            .unwrap();
+        assert!(manager.start("echo", "true", None).is_err());
+
+        manager.send_input("echo", "hello").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let output = manager.read_output("echo", None).unwrap();
+        assert!(output.contains("got hello"));
+
+        assert_eq!(manager.list().len(), 1);
+        assert!(manager.kill("echo").await.is_ok());
+        assert!(manager.list().is_empty());
+    }
+}
@@ -9,29 +9,67 @@ TOOL USAGE RULES:
 3. Chain multiple tools in one response
 4. Read files BEFORE answering questions about them
 5. Execute commands BEFORE reporting results
+6. Tool output comes back in <tool_result> tags - never write those yourself
 
 AVAILABLE TOOLS:
 
 <read_file>
-Read file contents
+Read file contents with line numbers (optional offset/limit line range)
 <tool_call>{"tool":"read_file","parameters":{"path":"file.txt"}}</tool_call>
+<tool_call>{"tool":"read_file","parameters":{"path":"big.log","offset":200,"limit":50}}</tool_call>
+
+<read_tool_output>
+Long tool output is shortened; read the saved full output by the id in the notice
+<tool_call>{"tool":"read_tool_output","parameters":{"id":"execute_bash-20260101120000-0","offset":400,"limit":100}}</tool_call>
 
 <write_file>
 Write/modify files (creates backup)
 <tool_call>{"tool":"write_file","parameters":{"path":"file.txt","content":"..."}}</tool_call>
 
+<apply_patch>
+Apply a unified diff (multi-file, create/delete supported)
+<tool_call>{"tool":"apply_patch","parameters":{"patch":"--- a/file.txt\n+++ b/file.txt\n@@ -1 +1 @@\n-old\n+new\n"}}</tool_call>
+
 <execute_bash>
 Run shell commands
 <tool_call>{"tool":"execute_bash","parameters":{"command":"ls -la"}}</tool_call>
 
+<start_process> / <read_process_output> / <send_process_input> / <kill_process>
+Run long-lived commands (dev servers, watchers) in the background and check on them
+<tool_call>{"tool":"start_process","parameters":{"name":"server","command":"npm run dev"}}</tool_call>
+<tool_call>{"tool":"read_process_output","parameters":{"name":"server"}}</tool_call>
+<tool_call>{"tool":"kill_process","parameters":{"name":"server"}}</tool_call>
+
 <search_files>
 Find files by pattern
 <tool_call>{"tool":"search_files","parameters":{"pattern":"*.rs","directory":"."}}</tool_call>
 
+<grep>
+Search file contents by regex (respects .gitignore; optional include/exclude globs, context, max_results)
+<tool_call>{"tool":"grep","parameters":{"pattern":"fn main","path":"src","include":"*.rs","context":2}}</tool_call>
+
+<outline> / <find_symbol>
+Jump to code without reading whole files: list a file's symbols with line ranges, or find a definition
+<tool_call>{"tool":"outline","parameters":{"path":"src/server.rs"}}</tool_call>
+<tool_call>{"tool":"find_symbol","parameters":{"name":"handle_request"}}</tool_call>
+
 <list_directory>
 List directory contents
 <tool_call>{"tool":"list_directory","parameters":{"path":"."}}</tool_call>
 
+<run_tests>
+Run the test suite (optionally filtered) and get passes plus each failure's name, file and message
+<tool_call>{"tool":"run_tests","parameters":{"filter":"parser"}}</tool_call>
+
+<git_status> / <git_diff> / <git_log> / <git_show>
+Inspect the repository: what changed, staged changes, history
+<tool_call>{"tool":"git_diff","parameters":{"staged":false,"path":"src/main.rs"}}</tool_call>
+<tool_call>{"tool":"git_show","parameters":{"rev":"HEAD","stat":true}}</tool_call>
+
+<git_commit>
+Commit all tracked changes (the user approves each commit)
+<tool_call>{"tool":"git_commit","parameters":{"message":"Fix off-by-one in pager"}}</tool_call>
+
 RESPONSE PATTERN:
 User: "What's in main.rs?"
 You: <tool_call>{"tool":"read_file","parameters":{"path":"main.rs"}}</tool_call>
@@ -114,16 +152,16 @@ You:
 pub async fn get_system_prompt_with_mcp() -> Result<String, Box<dyn std::error::Error>> {
     let mut prompt = get_system_prompt();
 
-    let mut mcp_client = crate::mcp::MCPClient::new();
-    if mcp_client.load_config().await.is_ok() && mcp_client.discover_tools().await.is_ok() {
-        let tools = mcp_client.list_available_tools();
-        if !tools.is_empty() {
-            prompt.push_str("\n\n## MCP Tools Available\n");
-            prompt.push_str("You have access to these additional tools via MCP:\n");
-            for tool in tools {
-                prompt.push_str(&format!("- {}: {}\n", tool.name, tool.description));
-            }
-            prompt.push_str("\nTo use: Call /mcp call <tool_name> <params>\n");
+    let mcp_tools: Vec<crate::tools::Tool> = crate::tools::tool_catalog()
+        .await
+        .into_iter()
+        .filter(|t| !crate::tools::is_builtin(&t.name))
+        .collect();
+    if !mcp_tools.is_empty() {
+        prompt.push_str("\n\n## MCP Tools Available\n");
+        prompt.push_str("Call these exactly like the tools above, with a <tool_call> block:\n\n");
+        for tool in &mcp_tools {
+            prompt.push_str(&crate::tools::tool_prompt(tool));
         }
     }
 
@@ -0,0 +1,262 @@
+use ignore::overrides::OverrideBuilder;
+use ignore::WalkBuilder;
+use regex::RegexBuilder;
+use std::path::Path;
+
+const DEFAULT_MAX_RESULTS: usize = 100;
+const MAX_FILE_SIZE: u64 = 2 * 1024 * 1024;
+
+pub struct GrepOptions {
+    pub pattern: String,
+    pub path: String,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub context: usize,
+    pub max_results: usize,
+    pub case_insensitive: bool,
+}
+
+impl GrepOptions {
+    pub fn new(pattern: &str) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            path: ".".to_string(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            context: 0,
+            max_results: DEFAULT_MAX_RESULTS,
+            case_insensitive: false,
+        }
+    }
+}
+
+pub struct GrepMatch {
+    pub path: String,
+    pub line_number: usize,
+    pub line: String,
+    pub before: Vec<(usize, String)>,
+    pub after: Vec<(usize, String)>,
+}
+
+pub struct GrepResults {
+    pub matches: Vec<GrepMatch>,
+    pub files_searched: usize,
+    pub truncated: bool,
+}
+
+impl GrepResults {
+    /// Render in ripgrep style: `path:line:text` for matches, `path-line-text` for context
+    pub fn display(&self) -> String {
+        if self.matches.is_empty() {
+            return format!("No matches ({} files searched)\n", self.files_searched);
+        }
+
+        let mut output = String::new();
+        let with_context = self
+            .matches
+            .iter()
+            .any(|m| !m.before.is_empty() || !m.after.is_empty());
+
+        for (i, m) in self.matches.iter().enumerate() {
+            if with_context && i > 0 {
+                output.push_str("--\n");
+            }
+            for (n, text) in &m.before {
+                output.push_str(&format!("{}-{}-{}\n", m.path, n, text));
+            }
+            output.push_str(&format!("{}:{}:{}\n", m.path, m.line_number, m.line));
+            for (n, text) in &m.after {
+                output.push_str(&format!("{}-{}-{}\n", m.path, n, text));
+            }
+        }
+
+        if self.truncated {
+            output.push_str(&format!(
+                "[results capped at {} matches; narrow the pattern or path]\n",
+                self.matches.len()
+            ));
+        }
+
+        output
+    }
+}
+
+/// Heuristic used by git and ripgrep: a NUL byte near the start means binary
+pub fn looks_binary(bytes: &[u8]) -> bool {
+    bytes.iter().take(8000).any(|&b| b == 0)
+}
+
+/// Regex search over file contents, honoring .gitignore and include/exclude globs
+pub fn grep(options: &GrepOptions) -> Result<GrepResults, String> {
+    let regex = RegexBuilder::new(&options.pattern)
+        .case_insensitive(options.case_insensitive)
+        .build()
+        .map_err(|e| format!("Invalid regex: {}", e))?;
+
+    let root = Path::new(&options.path);
+    if !root.exists() {
+        return Err(format!("Path not found: {}", options.path));
+    }
+
+    let mut walker = WalkBuilder::new(root);
+    walker.require_git(false);
+
+    if !options.include.is_empty() || !options.exclude.is_empty() {
+        let base = if root.is_dir() {
+            root
+        } else {
+            root.parent().unwrap_or(Path::new("."))
+        };
+        let mut overrides = OverrideBuilder::new(base);
+        for glob in &options.include {
+            overrides
+                .add(glob)
+                .map_err(|e| format!("Invalid include glob: {}", e))?;
+        }
+        for glob in &options.exclude {
+            overrides
+                .add(&format!("!{}", glob))
+                .map_err(|e| format!("Invalid exclude glob: {}", e))?;
+        }
+        walker.overrides(
+            overrides
+                .build()
+                .map_err(|e| format!("Invalid glob: {}", e))?,
+        );
+    }
+
+    let max_results = if options.max_results == 0 {
+        DEFAULT_MAX_RESULTS
+    } else {
+        options.max_results
+    };
+
+    let mut results = GrepResults {
+        matches: Vec::new(),
+        files_searched: 0,
+        truncated: false,
+    };
+
+    for entry in walker.build().filter_map(|e| e.ok()) {
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        if entry
+            .metadata()
+            .map(|m| m.len() > MAX_FILE_SIZE)
+            .unwrap_or(true)
+        {
+            continue;
+        }
+
+        let bytes = match std::fs::read(entry.path()) {
+            Ok(b) => b,
+            Err(_) => continue,
+        };
+        if looks_binary(&bytes) {
+            continue;
+        }
+        let content = String::from_utf8_lossy(&bytes);
+        results.files_searched += 1;
+
+        let path = entry
+            .path()
+            .strip_prefix("./")
+            .unwrap_or(entry.path())
+            .display()
+            .to_string();
+        let lines: Vec<&str> = content.lines().collect();
+
+        for (idx, line) in lines.iter().enumerate() {
+            if !regex.is_match(line) {
+                continue;
+            }
+            if results.matches.len() >= max_results {
+                results.truncated = true;
+                return Ok(results);
+            }
+
+            let start = idx.saturating_sub(options.context);
+            let end = (idx + options.context + 1).min(lines.len());
+            results.matches.push(GrepMatch {
+                path: path.clone(),
+                line_number: idx + 1,
+                line: line.to_string(),
+                before: (start..idx)
+                    .map(|n| (n + 1, lines[n].to_string()))
+                    .collect(),
+                after: (idx + 1..end)
+                    .map(|n| (n + 1, lines[n].to_string()))
+                    .collect(),
+            });
+        }
+    }
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> std::path::PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("wiseowl_grep_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::create_dir_all(dir.join("target")).unwrap();
+        std::fs::write(dir.join(".gitignore"), "target/\n").unwrap();
+        std::fs::write(
+            dir.join("src/lib.rs"),
+            "fn helper() {}\n\nfn main() {\n    helper();\n}\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("src/notes.md"), "call helper() here\n").unwrap();
+        std::fs::write(dir.join("target/out.rs"), "helper();\n").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_grep_respects_gitignore() {
+        let dir = fixture("gitignore");
+        let mut options = GrepOptions::new(r"helper\(\)");
+        options.path = dir.display().to_string();
+        let results = grep(&options).unwrap();
+        assert_eq!(results.matches.len(), 3);
+        assert!(results.matches.iter().all(|m| !m.path.contains("target")));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_grep_include_and_context() {
+        let dir = fixture("include");
+        let mut options = GrepOptions::new(r"^\s+helper");
+        options.path = dir.display().to_string();
+        options.include = vec!["*.rs".to_string()];
+        options.context = 1;
+        let results = grep(&options).unwrap();
+        assert_eq!(results.matches.len(), 1);
+        assert_eq!(results.matches[0].line_number, 4);
+        assert_eq!(results.matches[0].before.len(), 1);
+        assert_eq!(results.matches[0].after.len(), 1);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_grep_result_cap() {
+        let dir = fixture("cap");
+        let mut options = GrepOptions::new("helper");
+        options.path = dir.display().to_string();
+        options.exclude = vec!["*.md".to_string()];
+        options.max_results = 1;
+        let results = grep(&options).unwrap();
+        assert_eq!(results.matches.len(), 1);
+        assert!(results.truncated);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_grep_invalid_regex() {
+        assert!(grep(&GrepOptions::new("(unclosed")).is_err());
+    }
+}
@@ -1,10 +1,20 @@
+use crate::multi_file::MultiFileEditor;
+use crate::output_policy::OutputPolicy;
+use crate::tool_parser::{self, ParsedCalls};
+use crate::tools::{execute_tool_calls, is_read_only, needs_approval, ToolCall, ToolResult};
 use futures_util::StreamExt;
 use reqwest::Client;
 use serde_json::Value;
 use std::io::{self, Write};
+use std::time::Duration;
+use tokio::sync::mpsc;
 
 const UPDATE_INTERVAL: usize = 20;
 const BUFFER_SIZE: usize = 512;
+const MAX_TOOL_ROUNDS: usize = 10;
+const CALL_OPEN: &str = "<tool_call>";
+const CALL_CLOSE: &str = "</tool_call>";
+const RESULT_OPEN: &str = "<tool_result";
 
 pub struct StreamStats {
     pub token_count: usize,
@@ -27,11 +37,25 @@ impl StreamStats {
     }
 }
 
-pub async fn stream_response(
+/// What to do after each streamed piece of text
+#[derive(Debug, PartialEq)]
+pub enum StreamControl {
+    Continue,
+    Stop,
+}
+
+/// Stream a response, handing each piece of text to `on_text` as it arrives.
+/// Returning `StreamControl::Stop` closes the connection, which also stops
+/// generation on the server.
+pub async fn stream_response_until<F>(
     client: &Client,
     model: &str,
     prompt: &str,
-) -> Result<(String, StreamStats), Box<dyn std::error::Error>> {
+    mut on_text: F,
+) -> Result<(String, StreamStats), Box<dyn std::error::Error>>
+where
+    F: FnMut(&str) -> StreamControl,
+{
     let mut full_response = String::new();
     let mut output_buffer = String::with_capacity(BUFFER_SIZE);
     let start_time = std::time::Instant::now();
@@ -54,7 +78,7 @@ pub async fn stream_response(
 
     let mut stream = response.bytes_stream();
 
-    while let Some(chunk) = stream.next().await {
+    'stream: while let Some(chunk) = stream.next().await {
         let chunk = chunk?;
         if let Ok(text) = std::str::from_utf8(&chunk) {
             for line in text.lines() {
@@ -82,6 +106,12 @@ pub async fn stream_response(
                             io::stdout().flush()?;
                             output_buffer.clear();
                         }
+
+                        if on_text(response_text) == StreamControl::Stop {
+                            print!("{}", output_buffer);
+                            println!();
+                            break 'stream;
+                        }
                     }
                     if json.get("done").and_then(|d| d.as_bool()).unwrap_or(false) {
                         if !output_buffer.is_empty() {
@@ -100,6 +130,269 @@ pub async fn stream_response(
     Ok((full_response, stats))
 }
 
+/// Finds complete `<tool_call>` blocks in a response while it streams, and
+/// notices when the model starts writing a `<tool_result>` of its own
+pub struct ToolCallDetector {
+    text: String,
+    scanned: usize,
+    executed: Vec<(usize, usize)>,
+    fake_result_at: Option<usize>,
+}
+
+impl Default for ToolCallDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ToolCallDetector {
+    pub fn new() -> Self {
+        Self {
+            text: String::new(),
+            scanned: 0,
+            executed: Vec::new(),
+            fake_result_at: None,
+        }
+    }
+
+    /// Add streamed text and return any tool call blocks it completed
+    pub fn push(&mut self, chunk: &str) -> Vec<String> {
+        let mut blocks = Vec::new();
+        if self.fake_result_at.is_some() {
+            return blocks;
+        }
+        self.text.push_str(chunk);
+
+        loop {
+            let rest = &self.text[self.scanned..];
+            let call = rest.find(CALL_OPEN);
+            let result = rest.find(RESULT_OPEN);
+            match (call, result) {
+                (_, Some(r)) if call.is_none_or(|c| r < c) => {
+                    self.fake_result_at = Some(self.scanned + r);
+                    break;
+                }
+                (Some(c), _) => {
+                    let begin = self.scanned + c;
+                    let Some(len) = self.text[begin..].find(CALL_CLOSE) else {
+                        break;
+                    };
+                    let end = begin + len + CALL_CLOSE.len();
+                    blocks.push(self.text[begin..end].to_string());
+                    self.executed.push((begin, end));
+                    self.scanned = end;
+                }
+                _ => {
+                    // Keep the tail that could be the start of a tag split across chunks
+                    let mut keep = self.text.len().saturating_sub(RESULT_OPEN.len());
+                    while !self.text.is_char_boundary(keep) {
+                        keep -= 1;
+                    }
+                    self.scanned = self.scanned.max(keep);
+                    break;
+                }
+            }
+        }
+
+        blocks
+    }
+
+    pub fn fake_result_started(&self) -> bool {
+        self.fake_result_at.is_some()
+    }
+
+    /// The response up to any fake tool result
+    pub fn response(&self) -> &str {
+        &self.text[..self.fake_result_at.unwrap_or(self.text.len())]
+    }
+
+    /// The response without the blocks already executed, for the final parse
+    /// that picks up the other call formats
+    pub fn remainder(&self) -> String {
+        let response = self.response();
+        let mut rest = String::new();
+        let mut position = 0;
+        for &(begin, end) in &self.executed {
+            rest.push_str(&response[position..begin]);
+            position = end;
+        }
+        rest.push_str(&response[position..]);
+        rest
+    }
+}
+
+enum CallOutcome {
+    ParseError(String),
+    Ran(ToolCall, ToolResult, Duration),
+}
+
+/// Parse a batch of streamed tool call blocks
+fn parse_blocks(blocks: &[String], outcomes: &mut Vec<CallOutcome>) -> Vec<ToolCall> {
+    let mut calls = Vec::new();
+    for block in blocks {
+        let parsed = tool_parser::parse(block);
+        calls.extend(parsed.calls);
+        outcomes.extend(parsed.errors.into_iter().map(CallOutcome::ParseError));
+    }
+    calls
+}
+
+/// Split off the calls that must wait for the response to finish: the first
+/// one that may ask for approval and everything after it, so a prompt never
+/// lands in the middle of streamed text and calls still run in order
+fn split_held(calls: &mut Vec<ToolCall>) -> Vec<ToolCall> {
+    let ready = calls
+        .iter()
+        .take_while(|c| !needs_approval(&c.tool))
+        .count();
+    calls.split_off(ready)
+}
+
+async fn run_calls(
+    calls: Vec<ToolCall>,
+    staging: Option<&mut MultiFileEditor>,
+    outcomes: &mut Vec<CallOutcome>,
+) {
+    if calls.iter().filter(|c| is_read_only(&c.tool)).count() > 1 {
+        println!("\n⚡ Running read-only tool calls concurrently");
+    }
+    let results = execute_tool_calls(&calls, staging).await;
+    for (call, (result, elapsed)) in calls.into_iter().zip(results) {
+        println!("\n🔧 {} ({}ms)", call.tool, elapsed.as_millis());
+        outcomes.push(CallOutcome::Ran(call, result, elapsed));
+    }
+}
+
+pub struct AgentTurn {
+    pub response: String,
+    pub tool_timings: Vec<(String, std::time::Duration)>,
+}
+
+/// Run one agent turn: stream a response, execute any tool calls it makes,
+/// feed the results back and repeat until the model stops calling tools.
+/// With `staging` set, side effects are staged there instead of applied.
+pub async fn stream_with_tools(
+    client: &Client,
+    model: &str,
+    prompt: &str,
+    mut staging: Option<&mut MultiFileEditor>,
+) -> Result<AgentTurn, Box<dyn std::error::Error>> {
+    let mut conversation = prompt.to_string();
+    let mut transcript = String::new();
+    let mut tool_timings = Vec::new();
+    let policy = OutputPolicy::load().await;
+
+    for round in 0..MAX_TOOL_ROUNDS {
+        // Calls run as soon as their closing tag streams in, while the
+        // model keeps generating, unless they may ask the user something
+        let mut detector = ToolCallDetector::new();
+        let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+        let generation = {
+            let detector = &mut detector;
+            let conversation = &conversation;
+            async move {
+                let result = stream_response_until(client, model, conversation, |text| {
+                    for block in detector.push(text) {
+                        let _ = tx.send(block);
+                    }
+                    if detector.fake_result_started() {
+                        StreamControl::Stop
+                    } else {
+                        StreamControl::Continue
+                    }
+                })
+                .await;
+                drop(tx);
+                result
+            }
+        };
+        let executor = async {
+            let mut outcomes = Vec::new();
+            let mut held = Vec::new();
+            while let Some(block) = rx.recv().await {
+                let mut blocks = vec![block];
+                while let Ok(more) = rx.try_recv() {
+                    blocks.push(more);
+                }
+                let mut calls = parse_blocks(&blocks, &mut outcomes);
+                if held.is_empty() {
+                    held = split_held(&mut calls);
+                    run_calls(calls, staging.as_deref_mut(), &mut outcomes).await;
+                } else {
+                    held.extend(calls);
+                }
+            }
+            (outcomes, held)
+        };
+        let (generated, (mut outcomes, held)) = tokio::join!(generation, executor);
+        let (_, stats) = generated?;
+        println!(
+            "📊 {} tokens | {:.1} tok/s | {:.1}s",
+            stats.token_count, stats.tokens_per_sec, stats.elapsed_secs
+        );
+        if detector.fake_result_started() {
+            println!("✋ Stopped the model from writing its own tool result");
+        }
+
+        let response = detector.response().to_string();
+        transcript.push_str(&response);
+
+        run_calls(held, staging.as_deref_mut(), &mut outcomes).await;
+
+        // Other call formats are only picked up once the response is complete
+        let ParsedCalls { calls, errors } = tool_parser::parse(&detector.remainder());
+        outcomes.extend(errors.into_iter().map(CallOutcome::ParseError));
+        run_calls(calls, staging.as_deref_mut(), &mut outcomes).await;
+        if outcomes.is_empty() {
+            break;
+        }
+
+        let mut results = String::new();
+        for outcome in outcomes {
+            let (call, result, elapsed) = match outcome {
+                CallOutcome::ParseError(error) => {
+                    println!("⚠️  {}", error.lines().next().unwrap_or(""));
+                    results.push_str(&format!(
+                        "<tool_result tool=\"parse_error\" status=\"error\">\n{}\n</tool_result>\n",
+                        error
+                    ));
+                    continue;
+                }
+                CallOutcome::Ran(call, result, elapsed) => (call, result, elapsed),
+            };
+            tool_timings.push((call.tool.clone(), elapsed));
+            let (status, output) = match result {
+                ToolResult::Success(out) => ("success", out),
+                ToolResult::Error(err) => {
+                    println!("❌ {}", err.lines().next().unwrap_or(""));
+                    ("error", err)
+                }
+            };
+            let shaped = policy.shape(client, &call.tool, &output).await;
+            if let Some(id) = &shaped.stored_id {
+                println!("✂️  Output shortened; full text saved as {}", id);
+            }
+            results.push_str(&format!(
+                "<tool_result tool=\"{}\" status=\"{}\">\n{}\n</tool_result>\n",
+                call.tool, status, shaped.text
+            ));
+        }
+
+        transcript.push('\n');
+        transcript.push_str(&results);
+        conversation.push_str(&format!("\n\nAssistant: {}\n\n{}", response, results));
+
+        if round + 1 == MAX_TOOL_ROUNDS {
+            println!("⚠️  Stopped after {} tool rounds", MAX_TOOL_ROUNDS);
+        }
+    }
+
+    Ok(AgentTurn {
+        response: transcript,
+        tool_timings,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -118,6 +411,56 @@ mod tests {
         assert_eq!(stats.tokens_per_sec, 10.0);
     }
 
+    #[test]
+    fn test_detector_finds_calls_split_across_chunks() {
+        let mut detector = ToolCallDetector::new();
+        assert!(detector.push("Let me look. <tool_ca").is_empty());
+        assert!(detector.push("ll>{\"tool\":\"list_directory\",").is_empty());
+        let blocks = detector.push("\"parameters\":{}}</tool_call> and then");
+        assert_eq!(
+            blocks,
+            vec!["<tool_call>{\"tool\":\"list_directory\",\"parameters\":{}}</tool_call>"]
+        );
+        assert_eq!(detector.remainder(), "Let me look.  and then");
+        assert!(!detector.fake_result_started());
+    }
+
+    #[test]
+    fn test_detector_stops_at_fake_result() {
+        let mut detector = ToolCallDetector::new();
+        detector.push("<tool_call>{\"tool\":\"read_file\",\"parameters\":{}}</tool_call>\n<tool_");
+        assert!(!detector.fake_result_started());
+        detector.push("result tool=\"read_file\">made up</tool_result>");
+        assert!(detector.fake_result_started());
+        assert!(detector.push("<tool_call>{}</tool_call>").is_empty());
+        assert_eq!(
+            detector.response(),
+            "<tool_call>{\"tool\":\"read_file\",\"parameters\":{}}</tool_call>\n"
+        );
+    }
+
+    #[test]
+    fn test_calls_that_may_prompt_wait_for_the_stream() {
+        let call = |tool: &str| ToolCall {
+            tool: tool.to_string(),
+            parameters: serde_json::json!({}),
+        };
+        let mut calls = vec![
+            call("read_file"),
+            call("git_commit"),
+            call("list_directory"),
+        ];
+        let held = split_held(&mut calls);
+        assert_eq!(calls.len(), 1);
+        assert_eq!(held[0].tool, "git_commit");
+        assert_eq!(held[1].tool, "list_directory");
+
+        let mut calls = vec![call("read_file"), call("docs.search")];
+        let held = split_held(&mut calls);
+        assert_eq!(calls.len(), 1);
+        assert_eq!(held[0].tool, "docs.search");
+    }
+
     #[test]
     fn test_stream_stats_zero_time() {
         let stats = StreamStats::new(100, 0.0);
@@ -0,0 +1,763 @@
+use regex::Regex;
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+
+const MAX_MESSAGE_LINES: usize = 12;
+/// A test run still going after this long is assumed to hang and is killed
+const TEST_TIMEOUT: Duration = Duration::from_secs(600);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TestRunner {
+    Cargo,
+    Go,
+    Jest,
+    Npm,
+    Pytest,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestFailure {
+    pub name: String,
+    pub file: Option<String>,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestReport {
+    pub runner: TestRunner,
+    pub success: bool,
+    pub passed: usize,
+    pub ignored: usize,
+    pub failures: Vec<TestFailure>,
+    /// False when the output had no test counts, e.g. an unknown `npm test` script
+    pub counted: bool,
+}
+
+impl TestRunner {
+    /// Pick the runner from the project files, the same way `/build` does
+    pub fn detect(dir: &Path) -> Option<Self> {
+        if dir.join("Cargo.toml").exists() {
+            Some(TestRunner::Cargo)
+        } else if dir.join("go.mod").exists() {
+            Some(TestRunner::Go)
+        } else if dir.join("package.json").exists() {
+            let package = std::fs::read_to_string(dir.join("package.json")).unwrap_or_default();
+            let has_config = ["js", "ts", "mjs", "cjs", "json"]
+                .iter()
+                .any(|ext| dir.join(format!("jest.config.{}", ext)).exists());
+            if has_config || package.contains("\"jest\"") {
+                Some(TestRunner::Jest)
+            } else {
+                Some(TestRunner::Npm)
+            }
+        } else if [
+            "pytest.ini",
+            "pyproject.toml",
+            "setup.py",
+            "setup.cfg",
+            "conftest.py",
+        ]
+        .iter()
+        .any(|f| dir.join(f).exists())
+        {
+            Some(TestRunner::Pytest)
+        } else {
+            None
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "cargo" | "rust" => Some(TestRunner::Cargo),
+            "go" => Some(TestRunner::Go),
+            "jest" => Some(TestRunner::Jest),
+            "npm" | "node" => Some(TestRunner::Npm),
+            "pytest" | "python" => Some(TestRunner::Pytest),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TestRunner::Cargo => "cargo",
+            TestRunner::Go => "go",
+            TestRunner::Jest => "jest",
+            TestRunner::Npm => "npm",
+            TestRunner::Pytest => "pytest",
+        }
+    }
+
+    /// Program and arguments, asking each runner for machine-readable output where it has one
+    pub fn command(&self, filter: Option<&str>) -> (&'static str, Vec<String>) {
+        let mut args: Vec<String> = Vec::new();
+        let program = match self {
+            TestRunner::Cargo => {
+                args.extend(["test".to_string(), "--color=never".to_string()]);
+                args.extend(filter.map(|f| f.to_string()));
+                "cargo"
+            }
+            TestRunner::Go => {
+                args.extend(["test".to_string(), "-json".to_string()]);
+                if let Some(f) = filter {
+                    args.extend(["-run".to_string(), f.to_string()]);
+                }
+                args.push("./...".to_string());
+                "go"
+            }
+            TestRunner::Jest => {
+                args.extend(["jest".to_string(), "--json".to_string(), "--ci".to_string()]);
+                if let Some(f) = filter {
+                    args.extend(["-t".to_string(), f.to_string()]);
+                }
+                "npx"
+            }
+            TestRunner::Npm => {
+                args.push("test".to_string());
+                if let Some(f) = filter {
+                    args.extend(["--".to_string(), f.to_string()]);
+                }
+                "npm"
+            }
+            TestRunner::Pytest => {
+                args.extend(
+                    ["-m", "pytest", "-q", "-rf", "--tb=short", "--color=no"]
+                        .iter()
+                        .map(|a| a.to_string()),
+                );
+                if let Some(f) = filter {
+                    args.extend(["-k".to_string(), f.to_string()]);
+                }
+                "python3"
+            }
+        };
+        (program, args)
+    }
+
+    pub fn parse(&self, stdout: &str, stderr: &str, success: bool) -> TestReport {
+        let mut report = match self {
+            TestRunner::Cargo => parse_cargo(stdout, stderr),
+            TestRunner::Go => parse_go(stdout),
+            TestRunner::Jest => parse_jest(stdout),
+            TestRunner::Npm => parse_npm(stdout),
+            TestRunner::Pytest => parse_pytest(stdout),
+        };
+        report.runner = *self;
+        report.success = success;
+
+        // Build errors and unparsed output still need to reach the caller
+        if !success && report.failures.is_empty() {
+            let combined = format!("{}\n{}", stdout, stderr);
+            report.failures.push(TestFailure {
+                name: "(test run)".to_string(),
+                file: None,
+                line: None,
+                message: tail_lines(&combined, MAX_MESSAGE_LINES),
+            });
+        }
+        report
+    }
+}
+
+impl TestReport {
+    fn empty(runner: TestRunner) -> Self {
+        Self {
+            runner,
+            success: true,
+            passed: 0,
+            ignored: 0,
+            failures: Vec::new(),
+            counted: true,
+        }
+    }
+
+    /// Compact summary: counts, then one entry per failure
+    pub fn display(&self) -> String {
+        let mut output = if self.counted {
+            format!(
+                "{} test: {} passed, {} failed",
+                self.runner.name(),
+                self.passed,
+                self.failures.len()
+            )
+        } else {
+            format!(
+                "{} test: {} (no test counts in the output)",
+                self.runner.name(),
+                if self.success { "passed" } else { "failed" }
+            )
+        };
+        if self.ignored > 0 {
+            output.push_str(&format!(", {} ignored", self.ignored));
+        }
+        output.push('\n');
+
+        for failure in &self.failures {
+            output.push_str(&format!("\nFAILED {}", failure.name));
+            match (&failure.file, failure.line) {
+                (Some(file), Some(line)) => output.push_str(&format!(" ({}:{})", file, line)),
+                (Some(file), None) => output.push_str(&format!(" ({})", file)),
+                _ => {}
+            }
+            output.push('\n');
+            for line in failure.message.lines() {
+                output.push_str(&format!("  {}\n", line));
+            }
+        }
+        output
+    }
+}
+
+/// `/test` arguments: an optional runner (or `auto`) as with `/build`, then
+/// the filter. Returns `None` for the runner when it should be detected.
+pub fn parse_command_args(args: &[&str]) -> (Option<TestRunner>, Option<String>) {
+    let (runner, filter) = match args.first() {
+        Some(&"auto") => (None, &args[1..]),
+        Some(name) => match TestRunner::from_name(name) {
+            Some(runner) => (Some(runner), &args[1..]),
+            None => (None, args),
+        },
+        None => (None, args),
+    };
+    let filter = if filter.is_empty() {
+        None
+    } else {
+        Some(filter.join(" "))
+    };
+    (runner, filter)
+}
+
+/// Detect the runner in the current directory (unless given) and run it
+pub async fn run_tests(
+    runner: Option<TestRunner>,
+    filter: Option<&str>,
+) -> Result<TestReport, String> {
+    let runner = match runner {
+        Some(r) => r,
+        None => TestRunner::detect(Path::new(".")).ok_or_else(|| {
+            "Could not detect a test runner (cargo, go, jest, npm, pytest)".to_string()
+        })?,
+    };
+    let (program, args) = runner.command(filter);
+
+    // Dropping the output future on timeout kills the runner
+    let child = tokio::process::Command::new(program)
+        .args(&args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    let output = tokio::time::timeout(TEST_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| {
+            format!(
+                "{} test timed out after {}s and was stopped",
+                runner.name(),
+                TEST_TIMEOUT.as_secs()
+            )
+        })?
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+
+    Ok(runner.parse(
+        &String::from_utf8_lossy(&output.stdout),
+        &String::from_utf8_lossy(&output.stderr),
+        output.status.success(),
+    ))
+}
+
+fn tail_lines(text: &str, count: usize) -> String {
+    let lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+    lines[lines.len().saturating_sub(count)..].join("\n")
+}
+
+fn head_lines(text: &str, count: usize) -> String {
+    text.lines()
+        .filter(|l| !l.trim().is_empty())
+        .take(count)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn parse_cargo(stdout: &str, stderr: &str) -> TestReport {
+    let mut report = TestReport::empty(TestRunner::Cargo);
+    let summary =
+        Regex::new(r"test result: \w+\. (\d+) passed; (\d+) failed; (\d+) ignored").unwrap();
+    for caps in summary.captures_iter(stdout) {
+        report.passed += caps[1].parse::<usize>().unwrap_or(0);
+        report.ignored += caps[3].parse::<usize>().unwrap_or(0);
+    }
+
+    // Newer: "panicked at src/lib.rs:10:5:\nmessage"; older: "panicked at 'message', src/lib.rs:10:5"
+    let location = Regex::new(r"panicked at (?:'(.*)', )?([^\s:]+):(\d+):\d+:?").unwrap();
+    let failed = Regex::new(r"^test (\S+) \.\.\. FAILED$").unwrap();
+    let mut sections: HashMap<&str, String> = HashMap::new();
+    let mut current: Option<&str> = None;
+
+    for line in stdout.lines() {
+        if let Some(name) = line
+            .strip_prefix("---- ")
+            .and_then(|l| l.strip_suffix(" stdout ----"))
+        {
+            current = Some(name);
+            continue;
+        }
+        if line == "failures:" || line.starts_with("test result:") {
+            current = None;
+        }
+        if let Some(name) = current {
+            let section = sections.entry(name).or_default();
+            section.push_str(line);
+            section.push('\n');
+        }
+    }
+
+    for line in stdout.lines() {
+        let Some(caps) = failed.captures(line) else {
+            continue;
+        };
+        let name = caps[1].to_string();
+        let section = sections.get(name.as_str()).cloned().unwrap_or_default();
+        let mut failure = TestFailure {
+            name,
+            file: None,
+            line: None,
+            message: String::new(),
+        };
+
+        let body: Vec<&str> = section
+            .lines()
+            .filter(|l| !l.starts_with("note: run with `RUST_BACKTRACE"))
+            .collect();
+        if let Some((index, caps)) = body
+            .iter()
+            .enumerate()
+            .find_map(|(i, l)| location.captures(l).map(|c| (i, c)))
+        {
+            failure.file = Some(caps[2].to_string());
+            failure.line = caps[3].parse().ok();
+            failure.message = match caps.get(1) {
+                Some(message) => message.as_str().to_string(),
+                None => head_lines(&body[index + 1..].join("\n"), MAX_MESSAGE_LINES),
+            };
+        } else {
+            failure.message = head_lines(&body.join("\n"), MAX_MESSAGE_LINES);
+        }
+        report.failures.push(failure);
+    }
+
+    // Compile errors never get as far as running tests
+    if report.failures.is_empty() && stderr.contains("error[") {
+        let errors: Vec<&str> = stderr
+            .lines()
+            .filter(|l| l.starts_with("error") || l.trim_start().starts_with("-->"))
+            .collect();
+        report.failures.push(TestFailure {
+            name: "(build)".to_string(),
+            file: None,
+            line: None,
+            message: head_lines(&errors.join("\n"), MAX_MESSAGE_LINES),
+        });
+    }
+    report
+}
+
+fn parse_go(stdout: &str) -> TestReport {
+    let mut report = TestReport::empty(TestRunner::Go);
+    let location = Regex::new(r"^\s+([\w./-]+\.go):(\d+): ?(.*)$").unwrap();
+    let mut outputs: HashMap<String, String> = HashMap::new();
+
+    for line in stdout.lines() {
+        let Ok(event) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue;
+        };
+        let Some(test) = event.get("Test").and_then(|t| t.as_str()) else {
+            continue;
+        };
+        let package = event.get("Package").and_then(|p| p.as_str()).unwrap_or("");
+        let key = format!("{}.{}", package, test);
+
+        match event.get("Action").and_then(|a| a.as_str()) {
+            Some("output") => {
+                let text = event.get("Output").and_then(|o| o.as_str()).unwrap_or("");
+                outputs.entry(key).or_default().push_str(text);
+            }
+            Some("pass") => report.passed += 1,
+            Some("skip") => report.ignored += 1,
+            Some("fail") => {
+                let output = outputs.remove(&key).unwrap_or_default();
+                let mut failure = TestFailure {
+                    name: test.to_string(),
+                    file: None,
+                    line: None,
+                    message: String::new(),
+                };
+                let mut messages = Vec::new();
+                for line in output.lines() {
+                    if let Some(caps) = location.captures(line) {
+                        if failure.file.is_none() {
+                            failure.file = Some(caps[1].to_string());
+                            failure.line = caps[2].parse().ok();
+                        }
+                        messages.push(caps[3].to_string());
+                    } else if !messages.is_empty() && line.starts_with("        ") {
+                        messages.push(line.trim().to_string());
+                    }
+                }
+                failure.message = head_lines(&messages.join("\n"), MAX_MESSAGE_LINES);
+                report.failures.push(failure);
+            }
+            _ => {}
+        }
+    }
+    report
+}
+
+/// `npm test` runs whatever the package's script says, so look for the
+/// summary lines of the common runners: Jest/Vitest, Mocha and TAP
+fn parse_npm(stdout: &str) -> TestReport {
+    let mut report = TestReport::empty(TestRunner::Npm);
+    let jest = Regex::new(r"(?m)^\s*Tests:?\s+(.*)$").unwrap();
+    let count = Regex::new(r"(\d+) (passed|skipped|todo)").unwrap();
+    let mocha = Regex::new(r"(?m)^\s*(\d+) (passing|pending)\b").unwrap();
+    let tap = Regex::new(r"(?m)^# (pass|skip|todo)\s+(\d+)$").unwrap();
+
+    if let Some(summary) = jest.captures_iter(stdout).last() {
+        for caps in count.captures_iter(&summary[1]) {
+            let n: usize = caps[1].parse().unwrap_or(0);
+            match &caps[2] {
+                "passed" => report.passed += n,
+                _ => report.ignored += n,
+            }
+        }
+    } else if mocha.is_match(stdout) {
+        for caps in mocha.captures_iter(stdout) {
+            let n: usize = caps[1].parse().unwrap_or(0);
+            match &caps[2] {
+                "passing" => report.passed += n,
+                _ => report.ignored += n,
+            }
+        }
+    } else if tap.is_match(stdout) {
+        for caps in tap.captures_iter(stdout) {
+            let n: usize = caps[2].parse().unwrap_or(0);
+            match &caps[1] {
+                "pass" => report.passed += n,
+                _ => report.ignored += n,
+            }
+        }
+    } else {
+        report.counted = false;
+    }
+    report
+}
+
+fn parse_jest(stdout: &str) -> TestReport {
+    let mut report = TestReport::empty(TestRunner::Jest);
+    // Jest may print console output before and after the JSON document
+    let Some(json) = stdout.match_indices("{\"").find_map(|(start, _)| {
+        serde_json::Deserializer::from_str(&stdout[start..])
+            .into_iter::<serde_json::Value>()
+            .next()
+            .and_then(|value| value.ok())
+            .filter(|value| value.get("testResults").is_some())
+    }) else {
+        return report;
+    };
+
+    report.passed = json["numPassedTests"].as_u64().unwrap_or(0) as usize;
+    report.ignored = (json["numPendingTests"].as_u64().unwrap_or(0)
+        + json["numTodoTests"].as_u64().unwrap_or(0)) as usize;
+    let cwd = std::env::current_dir()
+        .map(|d| format!("{}/", d.display()))
+        .unwrap_or_default();
+    let frame = Regex::new(r"\(?([^\s()]+):(\d+):\d+\)?").unwrap();
+
+    for suite in json["testResults"].as_array().into_iter().flatten() {
+        let file = suite["name"].as_str().unwrap_or("");
+        let relative = file.strip_prefix(&cwd).unwrap_or(file).to_string();
+        for test in suite["assertionResults"].as_array().into_iter().flatten() {
+            if test["status"].as_str() != Some("failed") {
+                continue;
+            }
+            let message = test["failureMessages"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|m| m.as_str())
+                .collect::<Vec<_>>()
+                .join("\n");
+            let line = frame
+                .captures_iter(&message)
+                .find(|c| c[1].ends_with(&relative))
+                .and_then(|c| c[2].parse().ok());
+            let summary: Vec<&str> = message
+                .lines()
+                .take_while(|l| !l.trim_start().starts_with("at "))
+                .collect();
+            report.failures.push(TestFailure {
+                name: test["fullName"].as_str().unwrap_or("").to_string(),
+                file: Some(relative.clone()),
+                line,
+                message: head_lines(&summary.join("\n"), MAX_MESSAGE_LINES),
+            });
+        }
+
+        // A suite that fails to load has no assertion results of its own
+        if suite["status"].as_str() == Some("failed")
+            && suite["assertionResults"]
+                .as_array()
+                .is_none_or(|a| a.is_empty())
+        {
+            report.failures.push(TestFailure {
+                name: "(suite)".to_string(),
+                file: Some(relative),
+                line: None,
+                message: head_lines(suite["message"].as_str().unwrap_or(""), MAX_MESSAGE_LINES),
+            });
+        }
+    }
+    report
+}
+
+fn parse_pytest(stdout: &str) -> TestReport {
+    let mut report = TestReport::empty(TestRunner::Pytest);
+    let count = Regex::new(r"(\d+) (passed|skipped|xfailed)").unwrap();
+    if let Some(summary) = stdout.lines().rev().find(|l| count.is_match(l)) {
+        for caps in count.captures_iter(summary) {
+            let n: usize = caps[1].parse().unwrap_or(0);
+            match &caps[2] {
+                "passed" => report.passed += n,
+                _ => report.ignored += n,
+            }
+        }
+    }
+
+    // --tb=short prints each failure under a "____ test_name ____" header,
+    // with "path.py:12: in test_name" for each frame
+    let header = Regex::new(r"^_{3,} (.+?) _{3,}$").unwrap();
+    let mut sections: HashMap<&str, String> = HashMap::new();
+    let mut current = None;
+    for line in stdout.lines() {
+        if let Some(caps) = header.captures(line) {
+            current = Some(caps.get(1).unwrap().as_str());
+        } else if line.starts_with("====") {
+            current = None;
+        } else if let Some(title) = current {
+            let section = sections.entry(title).or_default();
+            section.push_str(line);
+            section.push('\n');
+        }
+    }
+
+    let mut frames: HashMap<String, Regex> = HashMap::new();
+    for line in stdout.lines() {
+        let Some(rest) = line.strip_prefix("FAILED ") else {
+            continue;
+        };
+        let (id, message) = rest.split_once(" - ").unwrap_or((rest, ""));
+        let mut parts = id.split("::");
+        let file = parts.next().map(|f| f.to_string());
+        // The section title is the test's class and name joined with dots
+        let title = parts.collect::<Vec<_>>().join(".");
+        let traceback = match sections.get(title.as_str()) {
+            Some(section) => section.as_str(),
+            None if sections.is_empty() => stdout,
+            None => "",
+        };
+        let line_number = file.as_ref().and_then(|f| {
+            let frame = frames.entry(f.clone()).or_insert_with(|| {
+                Regex::new(&format!(r"(?m)^{}:(\d+): ", regex::escape(f))).unwrap()
+            });
+            frame
+                .captures_iter(traceback)
+                .last()
+                .and_then(|c| c[1].parse().ok())
+        });
+        report.failures.push(TestFailure {
+            name: id.to_string(),
+            file,
+            line: line_number,
+            message: message.to_string(),
+        });
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cargo_output() {
+        let stdout = "\
+running 3 tests
+test tests::ok_one ... ok
+test tests::broken ... FAILED
+test tests::skipped ... ignored
+
+failures:
+
+---- tests::broken stdout ----
+thread 'tests::broken' panicked at src/lib.rs:42:9:
+assertion `left == right` failed
+  left: 1
+ right: 2
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+
+
+failures:
+    tests::broken
+
+test result: FAILED. 1 passed; 1 failed; 1 ignored; 0 measured; 0 filtered out; finished in 0.00s
+";
+        let report = TestRunner::Cargo.parse(stdout, "", false);
+        assert_eq!(report.passed, 1);
+        assert_eq!(report.ignored, 1);
+        assert_eq!(report.failures.len(), 1);
+        let failure = &report.failures[0];
+        assert_eq!(failure.name, "tests::broken");
+        assert_eq!(failure.file.as_deref(), Some("src/lib.rs"));
+        assert_eq!(failure.line, Some(42));
+        assert!(failure
+            .message
+            .starts_with("assertion `left == right` failed"));
+    }
+
+    #[test]
+    fn test_parse_cargo_build_error() {
+        let stderr = "error[E0425]: cannot find value `x` in this scope\n --> src/lib.rs:3:5\n";
+        let report = TestRunner::Cargo.parse("", stderr, false);
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].name, "(build)");
+        assert!(report.failures[0].message.contains("E0425"));
+    }
+
+    #[test]
+    fn test_parse_go_json() {
+        let stdout = r#"{"Action":"run","Package":"example/m","Test":"TestAdd"}
+{"Action":"pass","Package":"example/m","Test":"TestAdd","Elapsed":0}
+{"Action":"run","Package":"example/m","Test":"TestSub"}
+{"Action":"output","Package":"example/m","Test":"TestSub","Output":"    math_test.go:14: got 3, want 1\n"}
+{"Action":"fail","Package":"example/m","Test":"TestSub","Elapsed":0}
+{"Action":"fail","Package":"example/m","Elapsed":0.01}"#;
+        let report = TestRunner::Go.parse(stdout, "", false);
+        assert_eq!(report.passed, 1);
+        assert_eq!(
+            report.failures,
+            vec![TestFailure {
+                name: "TestSub".to_string(),
+                file: Some("math_test.go".to_string()),
+                line: Some(14),
+                message: "got 3, want 1".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_jest_json() {
+        let stdout = r#"{"numPassedTests":4,"numPendingTests":1,"numTodoTests":0,"testResults":[{"name":"src/sum.test.js","status":"failed","message":"","assertionResults":[{"fullName":"sum adds","status":"failed","failureMessages":["Error: expect(received).toBe(expected)\n\nExpected: 3\nReceived: 4\n    at Object.<anonymous> (src/sum.test.js:7:20)"]}]}]}"#;
+        let report = TestRunner::Jest.parse(stdout, "", false);
+        assert_eq!(report.passed, 4);
+        assert_eq!(report.ignored, 1);
+        assert_eq!(report.failures[0].name, "sum adds");
+        assert_eq!(report.failures[0].file.as_deref(), Some("src/sum.test.js"));
+        assert_eq!(report.failures[0].line, Some(7));
+        assert!(report.failures[0].message.contains("Received: 4"));
+    }
+
+    #[test]
+    fn test_parse_jest_json_between_console_output() {
+        let stdout = "console.log {\"debug\":true}\n{\"numPassedTests\":2,\"testResults\":[]}\nDone in 1.2s.\n";
+        let report = TestRunner::Jest.parse(stdout, "", true);
+        assert_eq!(report.passed, 2);
+        assert!(report.failures.is_empty());
+    }
+
+    #[test]
+    fn test_parse_npm_summaries() {
+        let jest = "Tests:       1 failed, 2 skipped, 7 passed, 10 total\n";
+        let report = TestRunner::Npm.parse(jest, "", false);
+        assert_eq!((report.passed, report.ignored), (7, 2));
+
+        let mocha = "  5 passing (20ms)\n  1 pending\n";
+        let report = TestRunner::Npm.parse(mocha, "", true);
+        assert_eq!((report.passed, report.ignored), (5, 1));
+        assert_eq!(
+            report.display(),
+            "npm test: 5 passed, 0 failed, 1 ignored\n"
+        );
+
+        let report = TestRunner::Npm.parse("all good\n", "", true);
+        assert!(!report.counted);
+        assert_eq!(
+            report.display(),
+            "npm test: passed (no test counts in the output)\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_command_args() {
+        assert_eq!(parse_command_args(&[]), (None, None));
+        assert_eq!(
+            parse_command_args(&["pytest", "test_div"]),
+            (Some(TestRunner::Pytest), Some("test_div".to_string()))
+        );
+        assert_eq!(
+            parse_command_args(&["auto", "go"]),
+            (None, Some("go".to_string()))
+        );
+        assert_eq!(
+            parse_command_args(&["parser", "tests"]),
+            (None, Some("parser tests".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_pytest_summary() {
+        let stdout = "\
+.F.
+tests/test_math.py:9: in test_div
+    assert div(4, 2) == 3
+E   assert 2.0 == 3
+=========================== short test summary info ============================
+FAILED tests/test_math.py::test_div - assert 2.0 == 3
+1 failed, 2 passed in 0.03s
+";
+        let report = TestRunner::Pytest.parse(stdout, "", false);
+        assert_eq!(report.passed, 2);
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].name, "tests/test_math.py::test_div");
+        assert_eq!(report.failures[0].line, Some(9));
+        assert_eq!(report.failures[0].message, "assert 2.0 == 3");
+        assert!(report
+            .display()
+            .contains("FAILED tests/test_math.py::test_div (tests/test_math.py:9)"));
+    }
+
+    #[test]
+    fn test_parse_pytest_failures_in_one_file() {
+        let stdout = "\
+.FF
+=================================== FAILURES ===================================
+___________________________________ test_div ___________________________________
+tests/test_math.py:9: in test_div
+    assert div(4, 2) == 3
+E   assert 2.0 == 3
+____________________________ TestMul.test_mul[2-3] _____________________________
+tests/test_math.py:21: in test_mul
+    assert mul(a, b) == 5
+E   assert 6 == 5
+=========================== short test summary info ============================
+FAILED tests/test_math.py::test_div - assert 2.0 == 3
+FAILED tests/test_math.py::TestMul::test_mul[2-3] - assert 6 == 5
+2 failed, 1 passed in 0.03s
+";
+        let report = TestRunner::Pytest.parse(stdout, "", false);
+        assert_eq!(report.failures.len(), 2);
+        assert_eq!(report.failures[0].line, Some(9));
+        assert_eq!(report.failures[1].line, Some(21));
+    }
+}
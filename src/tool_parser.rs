@@ -0,0 +1,503 @@
+use crate::tools::ToolCall;
+use serde_json::{Map, Value};
+
+const OPEN_TAG: &str = "<tool_call>";
+const CLOSE_TAG: &str = "</tool_call>";
+const FUNCTION_TAG: &str = "<function=";
+const FUNCTION_CLOSE: &str = "</function>";
+const PARAMETER_TAG: &str = "<parameter=";
+const PARAMETER_CLOSE: &str = "</parameter>";
+const FENCE: &str = "```";
+const SNIPPET_CHARS: usize = 80;
+
+/// Tool calls found in a model response, plus a message for every block that
+/// looked like a call but could not be understood
+#[derive(Debug, Default)]
+pub struct ParsedCalls {
+    pub calls: Vec<ToolCall>,
+    pub errors: Vec<String>,
+}
+
+/// Extract tool calls from model output. Accepts `<tool_call>` tags,
+/// `<function=name>` blocks and fenced JSON, in either the
+/// `{"tool", "parameters"}` or `{"name", "arguments"}` shape.
+pub fn parse(text: &str) -> ParsedCalls {
+    let mut parsed = ParsedCalls::default();
+    let mut found: Vec<(usize, Vec<ToolCall>)> = Vec::new();
+    let mut consumed: Vec<(usize, usize)> = Vec::new();
+
+    // <tool_call>...</tool_call>; a missing close tag runs to the end of the text
+    let mut start = 0;
+    while let Some(offset) = text[start..].find(OPEN_TAG) {
+        let begin = start + offset;
+        let body_start = begin + OPEN_TAG.len();
+        let (body_end, end) = match text[body_start..].find(CLOSE_TAG) {
+            Some(len) => (body_start + len, body_start + len + CLOSE_TAG.len()),
+            None => (text.len(), text.len()),
+        };
+        let body = &text[body_start..body_end];
+        // A JSON call may carry "<function=" in a string, e.g. file content
+        let json = strip_fences(body);
+        let result = if json.starts_with(['{', '[']) || !body.contains(FUNCTION_TAG) {
+            parse_json_calls(json)
+        } else {
+            parse_function_blocks(body)
+        };
+        match result {
+            Ok(calls) => found.push((begin, calls)),
+            Err(e) => parsed.errors.push(parse_error(&e, body)),
+        }
+        consumed.push((begin, end));
+        start = end;
+    }
+
+    // <function=name>...</function> outside of tool_call tags
+    let mut start = 0;
+    while let Some(offset) = text[start..].find(FUNCTION_TAG) {
+        let begin = start + offset;
+        let end = text[begin..]
+            .find(FUNCTION_CLOSE)
+            .map(|len| begin + len + FUNCTION_CLOSE.len())
+            .unwrap_or(text.len());
+        if !overlaps(&consumed, begin) {
+            match parse_function_blocks(&text[begin..end]) {
+                Ok(calls) => found.push((begin, calls)),
+                Err(e) => parsed.errors.push(parse_error(&e, &text[begin..end])),
+            }
+            consumed.push((begin, end));
+        }
+        start = end;
+    }
+
+    // Fenced JSON. Plain ```json blocks are often just examples, so they only
+    // count when they hold a call-shaped object; ```tool_call always counts.
+    let mut start = 0;
+    while let Some(offset) = text[start..].find(FENCE) {
+        let begin = start + offset;
+        let info_end = text[begin..]
+            .find('\n')
+            .map(|len| begin + len)
+            .unwrap_or(text.len());
+        let Some(close) = text[info_end..].find(FENCE) else {
+            break;
+        };
+        let end = info_end + close + FENCE.len();
+        if !overlaps(&consumed, begin) {
+            let info = text[begin + FENCE.len()..info_end].trim().to_lowercase();
+            let body = &text[info_end..info_end + close];
+            let explicit = matches!(info.as_str(), "tool_call" | "tool" | "tool_code");
+            if explicit || matches!(info.as_str(), "" | "json" | "json5") {
+                match parse_lenient(body) {
+                    Ok(value) if explicit || is_call_shaped(&value) => {
+                        match calls_from_value(value) {
+                            Ok(calls) => found.push((begin, calls)),
+                            Err(e) => parsed.errors.push(parse_error(&e, body)),
+                        }
+                    }
+                    Err(e) if explicit => parsed.errors.push(parse_error(&e, body)),
+                    _ => {}
+                }
+            }
+        }
+        start = end;
+    }
+
+    found.sort_by_key(|(position, _)| *position);
+    parsed.calls = found.into_iter().flat_map(|(_, calls)| calls).collect();
+    parsed
+}
+
+fn overlaps(spans: &[(usize, usize)], position: usize) -> bool {
+    spans
+        .iter()
+        .any(|&(start, end)| position >= start && position < end)
+}
+
+fn parse_error(reason: &str, snippet: &str) -> String {
+    let snippet = snippet.trim();
+    let mut shown: String = snippet.chars().take(SNIPPET_CHARS).collect();
+    if snippet.chars().count() > SNIPPET_CHARS {
+        shown.push('…');
+    }
+    format!(
+        "Could not parse tool call ({}): {}\nUse <tool_call>{{\"tool\": \"name\", \"parameters\": {{...}}}}</tool_call> with valid JSON.",
+        reason, shown
+    )
+}
+
+/// Drop a surrounding markdown fence, if any
+fn strip_fences(body: &str) -> &str {
+    let trimmed = body.trim();
+    let Some(rest) = trimmed.strip_prefix(FENCE) else {
+        return trimmed;
+    };
+    let rest = match rest.find('\n') {
+        Some(newline) => &rest[newline + 1..],
+        None => rest,
+    };
+    rest.trim_end().strip_suffix(FENCE).unwrap_or(rest).trim()
+}
+
+/// Parse a JSON call, or an array of them, repairing common mistakes first
+fn parse_json_calls(body: &str) -> Result<Vec<ToolCall>, String> {
+    calls_from_value(parse_lenient(body)?)
+}
+
+fn calls_from_value(value: Value) -> Result<Vec<ToolCall>, String> {
+    match value {
+        Value::Array(items) if !items.is_empty() => items.iter().map(normalize_call).collect(),
+        other => normalize_call(&other).map(|call| vec![call]),
+    }
+}
+
+fn parse_lenient(body: &str) -> Result<Value, String> {
+    let trimmed = body.trim();
+    if let Ok(value) = serde_json::from_str(trimmed) {
+        return Ok(value);
+    }
+
+    // Ignore prose around the object: "Sure! {...} Let me know"
+    let json = match (trimmed.find(['{', '[']), trimmed.rfind(['}', ']'])) {
+        (Some(start), Some(end)) if start < end => &trimmed[start..=end],
+        (Some(start), _) => &trimmed[start..],
+        _ => return Err("no JSON object found".to_string()),
+    };
+    serde_json::from_str(&repair_json(json)).map_err(|e| format!("invalid JSON: {}", e))
+}
+
+/// Whether a fenced JSON value is meant as a call rather than, say, a package.json
+fn is_call_shaped(value: &Value) -> bool {
+    match value {
+        Value::Array(items) => !items.is_empty() && items.iter().all(is_call_shaped),
+        Value::Object(object) => {
+            object.get("function").is_some_and(|f| f.is_object())
+                || (object.contains_key("tool") && object.contains_key("parameters"))
+                || (object.contains_key("name") && object.contains_key("arguments"))
+        }
+        _ => false,
+    }
+}
+
+/// Map the call shapes different models produce onto `ToolCall`
+fn normalize_call(value: &Value) -> Result<ToolCall, String> {
+    let object = value
+        .as_object()
+        .ok_or_else(|| "expected a JSON object".to_string())?;
+    if let Some(function @ Value::Object(_)) = object.get("function") {
+        return normalize_call(function);
+    }
+
+    let tool = ["tool", "name", "tool_name"]
+        .iter()
+        .find_map(|key| object.get(*key).and_then(|v| v.as_str()))
+        .ok_or_else(|| "missing \"tool\" name".to_string())?;
+
+    let parameters = ["parameters", "arguments", "args", "input", "params"]
+        .iter()
+        .find_map(|key| object.get(*key));
+    let parameters = match parameters {
+        None | Some(Value::Null) => Value::Object(Map::new()),
+        // OpenAI-style arguments arrive as a JSON-encoded string
+        Some(Value::String(s)) if s.trim().is_empty() => Value::Object(Map::new()),
+        Some(Value::String(s)) => parse_lenient(s)?,
+        Some(other) => other.clone(),
+    };
+    if !parameters.is_object() {
+        return Err(format!("parameters of {} must be an object", tool));
+    }
+
+    Ok(ToolCall {
+        tool: tool.to_string(),
+        parameters,
+    })
+}
+
+/// Parse `<function=name>` blocks, whose body is either a JSON object or a
+/// list of `<parameter=key>value</parameter>` entries
+fn parse_function_blocks(text: &str) -> Result<Vec<ToolCall>, String> {
+    let mut calls = Vec::new();
+    let mut start = 0;
+    while let Some(offset) = text[start..].find(FUNCTION_TAG) {
+        let name_start = start + offset + FUNCTION_TAG.len();
+        let name_end = text[name_start..]
+            .find('>')
+            .map(|len| name_start + len)
+            .ok_or_else(|| "unterminated <function=...> tag".to_string())?;
+        let tool = text[name_start..name_end].trim().trim_matches(['"', '\'']);
+        if tool.is_empty() {
+            return Err("missing function name".to_string());
+        }
+
+        let body_start = name_end + 1;
+        let body_end = text[body_start..]
+            .find(FUNCTION_CLOSE)
+            .map(|len| body_start + len)
+            .unwrap_or(text.len());
+        let body = &text[body_start..body_end];
+
+        let parameters = if body.contains(PARAMETER_TAG) {
+            parse_parameter_tags(body)?
+        } else if body.trim().is_empty() {
+            Value::Object(Map::new())
+        } else {
+            parse_lenient(body)?
+        };
+        if !parameters.is_object() {
+            return Err(format!("parameters of {} must be an object", tool));
+        }
+
+        calls.push(ToolCall {
+            tool: tool.to_string(),
+            parameters,
+        });
+        start = body_end;
+    }
+    Ok(calls)
+}
+
+fn parse_parameter_tags(body: &str) -> Result<Value, String> {
+    let mut parameters = Map::new();
+    let mut start = 0;
+    while let Some(offset) = body[start..].find(PARAMETER_TAG) {
+        let key_start = start + offset + PARAMETER_TAG.len();
+        let key_end = body[key_start..]
+            .find('>')
+            .map(|len| key_start + len)
+            .ok_or_else(|| "unterminated <parameter=...> tag".to_string())?;
+        let key = body[key_start..key_end].trim().trim_matches(['"', '\'']);
+
+        let value_start = key_end + 1;
+        let value_end = body[value_start..]
+            .find(PARAMETER_CLOSE)
+            .or_else(|| body[value_start..].find(PARAMETER_TAG))
+            .map(|len| value_start + len)
+            .unwrap_or(body.len());
+        let raw = body[value_start..value_end]
+            .strip_prefix('\n')
+            .unwrap_or(&body[value_start..value_end]);
+        let raw = raw.strip_suffix('\n').unwrap_or(raw);
+
+        // Numbers, booleans and objects keep their type; everything else is text
+        let value = match serde_json::from_str::<Value>(raw.trim()) {
+            Ok(v) if !v.is_string() => v,
+            _ => Value::String(raw.to_string()),
+        };
+        parameters.insert(key.to_string(), value);
+        start = value_end;
+    }
+    Ok(Value::Object(parameters))
+}
+
+/// Best-effort fix-up of almost-JSON: single-quoted strings, unquoted keys,
+/// trailing commas, raw newlines in strings, Python literals and missing
+/// closing brackets
+pub fn repair_json(input: &str) -> String {
+    let chars: Vec<char> = input.chars().collect();
+    let mut output = String::with_capacity(input.len());
+    let mut closers: Vec<char> = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '"' | '\'' => {
+                i = copy_string(&chars, i, &mut output);
+                continue;
+            }
+            ',' => {
+                let next = chars[i + 1..].iter().find(|c| !c.is_whitespace());
+                if !matches!(next, Some('}') | Some(']') | None) {
+                    output.push(c);
+                }
+            }
+            '{' => {
+                closers.push('}');
+                output.push(c);
+            }
+            '[' => {
+                closers.push(']');
+                output.push(c);
+            }
+            '}' | ']' => {
+                closers.pop();
+                output.push(c);
+            }
+            c if c.is_ascii_digit() => {
+                // Copy numbers whole so an exponent (1e5, 2.5E-3) isn't read as a word
+                let start = i;
+                while i < chars.len() {
+                    match chars[i] {
+                        '0'..='9' | '.' => i += 1,
+                        'e' | 'E' => {
+                            i += 1;
+                            if matches!(chars.get(i), Some('+') | Some('-')) {
+                                i += 1;
+                            }
+                        }
+                        _ => break,
+                    }
+                }
+                output.extend(&chars[start..i]);
+                continue;
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '-')
+                {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                let next = chars[i..].iter().find(|c| !c.is_whitespace());
+                if next == Some(&':') {
+                    output.push_str(&format!("\"{}\"", word));
+                } else {
+                    match word.as_str() {
+                        "true" | "True" => output.push_str("true"),
+                        "false" | "False" => output.push_str("false"),
+                        "null" | "None" => output.push_str("null"),
+                        // Bare words such as {tool: read_file}
+                        _ => output.push_str(&format!("\"{}\"", word)),
+                    }
+                }
+                continue;
+            }
+            _ => output.push(c),
+        }
+        i += 1;
+    }
+
+    while let Some(closer) = closers.pop() {
+        output.push(closer);
+    }
+    output
+}
+
+/// Copy the string starting at `chars[start]` as a valid double-quoted JSON
+/// string and return the index just past it
+fn copy_string(chars: &[char], start: usize, output: &mut String) -> usize {
+    let quote = chars[start];
+    let mut i = start + 1;
+    output.push('"');
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\\' if i + 1 < chars.len() => {
+                let escaped = chars[i + 1];
+                if escaped == '\'' {
+                    output.push('\'');
+                } else {
+                    output.push('\\');
+                    output.push(escaped);
+                }
+                i += 2;
+                continue;
+            }
+            c if c == quote => {
+                output.push('"');
+                return i + 1;
+            }
+            '"' => output.push_str("\\\""),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            _ => output.push(c),
+        }
+        i += 1;
+    }
+
+    // Unterminated string: close it so the rest of the repair can proceed
+    output.push('"');
+    i
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strict_tool_call() {
+        let parsed = parse(
+            r#"Reading. <tool_call>{"tool":"read_file","parameters":{"path":"a.rs"}}</tool_call>"#,
+        );
+        assert_eq!(parsed.calls.len(), 1);
+        assert_eq!(parsed.calls[0].tool, "read_file");
+        assert_eq!(parsed.calls[0].parameters["path"], "a.rs");
+        assert!(parsed.errors.is_empty());
+    }
+
+    #[test]
+    fn test_repairs_sloppy_json() {
+        let text = "<tool_call>{tool: 'execute_bash', parameters: {'command': 'echo \"hi\"',},}</tool_call>";
+        let parsed = parse(text);
+        assert_eq!(parsed.calls.len(), 1);
+        assert_eq!(parsed.calls[0].tool, "execute_bash");
+        assert_eq!(parsed.calls[0].parameters["command"], "echo \"hi\"");
+    }
+
+    #[test]
+    fn test_name_arguments_shape_in_fence() {
+        let text = "```json\n{\"name\": \"grep\", \"arguments\": \"{\\\"pattern\\\": \\\"fn main\\\"}\"}\n```";
+        let parsed = parse(text);
+        assert_eq!(parsed.calls.len(), 1);
+        assert_eq!(parsed.calls[0].tool, "grep");
+        assert_eq!(parsed.calls[0].parameters["pattern"], "fn main");
+    }
+
+    #[test]
+    fn test_fenced_example_without_call_is_ignored() {
+        let parsed = parse(
+            "```json\n{\"name\": \"my-app\", \"version\": \"1.0.0\"}\n```\n```rust\nfn main() {}\n```",
+        );
+        assert!(parsed.calls.is_empty());
+        assert!(parsed.errors.is_empty());
+    }
+
+    #[test]
+    fn test_function_style() {
+        let text = "<tool_call>\n<function=write_file>\n<parameter=path>\nout.txt\n</parameter>\n<parameter=content>\nline one\nline two\n</parameter>\n</function>\n</tool_call>\n<function=list_directory>{\"path\": \"src\"}</function>";
+        let parsed = parse(text);
+        assert_eq!(parsed.calls.len(), 2);
+        assert_eq!(parsed.calls[0].tool, "write_file");
+        assert_eq!(parsed.calls[0].parameters["path"], "out.txt");
+        assert_eq!(parsed.calls[0].parameters["content"], "line one\nline two");
+        assert_eq!(parsed.calls[1].tool, "list_directory");
+        assert_eq!(parsed.calls[1].parameters["path"], "src");
+    }
+
+    #[test]
+    fn test_reports_unparseable_call() {
+        let parsed = parse("<tool_call>read the file please</tool_call>");
+        assert!(parsed.calls.is_empty());
+        assert_eq!(parsed.errors.len(), 1);
+        assert!(parsed.errors[0].contains("read the file please"));
+    }
+
+    #[test]
+    fn test_repair_json() {
+        assert_eq!(
+            repair_json("{'a': True, 'b': [1, 2,],}"),
+            r#"{"a": true, "b": [1, 2]}"#
+        );
+        assert_eq!(repair_json(r#"{"a": {"b": 1"#), r#"{"a": {"b": 1}}"#);
+        assert_eq!(repair_json("{tool:read_file}"), r#"{"tool":"read_file"}"#);
+        assert_eq!(
+            repair_json("{limit: 1e5, ratio: 2.5E-3, 'n': 7,}"),
+            r#"{"limit": 1e5, "ratio": 2.5E-3, "n": 7}"#
+        );
+    }
+
+    #[test]
+    fn test_json_call_with_function_tag_in_content() {
+        let text = r#"<tool_call>{"tool": "write_file", "parameters": {"path": "prompt.txt", "content": "Reply with <function=name> blocks"}}</tool_call>"#;
+        let parsed = parse(text);
+        assert!(parsed.errors.is_empty(), "{:?}", parsed.errors);
+        assert_eq!(parsed.calls.len(), 1);
+        assert_eq!(parsed.calls[0].tool, "write_file");
+        assert_eq!(
+            parsed.calls[0].parameters["content"],
+            "Reply with <function=name> blocks"
+        );
+    }
+}
@@ -1,14 +1,99 @@
+use crate::audit::AuditRecord;
+use crate::git::GitHelper;
+use crate::mcp::{render_content, session_client, MCPTool};
+use crate::multi_file::{EditOperation, FileEdit, MultiFileEditor};
+use crate::outline::{display_matches, display_outline, find_symbol, outline, Language};
+use crate::output_policy::stored_output_path;
+use crate::patch::{apply_hunks, parse_unified_diff};
+use crate::process::processes;
+use crate::search::{grep, looks_binary, GrepOptions};
+use crate::test_runner::{run_tests, TestRunner};
+use futures_util::future::join_all;
 use serde::{Deserialize, Serialize};
+use std::io::{self, Write};
 use std::path::Path;
 use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tokio::time::timeout;
+
+const DEFAULT_READ_LIMIT: usize = 2000;
+const PARALLEL_TOOL_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_READ_FILE_SIZE: u64 = 10 * 1024 * 1024;
+const MAX_READ_OUTPUT: usize = 256 * 1024;
+const MAX_LINE_LENGTH: usize = 2000;
+/// Longest `start_process` waits for initial output, so a huge `wait_ms`
+/// cannot stall the turn
+const MAX_START_WAIT_MS: u64 = 10_000;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Tool {
     pub name: String,
     pub description: String,
+    /// Read-only tools have no side effects and may run concurrently
+    pub read_only: bool,
     pub parameters: Vec<Parameter>,
 }
 
+impl Tool {
+    /// Describe an MCP tool in the built-in catalog format, reading the
+    /// parameters from its input schema
+    pub fn from_mcp(tool: &MCPTool) -> Self {
+        let required: Vec<&str> = tool.input_schema["required"]
+            .as_array()
+            .map(|r| r.iter().filter_map(|v| v.as_str()).collect())
+            .unwrap_or_default();
+        let parameters = tool.input_schema["properties"]
+            .as_object()
+            .map(|properties| {
+                properties
+                    .iter()
+                    .map(|(name, schema)| Parameter {
+                        name: name.clone(),
+                        param_type: schema_type(schema),
+                        description: schema["description"].as_str().unwrap_or("").to_string(),
+                        required: required.contains(&name.as_str()),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Tool {
+            name: tool.qualified_name(),
+            description: format!("{} (MCP: {})", tool.description, tool.server),
+            read_only: false,
+            parameters,
+        }
+    }
+}
+
+fn schema_type(schema: &serde_json::Value) -> String {
+    match &schema["type"] {
+        serde_json::Value::String(t) if t == "array" => match schema["items"]["type"].as_str() {
+            Some(item) => format!("array of {}", item),
+            None => "array".to_string(),
+        },
+        serde_json::Value::String(t) => t.clone(),
+        serde_json::Value::Array(types) => types
+            .iter()
+            .filter_map(|t| t.as_str())
+            .collect::<Vec<_>>()
+            .join(" | "),
+        _ if schema["enum"].is_array() => format!(
+            "one of {}",
+            schema["enum"]
+                .as_array()
+                .map(|v| v
+                    .iter()
+                    .map(|e| e.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "))
+                .unwrap_or_default()
+        ),
+        _ => "any".to_string(),
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Parameter {
     pub name: String,
@@ -17,7 +102,7 @@ pub struct Parameter {
     pub required: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolCall {
     pub tool: String,
     pub parameters: serde_json::Value,
@@ -33,17 +118,58 @@ pub fn get_available_tools() -> Vec<Tool> {
     vec![
         Tool {
             name: "read_file".to_string(),
-            description: "Read contents of a file".to_string(),
-            parameters: vec![Parameter {
-                name: "path".to_string(),
-                param_type: "string".to_string(),
-                description: "File path to read".to_string(),
-                required: true,
-            }],
+            description: "Read a file with line numbers, optionally a line range".to_string(),
+            read_only: true,
+            parameters: vec![
+                Parameter {
+                    name: "path".to_string(),
+                    param_type: "string".to_string(),
+                    description: "File path to read".to_string(),
+                    required: true,
+                },
+                Parameter {
+                    name: "offset".to_string(),
+                    param_type: "integer".to_string(),
+                    description: "First line to read, 1-based (default: 1)".to_string(),
+                    required: false,
+                },
+                Parameter {
+                    name: "limit".to_string(),
+                    param_type: "integer".to_string(),
+                    description: "Number of lines to read (default: 2000)".to_string(),
+                    required: false,
+                },
+            ],
+        },
+        Tool {
+            name: "read_tool_output".to_string(),
+            description: "Read lines from a saved full tool output that was shortened".to_string(),
+            read_only: true,
+            parameters: vec![
+                Parameter {
+                    name: "id".to_string(),
+                    param_type: "string".to_string(),
+                    description: "Output id from the truncation notice".to_string(),
+                    required: true,
+                },
+                Parameter {
+                    name: "offset".to_string(),
+                    param_type: "integer".to_string(),
+                    description: "First line to read, 1-based (default: 1)".to_string(),
+                    required: false,
+                },
+                Parameter {
+                    name: "limit".to_string(),
+                    param_type: "integer".to_string(),
+                    description: "Number of lines to read (default: 2000)".to_string(),
+                    required: false,
+                },
+            ],
         },
         Tool {
             name: "write_file".to_string(),
             description: "Write content to a file".to_string(),
+            read_only: false,
             parameters: vec![
                 Parameter {
                     name: "path".to_string(),
@@ -59,9 +185,22 @@ pub fn get_available_tools() -> Vec<Tool> {
                 },
             ],
         },
+        Tool {
+            name: "apply_patch".to_string(),
+            description: "Apply a unified diff (may touch multiple files, create or delete them)"
+                .to_string(),
+            read_only: false,
+            parameters: vec![Parameter {
+                name: "patch".to_string(),
+                param_type: "string".to_string(),
+                description: "Unified diff text with ---/+++ file headers and @@ hunks".to_string(),
+                required: true,
+            }],
+        },
         Tool {
             name: "execute_bash".to_string(),
             description: "Execute a bash command".to_string(),
+            read_only: false,
             parameters: vec![Parameter {
                 name: "command".to_string(),
                 param_type: "string".to_string(),
@@ -69,9 +208,94 @@ pub fn get_available_tools() -> Vec<Tool> {
                 required: true,
             }],
         },
+        Tool {
+            name: "start_process".to_string(),
+            description: "Start a long-running command (dev server, watcher) in the background"
+                .to_string(),
+            read_only: false,
+            parameters: vec![
+                Parameter {
+                    name: "name".to_string(),
+                    param_type: "string".to_string(),
+                    description: "Name used to refer to the process later".to_string(),
+                    required: true,
+                },
+                Parameter {
+                    name: "command".to_string(),
+                    param_type: "string".to_string(),
+                    description: "Shell command to run".to_string(),
+                    required: true,
+                },
+                Parameter {
+                    name: "cwd".to_string(),
+                    param_type: "string".to_string(),
+                    description: "Working directory".to_string(),
+                    required: false,
+                },
+                Parameter {
+                    name: "wait_ms".to_string(),
+                    param_type: "integer".to_string(),
+                    description:
+                        "Milliseconds to wait for initial output (default: 1000, max: 10000)"
+                            .to_string(),
+                    required: false,
+                },
+            ],
+        },
+        Tool {
+            name: "read_process_output".to_string(),
+            description: "Read new output from a background process".to_string(),
+            // Advances the process's read cursor, so two reads must not overlap
+            read_only: false,
+            parameters: vec![
+                Parameter {
+                    name: "name".to_string(),
+                    param_type: "string".to_string(),
+                    description: "Process name".to_string(),
+                    required: true,
+                },
+                Parameter {
+                    name: "tail".to_string(),
+                    param_type: "integer".to_string(),
+                    description: "Return the last N lines instead of only new output".to_string(),
+                    required: false,
+                },
+            ],
+        },
+        Tool {
+            name: "send_process_input".to_string(),
+            description: "Write a line to a background process's stdin".to_string(),
+            read_only: false,
+            parameters: vec![
+                Parameter {
+                    name: "name".to_string(),
+                    param_type: "string".to_string(),
+                    description: "Process name".to_string(),
+                    required: true,
+                },
+                Parameter {
+                    name: "input".to_string(),
+                    param_type: "string".to_string(),
+                    description: "Text to send".to_string(),
+                    required: true,
+                },
+            ],
+        },
+        Tool {
+            name: "kill_process".to_string(),
+            description: "Stop a background process".to_string(),
+            read_only: false,
+            parameters: vec![Parameter {
+                name: "name".to_string(),
+                param_type: "string".to_string(),
+                description: "Process name".to_string(),
+                required: true,
+            }],
+        },
         Tool {
             name: "search_files".to_string(),
             description: "Search for files matching a pattern".to_string(),
+            read_only: true,
             parameters: vec![
                 Parameter {
                     name: "pattern".to_string(),
@@ -87,9 +311,59 @@ pub fn get_available_tools() -> Vec<Tool> {
                 },
             ],
         },
+        Tool {
+            name: "grep".to_string(),
+            description: "Regex search over file contents (respects .gitignore)".to_string(),
+            read_only: true,
+            parameters: vec![
+                Parameter {
+                    name: "pattern".to_string(),
+                    param_type: "string".to_string(),
+                    description: "Regular expression to search for".to_string(),
+                    required: true,
+                },
+                Parameter {
+                    name: "path".to_string(),
+                    param_type: "string".to_string(),
+                    description: "File or directory to search (default: .)".to_string(),
+                    required: false,
+                },
+                Parameter {
+                    name: "include".to_string(),
+                    param_type: "string".to_string(),
+                    description: "Comma-separated globs of files to search, e.g. *.rs".to_string(),
+                    required: false,
+                },
+                Parameter {
+                    name: "exclude".to_string(),
+                    param_type: "string".to_string(),
+                    description: "Comma-separated globs of files to skip".to_string(),
+                    required: false,
+                },
+                Parameter {
+                    name: "context".to_string(),
+                    param_type: "integer".to_string(),
+                    description: "Lines of context around each match".to_string(),
+                    required: false,
+                },
+                Parameter {
+                    name: "max_results".to_string(),
+                    param_type: "integer".to_string(),
+                    description: "Maximum number of matches (default: 100)".to_string(),
+                    required: false,
+                },
+                Parameter {
+                    name: "case_insensitive".to_string(),
+                    param_type: "boolean".to_string(),
+                    description: "Ignore case when matching".to_string(),
+                    required: false,
+                },
+            ],
+        },
         Tool {
             name: "list_directory".to_string(),
             description: "List contents of a directory".to_string(),
+            read_only: true,
             parameters: vec![Parameter {
                 name: "path".to_string(),
                 param_type: "string".to_string(),
@@ -97,16 +371,299 @@ pub fn get_available_tools() -> Vec<Tool> {
                 required: true,
             }],
         },
+        Tool {
+            name: "outline".to_string(),
+            description: "List a source file's functions, types, impls and classes with line ranges (Rust, Python, JS/TS, Go)".to_string(),
+            read_only: true,
+            parameters: vec![Parameter {
+                name: "path".to_string(),
+                param_type: "string".to_string(),
+                description: "Source file to outline".to_string(),
+                required: true,
+            }],
+        },
+        Tool {
+            name: "find_symbol".to_string(),
+            description: "Find where a function, type or class is defined across the repository".to_string(),
+            read_only: true,
+            parameters: vec![
+                Parameter {
+                    name: "name".to_string(),
+                    param_type: "string".to_string(),
+                    description: "Symbol name, optionally qualified (Type::method)".to_string(),
+                    required: true,
+                },
+                Parameter {
+                    name: "path".to_string(),
+                    param_type: "string".to_string(),
+                    description: "Directory to search (default: .)".to_string(),
+                    required: false,
+                },
+                Parameter {
+                    name: "kind".to_string(),
+                    param_type: "string".to_string(),
+                    description: "Only this kind: fn, struct, enum, trait, impl, class, def, function, func, method (members of impls, traits, classes and Go receivers), interface, type".to_string(),
+                    required: false,
+                },
+            ],
+        },
+        Tool {
+            name: "run_tests".to_string(),
+            description: "Run the project's tests (cargo, go, jest, npm or pytest) and report passes and failures".to_string(),
+            read_only: false,
+            parameters: vec![
+                Parameter {
+                    name: "filter".to_string(),
+                    param_type: "string".to_string(),
+                    description: "Only run tests matching this name".to_string(),
+                    required: false,
+                },
+                Parameter {
+                    name: "runner".to_string(),
+                    param_type: "string".to_string(),
+                    description: "cargo, go, jest, npm or pytest (default: detected)".to_string(),
+                    required: false,
+                },
+            ],
+        },
+        Tool {
+            name: "git_status".to_string(),
+            description: "Show changed and untracked files (git status --short)".to_string(),
+            read_only: true,
+            parameters: vec![],
+        },
+        Tool {
+            name: "git_diff".to_string(),
+            description: "Show uncommitted changes as a unified diff".to_string(),
+            read_only: true,
+            parameters: vec![
+                Parameter {
+                    name: "staged".to_string(),
+                    param_type: "boolean".to_string(),
+                    description: "Diff the staged changes instead of the working tree".to_string(),
+                    required: false,
+                },
+                Parameter {
+                    name: "path".to_string(),
+                    param_type: "string".to_string(),
+                    description: "Only diff this file or directory".to_string(),
+                    required: false,
+                },
+            ],
+        },
+        Tool {
+            name: "git_log".to_string(),
+            description: "List recent commits, one per line".to_string(),
+            read_only: true,
+            parameters: vec![
+                Parameter {
+                    name: "count".to_string(),
+                    param_type: "integer".to_string(),
+                    description: "Number of commits (default: 10)".to_string(),
+                    required: false,
+                },
+                Parameter {
+                    name: "path".to_string(),
+                    param_type: "string".to_string(),
+                    description: "Only commits touching this path".to_string(),
+                    required: false,
+                },
+            ],
+        },
+        Tool {
+            name: "git_show".to_string(),
+            description: "Show a commit's message and changes".to_string(),
+            read_only: true,
+            parameters: vec![
+                Parameter {
+                    name: "rev".to_string(),
+                    param_type: "string".to_string(),
+                    description: "Commit to show (default: HEAD)".to_string(),
+                    required: false,
+                },
+                Parameter {
+                    name: "stat".to_string(),
+                    param_type: "boolean".to_string(),
+                    description: "List changed files instead of the full patch".to_string(),
+                    required: false,
+                },
+            ],
+        },
+        Tool {
+            name: "git_commit".to_string(),
+            description: "Commit all tracked changes; the user is asked to approve first"
+                .to_string(),
+            read_only: false,
+            parameters: vec![Parameter {
+                name: "message".to_string(),
+                param_type: "string".to_string(),
+                description: "Commit message".to_string(),
+                required: true,
+            }],
+        },
     ]
 }
 
-pub async fn execute_tool(call: &ToolCall) -> ToolResult {
+/// Run a tool call. With `staging` set (dry-run mode), writes and commands are
+/// recorded in that editor instead of touching the disk or the shell.
+pub async fn execute_tool(call: &ToolCall, staging: Option<&mut MultiFileEditor>) -> ToolResult {
+    if !is_builtin(&call.tool) {
+        // MCP calls are audited by the MCP client, under their server's name
+        return match staging {
+            Some(editor) => defer_call(call, editor),
+            None => execute_mcp_tool(call).await,
+        };
+    }
+
+    let dry_run = staging.is_some();
+    audited(call, dry_run, dispatch_tool(call, staging)).await
+}
+
+/// Run a built-in tool and record it in the audit log
+async fn audited<F>(call: &ToolCall, dry_run: bool, run: F) -> ToolResult
+where
+    F: std::future::Future<Output = ToolResult>,
+{
+    let start = Instant::now();
+    let source = if dry_run {
+        "builtin:dry-run"
+    } else {
+        "builtin"
+    };
+    let (result, approved_by) = crate::audit::with_approval(run).await;
+
+    let (success, output) = match &result {
+        ToolResult::Success(out) => (true, out),
+        ToolResult::Error(err) => (false, err),
+    };
+    let mut record = AuditRecord::new(
+        source,
+        &call.tool,
+        &call.parameters,
+        success,
+        start.elapsed(),
+        output.len(),
+    );
+    record.approved_by = approved_by;
+    crate::audit::record(record).await;
+
+    result
+}
+
+pub fn is_builtin(tool: &str) -> bool {
+    get_available_tools().iter().any(|t| t.name == tool)
+}
+
+/// Built-in tools followed by the tools of the session's MCP servers, the
+/// latter named `server.tool` so servers cannot shadow each other
+pub async fn tool_catalog() -> Vec<Tool> {
+    let mut tools = get_available_tools();
+    let client = session_client().await.lock().await;
+    tools.extend(
+        client
+            .list_available_tools()
+            .into_iter()
+            .map(Tool::from_mcp),
+    );
+    tools
+}
+
+pub fn is_read_only(tool: &str) -> bool {
+    get_available_tools()
+        .iter()
+        .any(|t| t.name == tool && t.read_only)
+}
+
+/// Tools that may stop to ask the user: `git_commit`, and MCP tools, whose
+/// servers can request sampling while they run
+pub fn needs_approval(tool: &str) -> bool {
+    tool == "git_commit" || !is_builtin(tool)
+}
+
+/// Run calls in order, batching consecutive read-only calls to run concurrently.
+/// Results come back in the original order with each call's duration.
+pub async fn execute_tool_calls(
+    calls: &[ToolCall],
+    mut staging: Option<&mut MultiFileEditor>,
+) -> Vec<(ToolResult, Duration)> {
+    let mut results = Vec::with_capacity(calls.len());
+    let mut i = 0;
+
+    while i < calls.len() {
+        let batch = calls[i..]
+            .iter()
+            .take_while(|c| is_read_only(&c.tool))
+            .count();
+
+        if batch > 1 {
+            let staged = staging.as_deref();
+            let runs = calls[i..i + batch].iter().map(|call| async move {
+                let start = Instant::now();
+                // Read-only tools are all built in, and only need to see staged edits
+                let run = audited(call, staged.is_some(), async move {
+                    match staged.and_then(|editor| read_staged_file(call, editor)) {
+                        Some(result) => result,
+                        None => dispatch_tool(call, None).await,
+                    }
+                });
+                let result = match timeout(PARALLEL_TOOL_TIMEOUT, run).await {
+                    Ok(result) => result,
+                    Err(_) => ToolResult::Error(format!(
+                        "Timeout after {}s running {}",
+                        PARALLEL_TOOL_TIMEOUT.as_secs(),
+                        call.tool
+                    )),
+                };
+                (result, start.elapsed())
+            });
+            results.extend(join_all(runs).await);
+            i += batch;
+        } else {
+            let start = Instant::now();
+            let result = execute_tool(&calls[i], staging.as_deref_mut()).await;
+            results.push((result, start.elapsed()));
+            i += 1;
+        }
+    }
+
+    results
+}
+
+async fn dispatch_tool(call: &ToolCall, staging: Option<&mut MultiFileEditor>) -> ToolResult {
+    if let Some(editor) = staging {
+        match call.tool.as_str() {
+            "write_file" => return stage_write_file(call, editor),
+            "apply_patch" => return execute_apply_patch(call, Some(editor)).await,
+            "execute_bash" | "start_process" | "send_process_input" | "kill_process"
+            | "run_tests" | "git_commit" => return defer_call(call, editor),
+            _ => {
+                if let Some(result) = read_staged_file(call, editor) {
+                    return result;
+                }
+            }
+        }
+    }
+
     match call.tool.as_str() {
         "read_file" => execute_read_file(call).await,
+        "read_tool_output" => execute_read_tool_output(call).await,
         "write_file" => execute_write_file(call).await,
+        "apply_patch" => execute_apply_patch(call, None).await,
         "execute_bash" => execute_bash_command(call).await,
+        "start_process" => execute_start_process(call).await,
+        "read_process_output" => execute_read_process_output(call).await,
+        "send_process_input" => execute_send_process_input(call).await,
+        "kill_process" => execute_kill_process(call).await,
         "search_files" => execute_search_files(call).await,
+        "grep" => execute_grep(call).await,
         "list_directory" => execute_list_directory(call).await,
+        "outline" => execute_outline(call).await,
+        "find_symbol" => execute_find_symbol(call).await,
+        "run_tests" => execute_run_tests(call).await,
+        "git_status" | "git_diff" | "git_log" | "git_show" => {
+            execute_git_read(call, Path::new(".")).await
+        }
+        "git_commit" => execute_git_commit(call, Path::new("."), approve).await,
         _ => ToolResult::Error(format!("Unknown tool: {}", call.tool)),
     }
 }
@@ -117,10 +674,131 @@ async fn execute_read_file(call: &ToolCall) -> ToolResult {
         None => return ToolResult::Error("Missing 'path' parameter".to_string()),
     };
 
-    match tokio::fs::read_to_string(path).await {
-        Ok(content) => ToolResult::Success(content),
-        Err(e) => ToolResult::Error(format!("Failed to read {}: {}", path, e)),
+    let (offset, limit) = line_range(call);
+
+    let size = match tokio::fs::metadata(path).await {
+        Ok(m) if m.is_dir() => {
+            return ToolResult::Error(format!("{} is a directory, use list_directory", path))
+        }
+        Ok(m) => m.len(),
+        Err(e) => return ToolResult::Error(format!("Failed to read {}: {}", path, e)),
+    };
+    if size > MAX_READ_FILE_SIZE {
+        return ToolResult::Error(format!(
+            "{} is too large to read ({} bytes, limit {}). Use grep to find the relevant lines.",
+            path, size, MAX_READ_FILE_SIZE
+        ));
+    }
+
+    let bytes = match tokio::fs::read(path).await {
+        Ok(b) => b,
+        Err(e) => return ToolResult::Error(format!("Failed to read {}: {}", path, e)),
+    };
+    if looks_binary(&bytes) {
+        return ToolResult::Success(format!(
+            "{} is a binary file ({} bytes), contents not shown",
+            path, size
+        ));
+    }
+
+    ToolResult::Success(number_lines(
+        &String::from_utf8_lossy(&bytes),
+        offset,
+        limit,
+    ))
+}
+
+/// `read_file` of a file with a staged edit shows the staged content, so a
+/// dry run reads back its own writes. `None` for anything else.
+fn read_staged_file(call: &ToolCall, editor: &MultiFileEditor) -> Option<ToolResult> {
+    if call.tool != "read_file" {
+        return None;
     }
+    let path = call.parameters.get("path")?.as_str()?;
+    let edit = editor.pending_edit(path)?;
+    let (offset, limit) = line_range(call);
+    Some(match edit.operation {
+        EditOperation::Delete => ToolResult::Error(format!(
+            "Failed to read {}: staged for deletion in this dry run",
+            path
+        )),
+        _ => ToolResult::Success(number_lines(&edit.content, offset, limit)),
+    })
+}
+
+async fn execute_read_tool_output(call: &ToolCall) -> ToolResult {
+    let id = match call.parameters.get("id").and_then(|v| v.as_str()) {
+        Some(i) => i,
+        None => return ToolResult::Error("Missing 'id' parameter".to_string()),
+    };
+    let path = match stored_output_path(id) {
+        Ok(p) => p,
+        Err(e) => return ToolResult::Error(e),
+    };
+    let (offset, limit) = line_range(call);
+
+    match tokio::fs::read_to_string(&path).await {
+        Ok(content) => ToolResult::Success(number_lines(&content, offset, limit)),
+        Err(e) => ToolResult::Error(format!("No saved output {}: {}", id, e)),
+    }
+}
+
+/// `offset`/`limit` parameters shared by the line-oriented read tools
+fn line_range(call: &ToolCall) -> (usize, usize) {
+    let offset = call
+        .parameters
+        .get("offset")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(1)
+        .max(1) as usize;
+    let limit = call
+        .parameters
+        .get("limit")
+        .and_then(|v| v.as_u64())
+        .map(|l| l as usize)
+        .unwrap_or(DEFAULT_READ_LIMIT)
+        .max(1);
+    (offset, limit)
+}
+
+/// Render lines `offset..offset+limit` in `cat -n` style, noting any truncation
+fn number_lines(content: &str, offset: usize, limit: usize) -> String {
+    let total = content.lines().count();
+    if total == 0 {
+        return "(empty file)\n".to_string();
+    }
+    if offset > total {
+        return format!(
+            "(offset {} is past the end of the file, {} lines)\n",
+            offset, total
+        );
+    }
+
+    let mut output = String::new();
+    let mut shown = 0;
+    for (idx, line) in content.lines().enumerate().skip(offset - 1).take(limit) {
+        if output.len() >= MAX_READ_OUTPUT {
+            break;
+        }
+        let line = if line.chars().count() > MAX_LINE_LENGTH {
+            let cut: String = line.chars().take(MAX_LINE_LENGTH).collect();
+            format!("{}… [line truncated]", cut)
+        } else {
+            line.to_string()
+        };
+        output.push_str(&format!("{:>6}\t{}\n", idx + 1, line));
+        shown += 1;
+    }
+
+    let last = offset + shown - 1;
+    if offset > 1 || last < total {
+        output.push_str(&format!(
+            "[showing lines {}-{} of {}; use offset/limit to read more]\n",
+            offset, last, total
+        ));
+    }
+
+    output
 }
 
 async fn execute_write_file(call: &ToolCall) -> ToolResult {
@@ -148,6 +826,163 @@ async fn execute_write_file(call: &ToolCall) -> ToolResult {
     }
 }
 
+fn stage_write_file(call: &ToolCall, editor: &mut MultiFileEditor) -> ToolResult {
+    let path = match call.parameters.get("path").and_then(|v| v.as_str()) {
+        Some(p) => p,
+        None => return ToolResult::Error("Missing 'path' parameter".to_string()),
+    };
+    let content = match call.parameters.get("content").and_then(|v| v.as_str()) {
+        Some(c) => c,
+        None => return ToolResult::Error("Missing 'content' parameter".to_string()),
+    };
+
+    editor.add_edit(FileEdit {
+        path: path.to_string(),
+        content: content.to_string(),
+        operation: staged_operation(editor, path),
+    });
+    ToolResult::Success(format!(
+        "Dry run: staged write to {} ({} bytes), not written",
+        path,
+        content.len()
+    ))
+}
+
+/// Create if neither the disk nor an earlier staged edit has the file
+fn staged_operation(editor: &MultiFileEditor, path: &str) -> EditOperation {
+    let staged = editor
+        .pending_edit(path)
+        .map(|e| !matches!(e.operation, EditOperation::Delete));
+    if staged.unwrap_or_else(|| Path::new(path).exists()) {
+        EditOperation::Modify
+    } else {
+        EditOperation::Create
+    }
+}
+
+fn defer_call(call: &ToolCall, editor: &mut MultiFileEditor) -> ToolResult {
+    editor.defer_call(&call.tool, call.parameters.clone());
+    ToolResult::Success(format!(
+        "Dry run: recorded {} {}, not executed; it runs after all staged edits once the user applies them",
+        call.tool, call.parameters
+    ))
+}
+
+async fn execute_apply_patch(call: &ToolCall, staging: Option<&mut MultiFileEditor>) -> ToolResult {
+    let patch = match call.parameters.get("patch").and_then(|v| v.as_str()) {
+        Some(p) => p,
+        None => return ToolResult::Error("Missing 'patch' parameter".to_string()),
+    };
+
+    let file_patches = match parse_unified_diff(patch) {
+        Ok(p) => p,
+        Err(e) => return ToolResult::Error(format!("Invalid patch: {}", e)),
+    };
+
+    let dry_run = staging.is_some();
+    let mut local = MultiFileEditor::new();
+    let editor = staging.unwrap_or(&mut local);
+    let mut report = String::new();
+    let mut failed_hunks = 0;
+
+    for file_patch in &file_patches {
+        let path = file_patch.path().to_string();
+
+        let staged = editor.pending_edit(&path).map(|e| {
+            (
+                matches!(e.operation, EditOperation::Delete),
+                e.content.clone(),
+            )
+        });
+
+        if file_patch.is_delete() {
+            let exists = staged
+                .as_ref()
+                .map(|(deleted, _)| !deleted)
+                .unwrap_or_else(|| Path::new(&path).exists());
+            if !exists {
+                failed_hunks += file_patch.hunks.len().max(1);
+                report.push_str(&format!("✗ {}: cannot delete, file does not exist\n", path));
+                continue;
+            }
+            editor.add_edit(FileEdit {
+                path: path.clone(),
+                content: String::new(),
+                operation: EditOperation::Delete,
+            });
+            report.push_str(&format!("✓ {}: deleted\n", path));
+            continue;
+        }
+
+        let original = if file_patch.is_create() {
+            String::new()
+        } else if let Some((false, content)) = staged {
+            content
+        } else {
+            match tokio::fs::read_to_string(&path).await {
+                Ok(c) => c,
+                Err(e) => {
+                    failed_hunks += file_patch.hunks.len();
+                    report.push_str(&format!("✗ {}: failed to read: {}\n", path, e));
+                    continue;
+                }
+            }
+        };
+
+        let result = apply_hunks(&original, &file_patch.hunks, file_patch.no_newline_at_end);
+
+        // A file is only changed when every one of its hunks applies
+        if !result.failed.is_empty() {
+            failed_hunks += file_patch.hunks.len();
+            report.push_str(&format!(
+                "✗ {}: rejected, {}/{} hunks did not apply; file left unchanged\n",
+                path,
+                result.failed.len(),
+                file_patch.hunks.len()
+            ));
+            for failure in &result.failed {
+                report.push_str(&format!(
+                    "    hunk #{} at line {} failed: {}\n",
+                    failure.index, failure.old_start, failure.reason
+                ));
+            }
+            continue;
+        }
+
+        let operation = staged_operation(editor, &path);
+        editor.add_edit(FileEdit {
+            path: path.clone(),
+            content: result.content,
+            operation,
+        });
+
+        report.push_str(&format!(
+            "✓ {}: {}/{} hunks applied",
+            path,
+            result.applied,
+            file_patch.hunks.len()
+        ));
+        if result.fuzzed > 0 {
+            report.push_str(&format!(" ({} with fuzz)", result.fuzzed));
+        }
+        report.push('\n');
+    }
+
+    if dry_run {
+        report.push_str("Dry run: changes staged, not written\n");
+    } else if editor.has_pending() {
+        if let Err(e) = editor.apply_all().await {
+            return ToolResult::Error(format!("Failed to apply patch: {}\n{}", e, report));
+        }
+    }
+
+    if failed_hunks > 0 {
+        ToolResult::Error(format!("{} hunk(s) rejected\n{}", failed_hunks, report))
+    } else {
+        ToolResult::Success(report)
+    }
+}
+
 async fn execute_bash_command(call: &ToolCall) -> ToolResult {
     let command = match call.parameters.get("command").and_then(|v| v.as_str()) {
         Some(c) => c,
@@ -172,6 +1007,81 @@ async fn execute_bash_command(call: &ToolCall) -> ToolResult {
     }
 }
 
+async fn execute_start_process(call: &ToolCall) -> ToolResult {
+    let name = match call.parameters.get("name").and_then(|v| v.as_str()) {
+        Some(n) => n,
+        None => return ToolResult::Error("Missing 'name' parameter".to_string()),
+    };
+    let command = match call.parameters.get("command").and_then(|v| v.as_str()) {
+        Some(c) => c,
+        None => return ToolResult::Error("Missing 'command' parameter".to_string()),
+    };
+    let cwd = call.parameters.get("cwd").and_then(|v| v.as_str());
+    let wait_ms = call
+        .parameters
+        .get("wait_ms")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(1000)
+        .min(MAX_START_WAIT_MS);
+
+    let pid = match processes().lock().await.start(name, command, cwd) {
+        Ok(pid) => pid,
+        Err(e) => return ToolResult::Error(e),
+    };
+
+    // Give the process a moment so immediate failures show up in the result
+    tokio::time::sleep(std::time::Duration::from_millis(wait_ms)).await;
+    match processes().lock().await.read_output(name, None) {
+        Ok(output) => ToolResult::Success(format!("Started '{}' (pid {})\n{}", name, pid, output)),
+        Err(e) => ToolResult::Error(e),
+    }
+}
+
+async fn execute_read_process_output(call: &ToolCall) -> ToolResult {
+    let name = match call.parameters.get("name").and_then(|v| v.as_str()) {
+        Some(n) => n,
+        None => return ToolResult::Error("Missing 'name' parameter".to_string()),
+    };
+    let tail = call
+        .parameters
+        .get("tail")
+        .and_then(|v| v.as_u64())
+        .map(|t| t as usize);
+
+    match processes().lock().await.read_output(name, tail) {
+        Ok(output) => ToolResult::Success(output),
+        Err(e) => ToolResult::Error(e),
+    }
+}
+
+async fn execute_send_process_input(call: &ToolCall) -> ToolResult {
+    let name = match call.parameters.get("name").and_then(|v| v.as_str()) {
+        Some(n) => n,
+        None => return ToolResult::Error("Missing 'name' parameter".to_string()),
+    };
+    let input = match call.parameters.get("input").and_then(|v| v.as_str()) {
+        Some(i) => i,
+        None => return ToolResult::Error("Missing 'input' parameter".to_string()),
+    };
+
+    match processes().lock().await.send_input(name, input).await {
+        Ok(()) => ToolResult::Success(format!("Sent input to '{}'", name)),
+        Err(e) => ToolResult::Error(e),
+    }
+}
+
+async fn execute_kill_process(call: &ToolCall) -> ToolResult {
+    let name = match call.parameters.get("name").and_then(|v| v.as_str()) {
+        Some(n) => n,
+        None => return ToolResult::Error("Missing 'name' parameter".to_string()),
+    };
+
+    match processes().lock().await.kill(name).await {
+        Ok(msg) => ToolResult::Success(msg),
+        Err(e) => ToolResult::Error(e),
+    }
+}
+
 async fn execute_search_files(call: &ToolCall) -> ToolResult {
     let pattern = match call.parameters.get("pattern").and_then(|v| v.as_str()) {
         Some(p) => p,
@@ -199,6 +1109,57 @@ async fn execute_search_files(call: &ToolCall) -> ToolResult {
     }
 }
 
+async fn execute_grep(call: &ToolCall) -> ToolResult {
+    let pattern = match call.parameters.get("pattern").and_then(|v| v.as_str()) {
+        Some(p) => p,
+        None => return ToolResult::Error("Missing 'pattern' parameter".to_string()),
+    };
+
+    let mut options = GrepOptions::new(pattern);
+    if let Some(path) = call.parameters.get("path").and_then(|v| v.as_str()) {
+        options.path = path.to_string();
+    }
+    options.include = glob_list(call.parameters.get("include"));
+    options.exclude = glob_list(call.parameters.get("exclude"));
+    options.context = call
+        .parameters
+        .get("context")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0) as usize;
+    if let Some(max) = call.parameters.get("max_results").and_then(|v| v.as_u64()) {
+        options.max_results = max as usize;
+    }
+    options.case_insensitive = call
+        .parameters
+        .get("case_insensitive")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    match tokio::task::spawn_blocking(move || grep(&options)).await {
+        Ok(Ok(results)) => ToolResult::Success(results.display()),
+        Ok(Err(e)) => ToolResult::Error(e),
+        Err(e) => ToolResult::Error(format!("Search failed: {}", e)),
+    }
+}
+
+/// Accept globs as either a JSON array or a comma-separated string
+fn glob_list(value: Option<&serde_json::Value>) -> Vec<String> {
+    match value {
+        Some(serde_json::Value::Array(items)) => items
+            .iter()
+            .filter_map(|v| v.as_str())
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect(),
+        Some(serde_json::Value::String(s)) => s
+            .split(',')
+            .map(|g| g.trim().to_string())
+            .filter(|g| !g.is_empty())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
 async fn execute_list_directory(call: &ToolCall) -> ToolResult {
     let path = match call.parameters.get("path").and_then(|v| v.as_str()) {
         Some(p) => p,
@@ -219,47 +1180,219 @@ async fn execute_list_directory(call: &ToolCall) -> ToolResult {
     }
 }
 
-pub fn parse_tool_calls(text: &str) -> Vec<ToolCall> {
-    let mut calls = Vec::new();
+async fn execute_outline(call: &ToolCall) -> ToolResult {
+    let path = match call.parameters.get("path").and_then(|v| v.as_str()) {
+        Some(p) => p,
+        None => return ToolResult::Error("Missing 'path' parameter".to_string()),
+    };
+    let Some(language) = Language::from_path(Path::new(path)) else {
+        return ToolResult::Error(format!(
+            "Unsupported file type for outline: {} (Rust, Python, JS/TS and Go are supported)",
+            path
+        ));
+    };
 
-    // Look for tool calls in format: <tool_call>{tool:name,parameters:{...}}</tool_call>
-    let mut start = 0;
-    while let Some(begin) = text[start..].find("<tool_call>") {
-        let begin = start + begin + "<tool_call>".len();
-        if let Some(end) = text[begin..].find("</tool_call>") {
-            let json_str = &text[begin..begin + end];
-            if let Ok(call) = serde_json::from_str::<ToolCall>(json_str) {
-                calls.push(call);
-            }
-            start = begin + end + "</tool_call>".len();
-        } else {
-            break;
+    match tokio::fs::read_to_string(path).await {
+        Ok(source) => ToolResult::Success(display_outline(path, &outline(&source, language))),
+        Err(e) => ToolResult::Error(format!("Failed to read {}: {}", path, e)),
+    }
+}
+
+async fn execute_find_symbol(call: &ToolCall) -> ToolResult {
+    let name = match call.parameters.get("name").and_then(|v| v.as_str()) {
+        Some(n) if !n.is_empty() => n.to_string(),
+        _ => return ToolResult::Error("Missing 'name' parameter".to_string()),
+    };
+    let root = call
+        .parameters
+        .get("path")
+        .and_then(|v| v.as_str())
+        .unwrap_or(".")
+        .to_string();
+    let kind = call
+        .parameters
+        .get("kind")
+        .and_then(|v| v.as_str())
+        .map(|k| k.to_string());
+
+    let search = {
+        let name = name.clone();
+        tokio::task::spawn_blocking(move || find_symbol(&name, &root, kind.as_deref()))
+    };
+    match search.await {
+        Ok(Ok(matches)) => ToolResult::Success(display_matches(&name, &matches)),
+        Ok(Err(e)) => ToolResult::Error(e),
+        Err(e) => ToolResult::Error(format!("Symbol search failed: {}", e)),
+    }
+}
+
+async fn execute_run_tests(call: &ToolCall) -> ToolResult {
+    let runner = match call.parameters.get("runner").and_then(|v| v.as_str()) {
+        Some(name) => match TestRunner::from_name(name) {
+            Some(runner) => Some(runner),
+            None => return ToolResult::Error(format!("Unknown test runner: {}", name)),
+        },
+        None => None,
+    };
+    let filter = call.parameters.get("filter").and_then(|v| v.as_str());
+
+    // Failing tests are a normal result for the model to act on, not a tool error
+    match run_tests(runner, filter).await {
+        Ok(report) => ToolResult::Success(report.display()),
+        Err(e) => ToolResult::Error(e),
+    }
+}
+
+/// Run a read-only git tool in `dir`
+async fn execute_git_read(call: &ToolCall, dir: &Path) -> ToolResult {
+    let params = call.parameters.clone();
+    let tool = call.tool.clone();
+    let dir = dir.to_path_buf();
+    let output = tokio::task::spawn_blocking(move || {
+        let path = params.get("path").and_then(|v| v.as_str());
+        match tool.as_str() {
+            "git_status" => GitHelper::status_in(&dir),
+            "git_diff" => GitHelper::diff_with(
+                &dir,
+                params
+                    .get("staged")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false),
+                path,
+            ),
+            "git_log" => GitHelper::log_path(
+                &dir,
+                params.get("count").and_then(|v| v.as_u64()).unwrap_or(10) as usize,
+                path,
+            ),
+            _ => GitHelper::show(
+                &dir,
+                params.get("rev").and_then(|v| v.as_str()).unwrap_or("HEAD"),
+                params
+                    .get("stat")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false),
+            ),
         }
+    })
+    .await;
+
+    match output {
+        Ok(Ok(out)) if out.trim().is_empty() => ToolResult::Success("(no output)".to_string()),
+        Ok(Ok(out)) => ToolResult::Success(out),
+        Ok(Err(e)) => ToolResult::Error(e),
+        Err(e) => ToolResult::Error(format!("Git failed: {}", e)),
+    }
+}
+
+/// Commit in `dir` once `approve` allows it
+async fn execute_git_commit(call: &ToolCall, dir: &Path, approve: fn(&str) -> bool) -> ToolResult {
+    let message = match call.parameters.get("message").and_then(|v| v.as_str()) {
+        Some(m) if !m.trim().is_empty() => m,
+        _ => return ToolResult::Error("Missing 'message' parameter".to_string()),
+    };
+
+    let changes = GitHelper::status_in(dir).unwrap_or_default();
+    let prompt = format!(
+        "The assistant wants to commit:\n{}\nMessage: {}",
+        changes.trim_end(),
+        message
+    );
+    let approved = approve(&prompt);
+    crate::audit::note_approval(approved);
+    if !approved {
+        return ToolResult::Error("The user declined the commit".to_string());
+    }
+
+    match GitHelper::commit_in(dir, message) {
+        Ok(out) => ToolResult::Success(out),
+        Err(e) => ToolResult::Error(format!("Commit failed: {}", e)),
     }
+}
 
-    calls
+static PROMPT_ON_TTY: AtomicBool = AtomicBool::new(false);
+
+/// Ask approvals on the controlling terminal instead of stdin/stdout, for
+/// `mcp-serve` where those carry the protocol
+pub fn prompt_on_tty(enabled: bool) {
+    PROMPT_ON_TTY.store(enabled, Ordering::Relaxed);
+}
+
+/// One approval prompt at a time, however many tools or servers ask at once
+static PROMPT: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// Ask the user at the terminal before a tool does something hard to undo
+pub fn approve(action: &str) -> bool {
+    let _prompt = PROMPT.lock().unwrap_or_else(|e| e.into_inner());
+    if PROMPT_ON_TTY.load(Ordering::Relaxed) {
+        return ask_on_tty(action);
+    }
+    println!("\n🔐 {}", action);
+    print!("Allow? (y/N): ");
+    io::stdout().flush().ok();
+
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// Ask on the controlling terminal, never stdin, for requests that arrive
+/// from a background task while something else may be reading stdin
+pub fn approve_on_tty(action: &str) -> bool {
+    let _prompt = PROMPT.lock().unwrap_or_else(|e| e.into_inner());
+    ask_on_tty(action)
+}
+
+/// Without a terminal there is nobody to ask, so the action is declined
+fn ask_on_tty(action: &str) -> bool {
+    let Ok(mut tty) = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+    else {
+        return false;
+    };
+    if write!(tty, "\n🔐 {}\nAllow? (y/N): ", action).is_err() {
+        return false;
+    }
+
+    let mut answer = String::new();
+    if io::BufRead::read_line(&mut io::BufReader::new(tty), &mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+async fn execute_mcp_tool(call: &ToolCall) -> ToolResult {
+    let client = session_client().await.lock().await.clone();
+    if let Err(e) = client.resolve_tool(&call.tool) {
+        return ToolResult::Error(e);
+    }
+
+    match client
+        .call_tool_streaming(&call.tool, call.parameters.clone())
+        .await
+    {
+        Ok(result) => {
+            let text = render_content(&result);
+            if result["isError"].as_bool().unwrap_or(false) {
+                ToolResult::Error(text)
+            } else {
+                ToolResult::Success(text)
+            }
+        }
+        Err(e) => ToolResult::Error(format!("MCP call failed: {}", e)),
+    }
 }
 
 pub fn tools_to_prompt() -> String {
     let tools = get_available_tools();
     let mut prompt = String::from("You have access to these tools:\n\n");
 
-    for tool in tools {
-        prompt.push_str(&format!("Tool: {}\n", tool.name));
-        prompt.push_str(&format!("Description: {}\n", tool.description));
-        prompt.push_str("Parameters:\n");
-        for param in tool.parameters {
-            let req = if param.required {
-                "required"
-            } else {
-                "optional"
-            };
-            prompt.push_str(&format!(
-                "  - {} ({}): {} [{}]\n",
-                param.name, param.param_type, param.description, req
-            ));
-        }
-        prompt.push('\n');
+    for tool in &tools {
+        prompt.push_str(&tool_prompt(tool));
     }
 
     prompt.push_str("To use a tool, output: <tool_call>{\"tool\":\"tool_name\",\"parameters\":{...}}</tool_call>\n");
@@ -267,3 +1400,386 @@ pub fn tools_to_prompt() -> String {
 
     prompt
 }
+
+pub fn tool_prompt(tool: &Tool) -> String {
+    let mut prompt = format!("Tool: {}\n", tool.name);
+    prompt.push_str(&format!("Description: {}\n", tool.description));
+    prompt.push_str("Parameters:\n");
+    for param in &tool.parameters {
+        let req = if param.required {
+            "required"
+        } else {
+            "optional"
+        };
+        prompt.push_str(&format!(
+            "  - {} ({}): {} [{}]\n",
+            param.name, param.param_type, param.description, req
+        ));
+    }
+    prompt.push('\n');
+    prompt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("wiseowl_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn patch_call(patch: &str) -> ToolCall {
+        ToolCall {
+            tool: "apply_patch".to_string(),
+            parameters: serde_json::json!({ "patch": patch }),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_apply_patch_creates_and_deletes() {
+        let dir = temp_dir("patch_create");
+        let created = dir.join("nested/new.txt").display().to_string();
+        let doomed = dir.join("old.txt").display().to_string();
+        std::fs::write(&doomed, "bye\n").unwrap();
+
+        let patch = format!(
+            "--- /dev/null\n+++ {}\n@@ -0,0 +1 @@\n+hello\n--- {}\n+++ /dev/null\n@@ -1 +0,0 @@\n-bye\n",
+            created, doomed
+        );
+        let result = execute_apply_patch(&patch_call(&patch), None).await;
+        assert!(matches!(result, ToolResult::Success(_)), "{:?}", result);
+        assert_eq!(std::fs::read_to_string(&created).unwrap(), "hello\n");
+        assert!(!Path::new(&doomed).exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_apply_patch_rejects_partially_matching_file() {
+        let dir = temp_dir("patch_partial");
+        let path = dir.join("f.txt").display().to_string();
+        let missing = dir.join("missing/new.txt");
+        std::fs::write(&path, "one\ntwo\nthree\n").unwrap();
+
+        let patch = format!(
+            "--- {0}\n+++ {0}\n@@ -1 +1 @@\n-one\n+1\n@@ -3 +3 @@\n-nine\n+9\n\
+             --- /dev/null\n+++ {1}\n@@ -0,0 +1 @@\n+x\n--- {0}.none\n+++ {0}.none\n@@ -1 +1 @@\n-a\n+b\n",
+            path,
+            missing.display()
+        );
+        match execute_apply_patch(&patch_call(&patch), None).await {
+            ToolResult::Error(report) => {
+                assert!(
+                    report.contains("rejected, 1/2 hunks did not apply"),
+                    "{}",
+                    report
+                );
+            }
+            other => panic!("expected rejection, got {:?}", other),
+        }
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "one\ntwo\nthree\n");
+        // Files whose hunks all applied are still written
+        assert_eq!(std::fs::read_to_string(&missing).unwrap(), "x\n");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_apply_patch_dry_run_stages_without_writing() {
+        let dir = temp_dir("patch_dry_run");
+        let path = dir.join("f.txt").display().to_string();
+        let created = dir.join("sub/new.txt");
+        std::fs::write(&path, "one\n").unwrap();
+
+        let patch = format!(
+            "--- {0}\n+++ {0}\n@@ -1 +1 @@\n-one\n+1\n--- /dev/null\n+++ {1}\n@@ -0,0 +1 @@\n+x\n",
+            path,
+            created.display()
+        );
+        let mut editor = MultiFileEditor::new();
+        let result = execute_apply_patch(&patch_call(&patch), Some(&mut editor)).await;
+        assert!(matches!(result, ToolResult::Success(_)), "{:?}", result);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "one\n");
+        assert!(!dir.join("sub").exists());
+        assert_eq!(editor.pending_edit(&path).unwrap().content, "1\n");
+
+        // A second patch builds on the staged content
+        let patch = format!("--- {0}\n+++ {0}\n@@ -1 +1 @@\n-1\n+uno\n", path);
+        execute_apply_patch(&patch_call(&patch), Some(&mut editor)).await;
+        assert_eq!(editor.pending_edit(&path).unwrap().content, "uno\n");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    fn read_call(path: &str) -> ToolCall {
+        ToolCall {
+            tool: "read_file".to_string(),
+            parameters: serde_json::json!({ "path": path }),
+        }
+    }
+
+    fn output(result: &ToolResult) -> &str {
+        match result {
+            ToolResult::Success(output) => output,
+            ToolResult::Error(error) => panic!("tool failed: {}", error),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tool_results_keep_call_order_across_writes() {
+        crate::audit::use_test_dir();
+        let dir = temp_dir("call_order");
+        let a = dir.join("a.txt").display().to_string();
+        let b = dir.join("b.txt").display().to_string();
+        std::fs::write(&a, "alpha\n").unwrap();
+        std::fs::write(&b, "beta\n").unwrap();
+        let write = ToolCall {
+            tool: "write_file".to_string(),
+            parameters: serde_json::json!({ "path": a, "content": "rewritten\n" }),
+        };
+
+        // The write ends the first batch, so the reads after it see its result
+        let calls = [
+            read_call(&a),
+            read_call(&b),
+            write,
+            read_call(&a),
+            read_call(&b),
+        ];
+        let results = execute_tool_calls(&calls, None).await;
+        let outputs: Vec<&str> = results.iter().map(|(result, _)| output(result)).collect();
+        assert!(outputs[0].contains("alpha"));
+        assert!(outputs[1].contains("beta"));
+        assert!(outputs[3].contains("rewritten"));
+        assert!(outputs[4].contains("beta"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_read_only_batch_runs_concurrently() {
+        crate::audit::use_test_dir();
+        let dir = temp_dir("concurrent");
+        let first = dir.join("first").display().to_string();
+        let second = dir.join("second").display().to_string();
+        for fifo in [&first, &second] {
+            assert!(Command::new("mkfifo").arg(fifo).status().unwrap().success());
+        }
+
+        // Opening a FIFO for writing waits for its reader, and the second is
+        // written first: this only finishes if both reads are in flight at once
+        let (writer_first, writer_second) = (first.clone(), second.clone());
+        std::thread::spawn(move || {
+            std::fs::write(writer_second, "two\n").unwrap();
+            std::fs::write(writer_first, "one\n").unwrap();
+        });
+
+        let calls = [read_call(&first), read_call(&second)];
+        let results = match timeout(Duration::from_secs(10), execute_tool_calls(&calls, None)).await
+        {
+            Ok(results) => results,
+            Err(_) => {
+                // Release the writer, and with it the stuck read, before failing
+                let _ = std::fs::read(&second);
+                panic!("read-only calls ran one after the other");
+            }
+        };
+        assert!(output(&results[0].0).contains("one"));
+        assert!(output(&results[1].0).contains("two"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_parallel_dry_run_calls_are_audited_as_dry_run() {
+        crate::audit::use_test_dir();
+        let dir = temp_dir("dry_run_audit");
+        let path = dir.join("f.txt").display().to_string();
+        std::fs::write(&path, "x\n").unwrap();
+
+        let mut editor = MultiFileEditor::new();
+        let calls = [read_call(&path), read_call(&path)];
+        execute_tool_calls(&calls, Some(&mut editor)).await;
+
+        let filter = crate::audit::AuditFilter::parse(&["1000", "source=builtin:dry-run"]).unwrap();
+        let records = crate::audit::recent(&filter).await.unwrap();
+        let audited = records
+            .iter()
+            .filter(|r| r.arguments["path"] == path.as_str())
+            .count();
+        assert_eq!(audited, 2);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_reads_see_staged_content() {
+        crate::audit::use_test_dir();
+        let dir = temp_dir("staged_read");
+        let path = dir.join("f.txt").display().to_string();
+        std::fs::write(&path, "on disk\n").unwrap();
+        let read = ToolCall {
+            tool: "read_file".to_string(),
+            parameters: serde_json::json!({ "path": path }),
+        };
+        let write = ToolCall {
+            tool: "write_file".to_string(),
+            parameters: serde_json::json!({ "path": path, "content": "staged\n" }),
+        };
+
+        let mut editor = MultiFileEditor::new();
+        let calls = [write, read.clone(), read];
+        let results = execute_tool_calls(&calls, Some(&mut editor)).await;
+        for (result, _) in &results[1..] {
+            match result {
+                ToolResult::Success(output) => assert!(output.contains("staged"), "{}", output),
+                other => panic!("read failed: {:?}", other),
+            }
+        }
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "on disk\n");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// A repository with one committed file, changed since
+    fn git_repo(name: &str) -> PathBuf {
+        let dir = temp_dir(name);
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .args(args)
+                .current_dir(&dir)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {:?}", args);
+        };
+        git(&["init", "-q"]);
+        git(&["config", "user.name", "Test"]);
+        git(&["config", "user.email", "test@example.com"]);
+        git(&["config", "commit.gpgsign", "false"]);
+        std::fs::write(dir.join("f.txt"), "one\n").unwrap();
+        git(&["add", "f.txt"]);
+        git(&["commit", "-qm", "first"]);
+        std::fs::write(dir.join("f.txt"), "two\n").unwrap();
+        dir
+    }
+
+    fn commit_count(dir: &Path) -> String {
+        let output = Command::new("git")
+            .args(["rev-list", "--count", "HEAD"])
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    fn commit_call() -> ToolCall {
+        git_call("git_commit", serde_json::json!({ "message": "second" }))
+    }
+
+    fn git_call(tool: &str, parameters: serde_json::Value) -> ToolCall {
+        ToolCall {
+            tool: tool.to_string(),
+            parameters,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_git_read_tools_pass_their_parameters() {
+        let dir = git_repo("git_read");
+        std::fs::write(dir.join("g.txt"), "other\n").unwrap();
+        Command::new("git")
+            .args(["add", "g.txt"])
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+
+        let run = |tool: &str, parameters: serde_json::Value| {
+            let call = git_call(tool, parameters);
+            let dir = dir.clone();
+            async move {
+                match execute_git_read(&call, &dir).await {
+                    ToolResult::Success(out) => out,
+                    ToolResult::Error(e) => panic!("{} failed: {}", call.tool, e),
+                }
+            }
+        };
+
+        let status = run("git_status", serde_json::json!({})).await;
+        assert!(
+            status.contains(" M f.txt") && status.contains("A  g.txt"),
+            "{}",
+            status
+        );
+
+        // Unstaged changes to f.txt, staged addition of g.txt
+        let unstaged = run("git_diff", serde_json::json!({})).await;
+        assert!(
+            unstaged.contains("+two") && !unstaged.contains("g.txt"),
+            "{}",
+            unstaged
+        );
+        let staged = run("git_diff", serde_json::json!({ "staged": true })).await;
+        assert!(
+            staged.contains("+other") && !staged.contains("f.txt"),
+            "{}",
+            staged
+        );
+        let other = run("git_diff", serde_json::json!({ "path": "g.txt" })).await;
+        assert_eq!(other, "(no output)");
+
+        let log = run(
+            "git_log",
+            serde_json::json!({ "count": 5, "path": "f.txt" }),
+        )
+        .await;
+        assert!(log.trim_end().ends_with("first"), "{}", log);
+        assert_eq!(log.lines().count(), 1);
+        let log = run("git_log", serde_json::json!({ "path": "g.txt" })).await;
+        assert_eq!(log, "(no output)");
+
+        let show = run(
+            "git_show",
+            serde_json::json!({ "rev": "HEAD", "stat": true }),
+        )
+        .await;
+        assert!(
+            show.contains("f.txt | 1 +") && !show.contains("+one"),
+            "{}",
+            show
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_git_commit_declined() {
+        let dir = git_repo("commit_declined");
+        let call = commit_call();
+        let commit = execute_git_commit(&call, &dir, |_| false);
+        match crate::audit::with_approval(commit).await {
+            (ToolResult::Error(e), approved_by) => {
+                assert!(e.contains("declined"), "{}", e);
+                assert_eq!(approved_by, "declined");
+            }
+            other => panic!("commit was not refused: {:?}", other),
+        }
+        assert_eq!(commit_count(&dir), "1");
+        assert!(GitHelper::status_in(&dir).unwrap().contains("f.txt"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_git_commit_approved() {
+        let dir = git_repo("commit_approved");
+        let approve = |action: &str| action.contains("f.txt") && action.contains("second");
+        let call = commit_call();
+        let commit = execute_git_commit(&call, &dir, approve);
+        match crate::audit::with_approval(commit).await {
+            (ToolResult::Success(_), approved_by) => {
+                assert_eq!(approved_by, crate::audit::current_user())
+            }
+            other => panic!("commit failed: {:?}", other),
+        }
+        assert_eq!(commit_count(&dir), "2");
+        assert!(GitHelper::status_in(&dir).unwrap().trim().is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
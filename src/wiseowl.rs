@@ -15,6 +15,11 @@ impl WiseOwl {
         Ok(owl)
     }
 
+    /// Path of one of the memory files, e.g. `TODO.md`
+    pub fn path(&self, file: &str) -> PathBuf {
+        self.root.join(file)
+    }
+
     async fn create_structure(&self) -> Result<(), Box<dyn std::error::Error>> {
         // Create TODO.md if not exists
         let todo_file = self.root.join("TODO.md");
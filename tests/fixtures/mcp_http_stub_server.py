@@ -0,0 +1,156 @@
+#!/usr/bin/env python3
+"""HTTP stand-in for the mcp.rs transport tests, answering with the same
+handler as mcp_stub_server.py. Prints the port it listens on, then serves:
+
+  /mcp       Streamable HTTP. `initialize` opens a session (Mcp-Session-Id);
+             later requests need that header and MCP-Protocol-Version. An
+             unknown session gets 404, DELETE ends the session.
+  /sse       Legacy HTTP+SSE. The stream announces /messages?session_id=...,
+             and POSTs there are answered on the stream.
+
+Flags:
+  --token T           require "Authorization: Bearer T"
+  --stream-responses  answer /mcp POSTs with text/event-stream
+  --drop-after N      close each /sse stream after N messages
+"""
+import json
+import os
+import queue
+import sys
+import uuid
+from http.server import BaseHTTPRequestHandler, ThreadingHTTPServer
+from urllib.parse import parse_qs, urlparse
+
+sys.path.insert(0, os.path.dirname(os.path.abspath(__file__)))
+import mcp_stub_server as stub  # noqa: E402
+
+
+def flag(name, default=None):
+    if name in sys.argv:
+        return sys.argv[sys.argv.index(name) + 1]
+    return default
+
+
+token = flag("--token")
+stream_responses = "--stream-responses" in sys.argv
+drop_after = int(flag("--drop-after", "0"))
+
+sessions = set()
+sse_queues = {}
+
+
+class Handler(BaseHTTPRequestHandler):
+    def log_message(self, *args):
+        pass
+
+    def authorized(self):
+        if token and self.headers.get("Authorization") != "Bearer " + token:
+            self.send_error(401)
+            return False
+        return True
+
+    def read_message(self):
+        length = int(self.headers.get("Content-Length", "0"))
+        return json.loads(self.rfile.read(length))
+
+    def send_json(self, message, headers=None):
+        body = json.dumps(message).encode()
+        self.send_response(200)
+        self.send_header("Content-Type", "application/json")
+        self.send_header("Content-Length", str(len(body)))
+        for name, value in (headers or {}).items():
+            self.send_header(name, value)
+        self.end_headers()
+        self.wfile.write(body)
+
+    def send_events(self, messages, headers=None):
+        self.send_response(200)
+        self.send_header("Content-Type", "text/event-stream")
+        for name, value in (headers or {}).items():
+            self.send_header(name, value)
+        self.end_headers()
+        for message in messages:
+            self.wfile.write(b"event: message\ndata: " + json.dumps(message).encode() + b"\n\n")
+            self.wfile.flush()
+
+    def accepted(self):
+        self.send_response(202)
+        self.send_header("Content-Length", "0")
+        self.end_headers()
+
+    def do_GET(self):
+        if not self.authorized():
+            return
+        if self.path != "/sse":
+            self.send_error(405)
+            return
+        session = uuid.uuid4().hex
+        messages = queue.Queue()
+        sse_queues[session] = messages
+        self.send_response(200)
+        self.send_header("Content-Type", "text/event-stream")
+        self.end_headers()
+        self.wfile.write(("event: endpoint\ndata: /messages?session_id=%s\n\n" % session).encode())
+        self.wfile.flush()
+        sent = 0
+        while not drop_after or sent < drop_after:
+            message = messages.get()
+            self.wfile.write(b"event: message\ndata: " + json.dumps(message).encode() + b"\n\n")
+            self.wfile.flush()
+            sent += 1
+        del sse_queues[session]
+
+    def do_POST(self):
+        if not self.authorized():
+            return
+        url = urlparse(self.path)
+        if url.path == "/messages":
+            session = parse_qs(url.query).get("session_id", [""])[0]
+            if session not in sse_queues:
+                self.send_error(404)
+                return
+            response = stub.handle(self.read_message())
+            if response is not None:
+                sse_queues[session].put(response)
+            self.accepted()
+            return
+        if url.path != "/mcp":
+            self.send_error(404)
+            return
+
+        message = self.read_message()
+        headers = {}
+        if message.get("method") == "initialize":
+            session = uuid.uuid4().hex
+            sessions.add(session)
+            headers["Mcp-Session-Id"] = session
+        else:
+            session = self.headers.get("Mcp-Session-Id")
+            if session is None or not self.headers.get("MCP-Protocol-Version"):
+                self.send_error(400)
+                return
+            if session not in sessions:
+                self.send_error(404)
+                return
+
+        response = stub.handle(message)
+        if response is None:
+            self.accepted()
+        elif stream_responses:
+            log = {"jsonrpc": "2.0", "method": "notifications/message",
+                   "params": {"level": "info", "data": "working"}}
+            self.send_events([log, response], headers)
+        else:
+            self.send_json(response, headers)
+
+    def do_DELETE(self):
+        sessions.discard(self.headers.get("Mcp-Session-Id"))
+        self.send_response(200)
+        self.send_header("Content-Length", "0")
+        self.end_headers()
+
+
+server = ThreadingHTTPServer(("127.0.0.1", 0), Handler)
+server.daemon_threads = True
+print(server.server_address[1], flush=True)
+server.serve_forever()
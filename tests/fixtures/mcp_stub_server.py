@@ -0,0 +1,216 @@
+#!/usr/bin/env python3
+"""Minimal stdio MCP server used by the mcp.rs tests.
+
+Follows the lifecycle: every request before `initialize` and
+`notifications/initialized` is rejected. `--protocol VERSION` makes it answer
+`initialize` with that version regardless of what the client asked for.
+`--crash-on TOOL` makes calling that tool log to stderr and exit with status 3.
+
+Tools:
+  counter - returns how many times it has been called in this process
+  echo    - returns its "text" argument
+  environment - returns the working directory and the "name" variable
+  work    - reports two progress steps and logs at debug and error, then
+            returns the level set with logging/setLevel and the request ids
+            cancelled so far; with "hang" it never answers
+  summarize - asks the client to summarize "text" with sampling/createMessage
+
+Resources (listed in two pages): stub://notes.txt (text), stub://logo.png
+(blob), plus the template stub://greeting/{name}. Prompts: review <code> [focus].
+"""
+import json
+import os
+import sys
+
+SUPPORTED = ["2025-06-18", "2025-03-26", "2024-11-05"]
+forced_version = None
+if "--protocol" in sys.argv:
+    forced_version = sys.argv[sys.argv.index("--protocol") + 1]
+
+crash_on = None
+if "--crash-on" in sys.argv:
+    crash_on = sys.argv[sys.argv.index("--crash-on") + 1]
+
+calls = 0
+initialized = False
+log_level = None
+cancelled = []
+client_capabilities = {}
+
+
+def emit(message):
+    """Send a notification ahead of a response; only the stdio loop can"""
+
+
+def receive():
+    """The next message from the client; only the stdio loop can"""
+    return None
+
+
+def notify(method, params):
+    emit({"jsonrpc": "2.0", "method": method, "params": params})
+
+
+def reply(id, result=None, error=None):
+    message = {"jsonrpc": "2.0", "id": id}
+    if error is not None:
+        message["error"] = error
+    else:
+        message["result"] = result
+    return message
+
+
+def text(value):
+    return {"content": [{"type": "text", "text": value}]}
+
+
+def handle(request):
+    """Answer one JSON-RPC message; None for notifications"""
+    global calls, initialized, log_level, client_capabilities
+    method = request.get("method")
+    id = request.get("id")
+    params = request.get("params") or {}
+
+    if method == "notifications/initialized":
+        initialized = True
+        return None
+    if method == "notifications/cancelled":
+        cancelled.append(params.get("requestId"))
+        return None
+    if id is None:
+        return None
+    if method == "initialize":
+        client_capabilities = params.get("capabilities") or {}
+        requested = params.get("protocolVersion")
+        version = forced_version or (requested if requested in SUPPORTED else SUPPORTED[0])
+        return reply(id, {
+            "protocolVersion": version,
+            "capabilities": {"tools": {"listChanged": False},
+                             "resources": {}, "prompts": {}, "logging": {}},
+            "serverInfo": {"name": "stub-server", "version": "1.0.0"},
+            "instructions": "Test fixture",
+        })
+    elif method == "ping":
+        return reply(id, {})
+    elif not initialized:
+        return reply(id, error={"code": -32002, "message": "Server not initialized"})
+    elif method == "tools/list":
+        return reply(id, {"tools": [
+            {"name": "counter", "description": "Count calls",
+             "inputSchema": {"type": "object", "properties": {}}},
+            {"name": "echo", "description": "Echo text",
+             "inputSchema": {"type": "object",
+                             "properties": {"text": {"type": "string"}},
+                             "required": ["text"]}},
+            {"name": "environment", "description": "Show cwd and a variable",
+             "inputSchema": {"type": "object",
+                             "properties": {"name": {"type": "string"}}}},
+            {"name": "work", "description": "Report progress and log",
+             "inputSchema": {"type": "object",
+                             "properties": {"hang": {"type": "boolean"}}}},
+            {"name": "summarize", "description": "Summarize with the client's model",
+             "inputSchema": {"type": "object",
+                             "properties": {"text": {"type": "string"}},
+                             "required": ["text"]}},
+        ]})
+    elif method == "logging/setLevel":
+        log_level = params.get("level")
+        return reply(id, {})
+    elif method == "resources/list":
+        if params.get("cursor") == "page2":
+            return reply(id, {"resources": [
+                {"uri": "stub://logo.png", "name": "logo", "mimeType": "image/png"}]})
+        else:
+            return reply(id, {"resources": [
+                {"uri": "stub://notes.txt", "name": "notes", "mimeType": "text/plain"}],
+                "nextCursor": "page2"})
+    elif method == "resources/templates/list":
+        return reply(id, {"resourceTemplates": [
+            {"uriTemplate": "stub://greeting/{name}", "name": "greeting"}]})
+    elif method == "resources/read":
+        uri = params.get("uri", "")
+        if uri == "stub://notes.txt":
+            return reply(id, {"contents": [{"uri": uri, "text": "remember the milk"}]})
+        elif uri == "stub://logo.png":
+            return reply(id, {"contents": [{"uri": uri, "mimeType": "image/png", "blob": "iVBORw0K"}]})
+        elif uri.startswith("stub://greeting/"):
+            name = uri[len("stub://greeting/"):]
+            return reply(id, {"contents": [{"uri": uri, "text": "Hello, %s!" % name}]})
+        else:
+            return reply(id, error={"code": -32002, "message": "Resource not found: %s" % uri})
+    elif method == "prompts/list":
+        return reply(id, {"prompts": [{"name": "review", "description": "Review code",
+                                "arguments": [{"name": "code", "required": True},
+                                              {"name": "focus"}]}]})
+    elif method == "prompts/get":
+        arguments = params.get("arguments") or {}
+        if "code" not in arguments:
+            return reply(id, error={"code": -32602, "message": "Missing code"})
+        else:
+            return reply(id, {"messages": [{"role": "user", "content": {
+                "type": "text", "text": "Review this code:\n" + arguments["code"]}}]})
+    elif method == "tools/call":
+        name = params.get("name")
+        arguments = params.get("arguments") or {}
+        if name == crash_on:
+            sys.stderr.write("fatal: simulated crash in %s\n" % name)
+            sys.stderr.flush()
+            sys.exit(3)
+        if name == "counter":
+            calls += 1
+            return reply(id, text(str(calls)))
+        elif name == "environment":
+            value = os.environ.get(arguments.get("name", ""), "")
+            return reply(id, text("%s|%s" % (os.getcwd(), value)))
+        elif name == "work":
+            token = (params.get("_meta") or {}).get("progressToken")
+            for step in (1, 2):
+                if token is not None:
+                    notify("notifications/progress", {"progressToken": token, "progress": step,
+                                                      "total": 2, "message": "step %d" % step})
+            notify("notifications/message", {"level": "debug", "data": "noisy detail"})
+            notify("notifications/message", {"level": "error", "logger": "work",
+                                             "data": {"problem": "disk almost full"}})
+            if arguments.get("hang"):
+                return None
+            return reply(id, text(json.dumps({"level": log_level, "cancelled": cancelled})))
+        elif name == "summarize":
+            if "sampling" not in client_capabilities:
+                return reply(id, dict(text("client does not support sampling"), isError=True))
+            emit({"jsonrpc": "2.0", "id": "sample-1", "method": "sampling/createMessage",
+                  "params": {"systemPrompt": "You summarize.", "maxTokens": 50,
+                             "messages": [{"role": "user", "content": {
+                                 "type": "text", "text": arguments.get("text", "")}}]}})
+            answer = receive()
+            while answer is not None and answer.get("id") != "sample-1":
+                answer = receive()
+            if answer is None or "error" in answer:
+                error = (answer or {}).get("error", {}).get("message", "no answer")
+                return reply(id, dict(text("sampling failed: " + error), isError=True))
+            result = answer["result"]
+            return reply(id, text("%s (%s)" % (result["content"]["text"], result["model"])))
+        elif name == "echo":
+            return reply(id, text(arguments.get("text", "")))
+        else:
+            return reply(id, error={"code": -32602, "message": "Unknown tool: %s" % name})
+    else:
+        return reply(id, error={"code": -32601, "message": "Method not found"})
+
+
+def write(message):
+    sys.stdout.write(json.dumps(message) + "\n")
+    sys.stdout.flush()
+
+
+if __name__ == "__main__":
+    emit = write
+    receive = lambda: json.loads(sys.stdin.readline() or "null")  # noqa: E731
+    sys.stderr.write("stub-server starting\n")
+    sys.stderr.flush()
+    for line in sys.stdin:
+        try:
+            response = handle(json.loads(line))
+        except ValueError:
+            continue
+        if response is not None:
+            write(response)